@@ -0,0 +1,321 @@
+//! Read-through cache of databases, objects and columns, shared by the sessions of a process.
+//!
+//! Entries are valid for one version of the catalog, i.e., `my_catalog.version`, which every DDL
+//! transaction increments. Sessions check the version at the start of each statement, which
+//! catches changes made by other processes, and invalidate the database they changed right after
+//! committing.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use anyhow::Result;
+use sea_orm::{ConnectionTrait, EntityTrait};
+
+use crate::entities::prelude::*;
+use crate::entities::*;
+
+#[derive(Default)]
+pub struct Cache {
+    state: Mutex<State>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+#[derive(Default)]
+struct State {
+    /// Version of the catalog the entries were read at, if known.
+    version: Option<i32>,
+    /// Incremented by every invalidation, so that values read before are not cached after it.
+    generation: u64,
+    databases: HashMap<i32, my_database::Model>,
+    database_ids: HashMap<String, i32>,
+    /// Keyed by database id and name.
+    schemas: HashMap<(i32, String), my_schema::Model>,
+    objects: HashMap<i32, my_object::Model>,
+    /// Keyed by schema id, type and name.
+    object_ids: HashMap<(i32, String, String), i32>,
+    /// All objects of a database, ordered by name.
+    listings: HashMap<i32, Vec<my_object::Model>>,
+    /// Columns of a table, ordered by id, with the id of its database.
+    columns: HashMap<i32, (i32, Vec<my_column::Model>)>,
+}
+
+impl State {
+    fn clear(&mut self) {
+        self.databases.clear();
+        self.database_ids.clear();
+        self.schemas.clear();
+        self.objects.clear();
+        self.object_ids.clear();
+        self.listings.clear();
+        self.columns.clear();
+    }
+
+    fn insert_object(&mut self, object: &my_object::Model) {
+        let key = (object.schema_id, object.r#type.clone(), object.name.clone());
+        self.object_ids.insert(key, object.id);
+        self.objects.insert(object.id, object.clone());
+    }
+}
+
+/// What `SHOW CACHE` tells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl Cache {
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Drop all entries if the catalog has been changed since they were read.
+    pub async fn check_version(&self, conn: &impl ConnectionTrait) -> Result<()> {
+        let version = catalog_version(conn).await?;
+
+        let mut state = self.state.lock().unwrap();
+        if state.version != Some(version) {
+            state.clear();
+            state.generation += 1;
+            state.version = Some(version);
+        }
+        Ok(())
+    }
+
+    /// Drop the entries of a database changed by a transaction, committed at `version`. If other
+    /// transactions were committed in between, drop everything.
+    pub fn invalidate(&self, db_id: i32, version: i32) {
+        let mut guard = self.state.lock().unwrap();
+        let state = &mut *guard;
+        state.generation += 1;
+
+        if state.version != Some(version - 1) {
+            state.clear();
+        } else {
+            state.schemas.retain(|(id, _), _| *id != db_id);
+            let objects = &state.objects;
+            (state.object_ids)
+                .retain(|_, id| objects.get(id).is_some_and(|o| o.database_id != db_id));
+            state.objects.retain(|_, o| o.database_id != db_id);
+            state.listings.remove(&db_id);
+            state.columns.retain(|_, (id, _)| *id != db_id);
+        }
+        state.version = Some(version);
+    }
+
+    pub async fn database(
+        &self,
+        name: &str,
+        load: impl Future<Output = Result<Option<my_database::Model>>>,
+    ) -> Result<Option<my_database::Model>> {
+        self.read_through(
+            |state| {
+                let id = state.database_ids.get(name)?;
+                state.databases.get(id).cloned().map(Some)
+            },
+            load,
+            |state, db| {
+                if let Some(db) = db {
+                    state.database_ids.insert(db.name.clone(), db.id);
+                    state.databases.insert(db.id, db.clone());
+                }
+            },
+        )
+        .await
+    }
+
+    pub async fn schema(
+        &self,
+        db_id: i32,
+        name: &str,
+        load: impl Future<Output = Result<Option<my_schema::Model>>>,
+    ) -> Result<Option<my_schema::Model>> {
+        self.read_through(
+            |state| {
+                let schema = state.schemas.get(&(db_id, name.to_owned()))?;
+                Some(Some(schema.clone()))
+            },
+            load,
+            |state, schema| {
+                if let Some(schema) = schema {
+                    let key = (schema.database_id, schema.name.clone());
+                    state.schemas.insert(key, schema.clone());
+                }
+            },
+        )
+        .await
+    }
+
+    /// Objects of the given types with the given names, in no particular order. Those not cached
+    /// are loaded at once by `load`, given their names.
+    pub async fn objects_by_name<F, Fut>(
+        &self,
+        schema_id: i32,
+        types: &[&str],
+        names: &[String],
+        load: F,
+    ) -> Result<Vec<my_object::Model>>
+    where
+        F: FnOnce(Vec<String>) -> Fut,
+        Fut: Future<Output = Result<Vec<my_object::Model>>>,
+    {
+        let (mut found, mut missing) = (Vec::new(), Vec::new());
+        let generation = {
+            let state = self.state.lock().unwrap();
+            for name in names {
+                let object = types.iter().find_map(|t| {
+                    let id = state
+                        .object_ids
+                        .get(&(schema_id, t.to_string(), name.clone()))?;
+                    state.objects.get(id)
+                });
+                match object {
+                    Some(object) => found.push(object.clone()),
+                    None => missing.push(name.clone()),
+                }
+            }
+            state.generation
+        };
+
+        self.hits.fetch_add(found.len() as u64, Ordering::Relaxed);
+        if missing.is_empty() {
+            return Ok(found);
+        }
+        self.misses
+            .fetch_add(missing.len() as u64, Ordering::Relaxed);
+        let loaded = load(missing).await?;
+
+        let mut state = self.state.lock().unwrap();
+        if state.generation == generation {
+            for object in &loaded {
+                state.insert_object(object);
+            }
+        }
+        found.extend(loaded);
+        Ok(found)
+    }
+
+    /// All objects of a database, ordered by name.
+    pub async fn objects(
+        &self,
+        db_id: i32,
+        load: impl Future<Output = Result<Vec<my_object::Model>>>,
+    ) -> Result<Vec<my_object::Model>> {
+        self.read_through(
+            |state| state.listings.get(&db_id).cloned(),
+            load,
+            |state, objects| {
+                for object in objects {
+                    state.insert_object(object);
+                }
+                state.listings.insert(db_id, objects.clone());
+            },
+        )
+        .await
+    }
+
+    /// Columns of a table, ordered by id.
+    pub async fn columns(
+        &self,
+        table: &my_object::Model,
+        load: impl Future<Output = Result<Vec<my_column::Model>>>,
+    ) -> Result<Vec<my_column::Model>> {
+        self.read_through(
+            |state| state.columns.get(&table.id).map(|(_, c)| c.clone()),
+            load,
+            |state, columns| {
+                let entry = (table.database_id, columns.clone());
+                state.columns.insert(table.id, entry);
+            },
+        )
+        .await
+    }
+
+    async fn read_through<T>(
+        &self,
+        get: impl FnOnce(&State) -> Option<T>,
+        load: impl Future<Output = Result<T>>,
+        insert: impl FnOnce(&mut State, &T),
+    ) -> Result<T> {
+        let generation = {
+            let state = self.state.lock().unwrap();
+            if let Some(value) = get(&state) {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(value);
+            }
+            state.generation
+        };
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let value = load.await?;
+
+        let mut state = self.state.lock().unwrap();
+        // Otherwise the value may be older than the invalidation.
+        if state.generation == generation {
+            insert(&mut state, &value);
+        }
+        Ok(value)
+    }
+}
+
+pub async fn catalog_version(conn: &impl ConnectionTrait) -> Result<i32> {
+    let catalog = MyCatalog::find_by_id(1).one(conn).await?;
+    Ok(catalog.map_or(0, |c| c.version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::Catalog;
+    use crate::metastore;
+
+    #[tokio::test]
+    async fn test_cache() {
+        let meta = metastore::connect(metastore::MEMORY_URL).await.unwrap();
+        let catalog = Catalog::with_cache(meta.clone());
+        // Another process writing to the same metastore.
+        let other = Catalog::new(meta);
+
+        let mut session = catalog.session("test");
+        session
+            .execute("CREATE DATABASE shop; USE shop; CREATE TABLE users (id INT PRIMARY KEY)")
+            .await
+            .unwrap();
+        let cache = catalog.cache().unwrap();
+        let stats = cache.stats();
+
+        let mut columns = Vec::new();
+        for sql in [
+            "EXPLAIN users",
+            "EXPLAIN users",
+            "ALTER TABLE users ADD COLUMN name TEXT",
+            "EXPLAIN users",
+        ] {
+            let stmt = crate::parser::parse(sql).unwrap().remove(0);
+            if let Some(result) = session.handle(stmt).await.unwrap() {
+                columns.push(result.rows.len());
+            }
+        }
+        let mut other = other.session("other");
+        other
+            .execute("USE shop; ALTER TABLE users ADD COLUMN email TEXT")
+            .await
+            .unwrap();
+        let stmt = crate::parser::parse("EXPLAIN users").unwrap().remove(0);
+        columns.push(session.handle(stmt).await.unwrap().unwrap().rows.len());
+
+        assert_eq!(columns, [1, 1, 2, 3]);
+        // Schema, object and columns are read once for each version of the table.
+        let stats = CacheStats {
+            hits: cache.stats().hits - stats.hits,
+            misses: cache.stats().misses - stats.misses,
+        };
+        assert_eq!(stats, CacheStats { hits: 4, misses: 9 });
+    }
+}
@@ -0,0 +1,631 @@
+//! State shared by all sessions of a process: the metastore connection pool, the registry of
+//! sessions, the subscribers to changes and the optional [`Cache`].
+//!
+//! Concurrent DDL is safe on every backend thanks to transactions and constraints. On SQLite,
+//! writers are additionally serialised by [`Catalog::write_lock`], as two write transactions on
+//! different connections may fail with `SQLITE_BUSY` instead of waiting for each other. Writers of
+//! other processes are waited for by the busy timeout of the connections, and statements failing
+//! with `SQLITE_BUSY` nevertheless are retried by the session.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{bail, Context, Result};
+use futures_util::Stream;
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    RuntimeErr,
+};
+use sqlparser::ast;
+use tokio::sync::broadcast;
+
+use crate::cache::Cache;
+use crate::entities::prelude::*;
+use crate::entities::*;
+use crate::error::NotFound;
+use crate::event::CatalogEvent;
+use crate::session::Session;
+
+/// Schema created with every database, and the default search path.
+pub const DEFAULT_SCHEMA: &str = "public";
+
+/// Name of an object as shown to users: qualified by its schema, unless it's the default one.
+pub fn display_name(schema: &str, name: &str) -> String {
+    match schema {
+        DEFAULT_SCHEMA => name.to_owned(),
+        _ => format!("{schema}.{name}"),
+    }
+}
+
+/// How an object type is called in messages, e.g., `materialized view`.
+pub fn object_kind(object_type: &str) -> &str {
+    match object_type {
+        "materialized_view" => "materialized view",
+        other => other,
+    }
+}
+
+/// Events kept for subscribers that are behind, before they get [`CatalogEvent::Lagged`].
+const EVENT_CAPACITY: usize = 1024;
+
+#[derive(Clone)]
+pub struct Catalog {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    meta: DatabaseConnection,
+    sessions: Mutex<Sessions>,
+    write_lock: tokio::sync::Mutex<()>,
+    events: broadcast::Sender<CatalogEvent>,
+    cache: Option<Cache>,
+}
+
+#[derive(Default)]
+struct Sessions {
+    next_id: u32,
+    sessions: BTreeMap<u32, SessionInfo>,
+}
+
+/// Where names without a database or schema are looked up: the current database of a session and
+/// its search path.
+#[derive(Debug, Clone, Copy)]
+pub struct Scope<'a> {
+    pub db: Option<&'a my_database::Model>,
+    pub search_path: &'a [String],
+}
+
+/// The existing schemas an object name may refer to, in the order they are searched.
+#[derive(Debug)]
+pub struct Candidates {
+    pub db: my_database::Model,
+    pub schemas: Vec<my_schema::Model>,
+    /// The last part of the name.
+    pub name: String,
+}
+
+/// Where an object is, or is to be created.
+#[derive(Debug, Clone)]
+pub struct QualifiedName {
+    pub db: my_database::Model,
+    pub schema: my_schema::Model,
+    pub name: String,
+}
+
+/// What `SHOW SESSIONS` tells about a session.
+#[derive(Debug, Clone)]
+pub struct SessionInfo {
+    pub id: u32,
+    /// Where the session comes from, e.g., `repl` or the address of a client.
+    pub client: String,
+    pub database: Option<String>,
+}
+
+impl Catalog {
+    pub fn new(meta: DatabaseConnection) -> Self {
+        Self::build(meta, None)
+    }
+
+    /// Also keep what sessions read from the catalog in memory.
+    pub fn with_cache(meta: DatabaseConnection) -> Self {
+        Self::build(meta, Some(Cache::default()))
+    }
+
+    fn build(meta: DatabaseConnection, cache: Option<Cache>) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                meta,
+                sessions: Default::default(),
+                write_lock: Default::default(),
+                events: broadcast::channel(EVENT_CAPACITY).0,
+                cache,
+            }),
+        }
+    }
+
+    pub fn meta(&self) -> &DatabaseConnection {
+        &self.inner.meta
+    }
+
+    /// Start a session, which is registered until dropped.
+    pub fn session(&self, client: impl Into<String>) -> Session {
+        let mut sessions = self.inner.sessions.lock().unwrap();
+        sessions.next_id += 1;
+        let id = sessions.next_id;
+        sessions.sessions.insert(
+            id,
+            SessionInfo {
+                id,
+                client: client.into(),
+                database: None,
+            },
+        );
+
+        Session::new(self.clone(), id)
+    }
+
+    /// All live sessions, ordered by id.
+    pub fn sessions(&self) -> Vec<SessionInfo> {
+        let sessions = self.inner.sessions.lock().unwrap();
+        sessions.sessions.values().cloned().collect()
+    }
+
+    pub(crate) fn set_session_database(&self, id: u32, database: Option<String>) {
+        let mut sessions = self.inner.sessions.lock().unwrap();
+        if let Some(session) = sessions.sessions.get_mut(&id) {
+            session.database = database;
+        }
+    }
+
+    pub(crate) fn unregister_session(&self, id: u32) {
+        self.inner.sessions.lock().unwrap().sessions.remove(&id);
+    }
+
+    /// Changes committed by any session from now on, in the order of commits.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn subscribe(&self) -> impl Stream<Item = CatalogEvent> + Send + 'static {
+        let receiver = self.inner.events.subscribe();
+
+        futures_util::stream::unfold(receiver, |mut receiver| async move {
+            let event = match receiver.recv().await {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(missed)) => CatalogEvent::Lagged { missed },
+                // The catalog is gone.
+                Err(broadcast::error::RecvError::Closed) => return None,
+            };
+            Some((event, receiver))
+        })
+    }
+
+    /// To be called once a transaction changing the database `db_id` has been committed, bumping
+    /// the catalog to `version`. Invalidates the cache and notifies subscribers.
+    pub(crate) fn committed(
+        &self,
+        db_id: i32,
+        version: i32,
+        events: impl IntoIterator<Item = CatalogEvent>,
+    ) {
+        if let Some(cache) = self.cache() {
+            cache.invalidate(db_id, version);
+        }
+        for event in events {
+            // Fails only if there's no subscriber.
+            let _ = self.inner.events.send(event);
+        }
+    }
+
+    pub fn cache(&self) -> Option<&Cache> {
+        self.inner.cache.as_ref()
+    }
+
+    // Lookups going through the cache if enabled. `conn` is the metastore or a transaction on it.
+
+    pub async fn find_database(
+        &self,
+        conn: &impl ConnectionTrait,
+        name: &str,
+    ) -> Result<Option<my_database::Model>> {
+        let load = async {
+            let db = MyDatabase::find()
+                .filter(my_database::Column::Name.eq(name))
+                .one(conn)
+                .await?;
+            Ok(db)
+        };
+
+        match self.cache() {
+            Some(cache) => cache.database(name, load).await,
+            None => load.await,
+        }
+    }
+
+    pub async fn find_schema(
+        &self,
+        conn: &impl ConnectionTrait,
+        db_id: i32,
+        name: &str,
+    ) -> Result<Option<my_schema::Model>> {
+        let load = async {
+            let schema = MySchema::find()
+                .filter(
+                    (my_schema::Column::DatabaseId.eq(db_id)).and(my_schema::Column::Name.eq(name)),
+                )
+                .one(conn)
+                .await?;
+            Ok(schema)
+        };
+
+        match self.cache() {
+            Some(cache) => cache.schema(db_id, name, load).await,
+            None => load.await,
+        }
+    }
+
+    /// The object of one of the given types with the given name in a schema.
+    pub async fn find_object(
+        &self,
+        conn: &impl ConnectionTrait,
+        schema_id: i32,
+        types: &[&str],
+        name: &str,
+    ) -> Result<Option<my_object::Model>> {
+        let objects = self
+            .find_objects(conn, schema_id, types, &[name.to_owned()])
+            .await?;
+        Ok(objects.into_iter().next())
+    }
+
+    /// Objects of the given types with the given names in a schema, in no particular order, with a
+    /// single query. Names not found are left out.
+    pub async fn find_objects(
+        &self,
+        conn: &impl ConnectionTrait,
+        schema_id: i32,
+        types: &[&str],
+        names: &[String],
+    ) -> Result<Vec<my_object::Model>> {
+        let load = |names: Vec<String>| async move {
+            let objects = MyObject::find()
+                .filter(
+                    (my_object::Column::SchemaId.eq(schema_id))
+                        .and(my_object::Column::Type.is_in(types.iter().copied()))
+                        .and(my_object::Column::Name.is_in(names)),
+                )
+                .all(conn)
+                .await?;
+            Ok(objects)
+        };
+
+        match self.cache() {
+            Some(cache) => cache.objects_by_name(schema_id, types, names, load).await,
+            None => load(names.to_vec()).await,
+        }
+    }
+
+    /// All objects of a database, ordered by name.
+    pub async fn list_objects(
+        &self,
+        conn: &impl ConnectionTrait,
+        db_id: i32,
+    ) -> Result<Vec<my_object::Model>> {
+        let load = async {
+            let objects = MyObject::find()
+                .filter(my_object::Column::DatabaseId.eq(db_id))
+                .order_by_asc(my_object::Column::Name)
+                .all(conn)
+                .await?;
+            Ok(objects)
+        };
+
+        match self.cache() {
+            Some(cache) => cache.objects(db_id, load).await,
+            None => load.await,
+        }
+    }
+
+    /// Columns of a table, ordered by id.
+    pub async fn columns(
+        &self,
+        conn: &impl ConnectionTrait,
+        table: &my_object::Model,
+    ) -> Result<Vec<my_column::Model>> {
+        let load = async {
+            let columns = MyColumn::find()
+                .filter(my_column::Column::TableId.eq(table.id))
+                .order_by_asc(my_column::Column::Id)
+                .all(conn)
+                .await?;
+            Ok(columns)
+        };
+
+        match self.cache() {
+            Some(cache) => cache.columns(table, load).await,
+            None => load.await,
+        }
+    }
+
+    // Resolution of names of objects, which may be qualified by their schema, or by their database
+    // and schema.
+
+    /// The schemas that may contain the object `name` refers to, in order.
+    pub async fn candidates(
+        &self,
+        conn: &impl ConnectionTrait,
+        scope: Scope<'_>,
+        name: &ast::ObjectName,
+    ) -> Result<Candidates> {
+        self.candidates_memo(conn, scope, name, &mut HashMap::new())
+            .await
+    }
+
+    /// Same as [`Self::candidates`], looking up each schema once for all the names resolved with
+    /// the same `memo`.
+    async fn candidates_memo(
+        &self,
+        conn: &impl ConnectionTrait,
+        scope: Scope<'_>,
+        name: &ast::ObjectName,
+        memo: &mut HashMap<(i32, String), Option<my_schema::Model>>,
+    ) -> Result<Candidates> {
+        let parts = name.0.iter().map(|i| i.value.as_str()).collect::<Vec<_>>();
+        let (db_name, schema_names, object_name) = match parts[..] {
+            [object] => (None, scope.search_path.to_vec(), object),
+            [schema, object] => (None, vec![schema.to_owned()], object),
+            [db, schema, object] => (Some(db), vec![schema.to_owned()], object),
+            _ => bail!("invalid name `{name}`"),
+        };
+
+        let db = match db_name {
+            Some(db_name) => self
+                .find_database(conn, db_name)
+                .await?
+                .with_context(|| NotFound::new("database", db_name))?,
+            None => scope.db.context("no database selected")?.clone(),
+        };
+
+        let mut schemas = Vec::new();
+        for schema_name in schema_names {
+            let key = (db.id, schema_name.clone());
+            let schema = match memo.get(&key) {
+                Some(schema) => schema.clone(),
+                None => {
+                    let schema = self.find_schema(conn, db.id, &schema_name).await?;
+                    memo.insert(key, schema.clone());
+                    schema
+                }
+            };
+            match schema {
+                Some(schema) => schemas.push(schema),
+                // Schemas in the search path may not exist, like in Postgres.
+                None if parts.len() == 1 => {}
+                None => return Err(NotFound::new("schema", schema_name).into()),
+            }
+        }
+
+        Ok(Candidates {
+            db,
+            schemas,
+            name: object_name.to_owned(),
+        })
+    }
+
+    /// Where an object named `name` is to be created: the first existing schema of the search path
+    /// if it's not qualified.
+    pub async fn resolve_new(
+        &self,
+        conn: &impl ConnectionTrait,
+        scope: Scope<'_>,
+        name: &ast::ObjectName,
+    ) -> Result<QualifiedName> {
+        let candidates = self.candidates(conn, scope, name).await?;
+        let schema = candidates
+            .schemas
+            .into_iter()
+            .next()
+            .context("no schema has been selected to create in")?;
+
+        Ok(QualifiedName {
+            db: candidates.db,
+            schema,
+            name: candidates.name,
+        })
+    }
+
+    /// The object of one of the given types `name` refers to, with where it was found.
+    pub async fn resolve_object(
+        &self,
+        conn: &impl ConnectionTrait,
+        scope: Scope<'_>,
+        name: &ast::ObjectName,
+        types: &[&str],
+    ) -> Result<Option<(QualifiedName, my_object::Model)>> {
+        let candidates = self.candidates(conn, scope, name).await?;
+        for schema in candidates.schemas {
+            if let Some(object) = self
+                .find_object(conn, schema.id, types, &candidates.name)
+                .await?
+            {
+                let name = QualifiedName {
+                    db: candidates.db,
+                    schema,
+                    name: candidates.name,
+                };
+                return Ok(Some((name, object)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// The objects of the given types the names refer to, with their schema, or `None` for those
+    /// not found. There's one query per schema and position in the search path, rather than per
+    /// name.
+    pub async fn resolve_objects(
+        &self,
+        conn: &impl ConnectionTrait,
+        scope: Scope<'_>,
+        names: &[ast::ObjectName],
+        types: &[&str],
+    ) -> Result<Vec<Option<(my_schema::Model, my_object::Model)>>> {
+        let mut memo = HashMap::new();
+        let mut candidates = Vec::new();
+        for name in names {
+            candidates.push(self.candidates_memo(conn, scope, name, &mut memo).await?);
+        }
+
+        let mut resolved = vec![None; names.len()];
+        for depth in 0.. {
+            // Names not resolved yet, by the schema to look them up in next.
+            let mut pending = BTreeMap::<i32, (&my_schema::Model, Vec<usize>)>::new();
+            for (i, candidates) in candidates.iter().enumerate() {
+                if let (None, Some(schema)) = (&resolved[i], candidates.schemas.get(depth)) {
+                    pending
+                        .entry(schema.id)
+                        .or_insert((schema, vec![]))
+                        .1
+                        .push(i);
+                }
+            }
+            if pending.is_empty() {
+                break;
+            }
+
+            for (schema_id, (schema, indexes)) in pending {
+                let names = indexes
+                    .iter()
+                    .map(|&i| candidates[i].name.clone())
+                    .collect::<Vec<_>>();
+                let objects = self.find_objects(conn, schema_id, types, &names).await?;
+                for i in indexes {
+                    resolved[i] = objects
+                        .iter()
+                        .find(|o| o.name == candidates[i].name)
+                        .map(|o| (schema.clone(), o.clone()));
+                }
+            }
+        }
+
+        Ok(resolved)
+    }
+
+    /// Held by statements modifying the catalog, so that there's one writer at a time in this
+    /// process.
+    pub async fn write_lock(&self) -> tokio::sync::MutexGuard<'_, ()> {
+        self.inner.write_lock.lock().await
+    }
+}
+
+/// Whether the error is caused by SQLite failing to get a lock in time, so that the statement can
+/// be retried.
+pub fn is_busy(e: &anyhow::Error) -> bool {
+    e.chain().any(|e| {
+        let Some(
+            DbErr::Conn(RuntimeErr::SqlxError(sqlx::Error::Database(e)))
+            | DbErr::Exec(RuntimeErr::SqlxError(sqlx::Error::Database(e)))
+            | DbErr::Query(RuntimeErr::SqlxError(sqlx::Error::Database(e))),
+        ) = e.downcast_ref::<DbErr>()
+        else {
+            return false;
+        };
+
+        // Primary result codes `SQLITE_BUSY` and `SQLITE_LOCKED`, extended codes have more bits.
+        let code = e.code().and_then(|c| c.parse::<i32>().ok()).unwrap_or(0);
+        e.try_downcast_ref::<sqlx::sqlite::SqliteError>().is_some() && matches!(code & 0xff, 5 | 6)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+    use futures_util::{FutureExt, StreamExt};
+    use sea_orm::{EntityTrait, PaginatorTrait};
+
+    use super::*;
+    use crate::metastore;
+    use crate::parser::Statement;
+
+    #[tokio::test]
+    async fn test_show_sessions() {
+        let catalog = Catalog::new(metastore::connect(metastore::MEMORY_URL).await.unwrap());
+
+        let mut repl = catalog.session("repl");
+        repl.execute("CREATE DATABASE shop").await.unwrap();
+        let other = catalog.session("127.0.0.1:5555");
+        let dropped = catalog.session("http");
+        drop(dropped);
+
+        let result = repl.handle(Statement::ShowSessions).await.unwrap().unwrap();
+        drop(other);
+
+        expect![[r#"
+             Id | Client         | Database | Current
+            ----+----------------+----------+---------
+             1  | repl           | shop     | *
+             2  | 127.0.0.1:5555 |          |
+            (2 rows)
+        "#]]
+        .assert_eq(&result.render(Default::default(), false));
+        assert_eq!(catalog.sessions().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe() {
+        let catalog = Catalog::new(metastore::connect(metastore::MEMORY_URL).await.unwrap());
+        let mut events = std::pin::pin!(catalog.subscribe());
+
+        let mut session = catalog.session("test");
+        for sql in [
+            "CREATE DATABASE shop",
+            "USE shop",
+            "CREATE TABLE users (id INT PRIMARY KEY, name TEXT)",
+            "ALTER TABLE users ADD COLUMN email TEXT, ALTER COLUMN name SET NOT NULL",
+            // Rolled back, so nothing is published.
+            "ALTER TABLE users DROP COLUMN email, DROP COLUMN nope",
+            "ALTER TABLE users DROP COLUMN email",
+            "CREATE VIEW names AS SELECT name FROM users",
+            "CREATE OR REPLACE VIEW names AS SELECT id, name FROM users",
+            "CREATE OR REPLACE VIEW names AS SELECT * FROM names",
+            "DROP VIEW names",
+            "DROP TABLE users",
+        ] {
+            let _ = session.execute(sql).await;
+        }
+
+        let mut out = String::new();
+        while let Some(Some(event)) = events.next().now_or_never() {
+            out += &format!("{event}\n");
+        }
+        expect![[r#"
+            database `shop` created
+            table `shop.public.users` created (id INT PRIMARY KEY, name TEXT)
+            column `email TEXT` added to `shop.public.users`
+            column of `shop.public.users` altered to `name TEXT NOT NULL`
+            column `email` dropped from `shop.public.users`
+            view `shop.public.names` created as SELECT name FROM public.users
+            view `shop.public.names` replaced with SELECT id, name FROM public.users
+            view `shop.public.names` dropped
+            table `shop.public.users` dropped
+        "#]]
+        .assert_eq(&out);
+    }
+
+    /// Many sessions of two processes, i.e., with different pools, create tables at once.
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_concurrent_ddl() {
+        let path = std::env::temp_dir().join(format!("concurrent-{}.db", std::process::id()));
+        let url = format!("sqlite:{}?mode=rwc", path.display());
+
+        let first = Catalog::new(metastore::connect(&url).await.unwrap());
+        let second = Catalog::new(metastore::connect(&url).await.unwrap());
+        first
+            .session("setup")
+            .execute("CREATE DATABASE shop")
+            .await
+            .unwrap();
+
+        let mut tasks = Vec::new();
+        for i in 0..8 {
+            let catalog = if i % 2 == 0 { &first } else { &second };
+            let mut session = catalog.session(format!("client {i}"));
+            tasks.push(tokio::spawn(async move {
+                session.execute("USE shop").await?;
+                for j in 0..10 {
+                    let sql = format!("CREATE TABLE t_{i}_{j} (id INT PRIMARY KEY, v TEXT)");
+                    session.execute(&sql).await?;
+                }
+                anyhow::Ok(())
+            }));
+        }
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+
+        let meta = first.meta();
+        assert_eq!(MyObject::find().count(meta).await.unwrap(), 80);
+        assert_eq!(MyTable::find().count(meta).await.unwrap(), 80);
+        assert_eq!(MyColumn::find().count(meta).await.unwrap(), 160);
+
+        first.meta().clone().close().await.unwrap();
+        second.meta().clone().close().await.unwrap();
+        std::fs::remove_file(path).unwrap();
+    }
+}
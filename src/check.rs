@@ -0,0 +1,782 @@
+//! Integrity checks of the catalog, for `CHECK CATALOG [REPAIR]`.
+//!
+//! The schema of the metastore doesn't enforce everything the entities imply, e.g., that a table
+//! has a `my_table` row or that views only depend on objects of their own database. Metastores
+//! written by old versions, or edited by hand, may violate these invariants.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ops::ControlFlow;
+
+use anyhow::Result;
+use sea_orm::sea_query::Expr;
+use sea_orm::ActiveValue::*;
+use sea_orm::{ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter};
+use sqlparser::ast::{visit_relations, ObjectName};
+use sqlparser::dialect::GenericDialect;
+use sqlparser::parser::Parser;
+
+use crate::catalog::{display_name, object_kind, DEFAULT_SCHEMA};
+use crate::entities::prelude::*;
+use crate::entities::*;
+use crate::user_type;
+
+/// A broken invariant.
+#[derive(Debug)]
+pub struct Violation {
+    /// What's broken, e.g., `shop.users` or `my_column #12` if it doesn't belong to an object.
+    pub object: String,
+    pub problem: String,
+    /// How to fix it, if it can be done without losing anything but inconsistent rows.
+    pub repair: Option<Repair>,
+}
+
+/// A safe fix of a [`Violation`].
+#[derive(Debug)]
+pub enum Repair {
+    InsertCatalogVersion,
+    /// Add the missing `my_table` row of a table object, which then has no columns.
+    InsertTable(i32),
+    /// Delete a `my_table` row not belonging to a table, with its columns.
+    DeleteTable(i32),
+    /// Delete a `my_view` row not belonging to a view, with its dependencies.
+    DeleteView(i32),
+    /// Delete a `my_materialized_view` row not belonging to a materialized view.
+    DeleteMaterializedView(i32),
+    /// Delete a `my_sequence` row not belonging to a sequence.
+    DeleteSequence(i32),
+    /// Delete a `my_type` row not belonging to a type, and the references to it.
+    DeleteType(i32),
+    DeleteColumn(i32),
+    SetNotNull(i32),
+    InsertDependency {
+        view_id: i32,
+        object_id: i32,
+    },
+    DeleteDependency {
+        view_id: i32,
+        object_id: i32,
+    },
+}
+
+impl Repair {
+    pub async fn apply(&self, conn: &impl ConnectionTrait) -> Result<()> {
+        match *self {
+            Repair::InsertCatalogVersion => {
+                let catalog = my_catalog::ActiveModel {
+                    id: Set(1),
+                    version: NotSet,
+                };
+                catalog.insert(conn).await?;
+            }
+            Repair::InsertTable(object_id) => {
+                let table = my_table::ActiveModel {
+                    object_id: Set(object_id),
+                };
+                table.insert(conn).await?;
+            }
+            Repair::DeleteTable(object_id) => {
+                MyColumn::delete_many()
+                    .filter(my_column::Column::TableId.eq(object_id))
+                    .exec(conn)
+                    .await?;
+                MyTable::delete_by_id(object_id).exec(conn).await?;
+            }
+            Repair::DeleteView(object_id) => {
+                MyViewDependency::delete_many()
+                    .filter(my_view_dependency::Column::ViewId.eq(object_id))
+                    .exec(conn)
+                    .await?;
+                MyView::delete_by_id(object_id).exec(conn).await?;
+            }
+            Repair::DeleteMaterializedView(object_id) => {
+                MyMaterializedView::delete_by_id(object_id)
+                    .exec(conn)
+                    .await?;
+            }
+            Repair::DeleteSequence(object_id) => {
+                MySequence::delete_by_id(object_id).exec(conn).await?;
+            }
+            Repair::DeleteType(object_id) => {
+                MyColumn::update_many()
+                    .col_expr(my_column::Column::UserTypeId, Expr::value(None::<i32>))
+                    .filter(my_column::Column::UserTypeId.eq(object_id))
+                    .exec(conn)
+                    .await?;
+                MyTypeAttribute::update_many()
+                    .col_expr(
+                        my_type_attribute::Column::UserTypeId,
+                        Expr::value(None::<i32>),
+                    )
+                    .filter(my_type_attribute::Column::UserTypeId.eq(object_id))
+                    .exec(conn)
+                    .await?;
+                MyType::update_many()
+                    .col_expr(my_type::Column::BaseTypeId, Expr::value(None::<i32>))
+                    .filter(my_type::Column::BaseTypeId.eq(object_id))
+                    .exec(conn)
+                    .await?;
+                MyType::delete_by_id(object_id).exec(conn).await?;
+            }
+            Repair::DeleteColumn(id) => {
+                MyColumn::delete_by_id(id).exec(conn).await?;
+            }
+            Repair::SetNotNull(id) => {
+                let column = my_column::ActiveModel {
+                    id: Set(id),
+                    is_nullable: Set(false),
+                    ..Default::default()
+                };
+                column.update(conn).await?;
+            }
+            Repair::InsertDependency { view_id, object_id } => {
+                let dependency = my_view_dependency::ActiveModel {
+                    view_id: Set(view_id),
+                    dependent_object_id: Set(object_id),
+                };
+                dependency.insert(conn).await?;
+            }
+            Repair::DeleteDependency { view_id, object_id } => {
+                MyViewDependency::delete_by_id((view_id, object_id))
+                    .exec(conn)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Everything in the metastore, by id.
+struct Catalog {
+    databases: BTreeMap<i32, my_database::Model>,
+    schemas: BTreeMap<i32, my_schema::Model>,
+    objects: BTreeMap<i32, my_object::Model>,
+    tables: BTreeSet<i32>,
+    views: BTreeMap<i32, my_view::Model>,
+    materialized_views: BTreeSet<i32>,
+    sequences: BTreeMap<i32, my_sequence::Model>,
+    types: BTreeMap<i32, my_type::Model>,
+    columns: Vec<my_column::Model>,
+    dependencies: Vec<my_view_dependency::Model>,
+}
+
+impl Catalog {
+    fn name(&self, object: &my_object::Model) -> String {
+        let name = match self.schemas.get(&object.schema_id) {
+            Some(schema) => display_name(&schema.name, &object.name),
+            None => object.name.clone(),
+        };
+        match self.databases.get(&object.database_id) {
+            Some(db) => format!("{}.{name}", db.name),
+            None => name,
+        }
+    }
+
+    /// The table, view or materialized view a view definition refers to by `name`, as resolved by
+    /// `CREATE VIEW`.
+    fn resolve(&self, view: &my_object::Model, name: &ObjectName) -> Option<&my_object::Model> {
+        let db = self.databases.get(&view.database_id)?;
+        let (db_name, schema_name, name) = match &name.0[..] {
+            [name] => (&db.name, DEFAULT_SCHEMA, name),
+            [schema, name] => (&db.name, schema.value.as_str(), name),
+            [db, schema, name] => (&db.value, schema.value.as_str(), name),
+            _ => return None,
+        };
+        self.objects.values().find(|o| {
+            let schema = self.schemas.get(&o.schema_id);
+            o.name == name.value
+                && ["table", "view", "materialized_view"].contains(&o.r#type.as_str())
+                && schema.is_some_and(|s| s.name == schema_name)
+                && self
+                    .databases
+                    .get(&o.database_id)
+                    .is_some_and(|d| &d.name == db_name)
+        })
+    }
+
+    /// Name of the object, or of the row of `entity` if there's no such object.
+    fn name_of(&self, id: i32, entity: &str) -> String {
+        match self.objects.get(&id) {
+            Some(object) => self.name(object),
+            None => format!("{entity} #{id}"),
+        }
+    }
+}
+
+/// Find all violations, in a stable order.
+pub async fn check_catalog(conn: &impl ConnectionTrait) -> Result<Vec<Violation>> {
+    let catalog = Catalog {
+        databases: by_id(MyDatabase::find().all(conn).await?, |d| d.id),
+        schemas: by_id(MySchema::find().all(conn).await?, |s| s.id),
+        objects: by_id(MyObject::find().all(conn).await?, |o| o.id),
+        tables: MyTable::find()
+            .all(conn)
+            .await?
+            .into_iter()
+            .map(|t| t.object_id)
+            .collect(),
+        views: by_id(MyView::find().all(conn).await?, |v| v.object_id),
+        materialized_views: MyMaterializedView::find()
+            .all(conn)
+            .await?
+            .into_iter()
+            .map(|v| v.object_id)
+            .collect(),
+        sequences: by_id(MySequence::find().all(conn).await?, |s| s.object_id),
+        types: by_id(MyType::find().all(conn).await?, |t| t.object_id),
+        columns: MyColumn::find().all(conn).await?,
+        dependencies: MyViewDependency::find().all(conn).await?,
+    };
+
+    let mut violations = Vec::new();
+    let mut report = |object: String, problem: String, repair: Option<Repair>| {
+        violations.push(Violation {
+            object,
+            problem,
+            repair,
+        })
+    };
+
+    if MyCatalog::find_by_id(1).one(conn).await?.is_none() {
+        report(
+            "my_catalog".to_owned(),
+            "catalog version is missing".to_owned(),
+            Some(Repair::InsertCatalogVersion),
+        );
+    }
+
+    for object in catalog.objects.values() {
+        let name = catalog.name(object);
+        if !catalog.databases.contains_key(&object.database_id) {
+            let problem = format!("database #{} does not exist", object.database_id);
+            report(name.clone(), problem, None);
+        }
+        match catalog.schemas.get(&object.schema_id) {
+            None => {
+                let problem = format!("schema #{} does not exist", object.schema_id);
+                report(name.clone(), problem, None);
+            }
+            Some(schema) if schema.database_id != object.database_id => {
+                let problem = format!("schema `{}` belongs to another database", schema.name);
+                report(name.clone(), problem, None);
+            }
+            Some(_) => {}
+        }
+        match object.r#type.as_str() {
+            "table" if !catalog.tables.contains(&object.id) => report(
+                name,
+                "table has no my_table row".to_owned(),
+                Some(Repair::InsertTable(object.id)),
+            ),
+            "view" if !catalog.views.contains_key(&object.id) => {
+                report(name, "view has no definition".to_owned(), None)
+            }
+            "materialized_view" => {
+                // Its storage is a table, and its definition a view.
+                if !catalog.tables.contains(&object.id) {
+                    report(
+                        name.clone(),
+                        "materialized view has no my_table row".to_owned(),
+                        Some(Repair::InsertTable(object.id)),
+                    );
+                }
+                if !catalog.views.contains_key(&object.id) {
+                    let problem = "materialized view has no definition".to_owned();
+                    report(name.clone(), problem, None);
+                }
+                if !catalog.materialized_views.contains(&object.id) {
+                    let problem = "materialized view has no refresh metadata".to_owned();
+                    report(name, problem, None);
+                }
+            }
+            "sequence" if !catalog.sequences.contains_key(&object.id) => {
+                report(name, "sequence has no my_sequence row".to_owned(), None)
+            }
+            "type" if !catalog.types.contains_key(&object.id) => {
+                report(name, "type has no my_type row".to_owned(), None)
+            }
+            "table" | "view" | "sequence" | "type" => {}
+            other => report(name, format!("unknown object type `{other}`"), None),
+        }
+    }
+
+    for &id in &catalog.tables {
+        match catalog.objects.get(&id) {
+            Some(object) if ["table", "materialized_view"].contains(&object.r#type.as_str()) => {}
+            object => {
+                let problem = match object {
+                    Some(object) => format!("my_table row of a {}", object_kind(&object.r#type)),
+                    None => "my_table row of a missing object".to_owned(),
+                };
+                report(
+                    catalog.name_of(id, "my_table"),
+                    problem,
+                    Some(Repair::DeleteTable(id)),
+                );
+            }
+        }
+    }
+
+    for &id in catalog.views.keys() {
+        match catalog.objects.get(&id) {
+            Some(object) if ["view", "materialized_view"].contains(&object.r#type.as_str()) => {}
+            object => {
+                let problem = match object {
+                    Some(object) => format!("my_view row of a {}", object_kind(&object.r#type)),
+                    None => "my_view row of a missing object".to_owned(),
+                };
+                report(
+                    catalog.name_of(id, "my_view"),
+                    problem,
+                    Some(Repair::DeleteView(id)),
+                );
+            }
+        }
+    }
+
+    for &id in &catalog.materialized_views {
+        match catalog.objects.get(&id) {
+            Some(object) if object.r#type == "materialized_view" => {}
+            object => {
+                let problem = match object {
+                    Some(object) => format!(
+                        "my_materialized_view row of a {}",
+                        object_kind(&object.r#type)
+                    ),
+                    None => "my_materialized_view row of a missing object".to_owned(),
+                };
+                report(
+                    catalog.name_of(id, "my_materialized_view"),
+                    problem,
+                    Some(Repair::DeleteMaterializedView(id)),
+                );
+            }
+        }
+    }
+
+    for (&id, sequence) in &catalog.sequences {
+        let name = catalog.name_of(id, "my_sequence");
+        match catalog.objects.get(&id) {
+            Some(object) if object.r#type == "sequence" => {}
+            object => {
+                let problem = match object {
+                    Some(object) => format!("my_sequence row of a {}", object_kind(&object.r#type)),
+                    None => "my_sequence row of a missing object".to_owned(),
+                };
+                report(name, problem, Some(Repair::DeleteSequence(id)));
+                continue;
+            }
+        }
+
+        let (min, max) = (sequence.min_value, sequence.max_value);
+        if min >= max {
+            let problem = format!("MINVALUE ({min}) is not less than MAXVALUE ({max})");
+            report(name.clone(), problem, None);
+        }
+        if sequence.increment == 0 {
+            report(name.clone(), "INCREMENT is zero".to_owned(), None);
+        }
+        for (what, value) in [
+            ("start", sequence.start_value),
+            ("current", sequence.current_value),
+        ] {
+            if !(min..=max).contains(&value) {
+                let problem = format!("{what} value ({value}) is out of [{min}, {max}]");
+                report(name.clone(), problem, None);
+            }
+        }
+    }
+
+    for (&id, user_type) in &catalog.types {
+        let name = catalog.name_of(id, "my_type");
+        match catalog.objects.get(&id) {
+            Some(object) if object.r#type == "type" => {}
+            object => {
+                let problem = match object {
+                    Some(object) => format!("my_type row of a {}", object_kind(&object.r#type)),
+                    None => "my_type row of a missing object".to_owned(),
+                };
+                report(name, problem, Some(Repair::DeleteType(id)));
+                continue;
+            }
+        }
+
+        match user_type.kind.as_str() {
+            user_type::DOMAIN if user_type.base_type.is_none() => {
+                report(name, "domain has no base type".to_owned(), None)
+            }
+            user_type::ENUM | user_type::COMPOSITE | user_type::DOMAIN => {}
+            other => report(name, format!("unknown type kind `{other}`"), None),
+        }
+    }
+
+    let mut names = HashMap::new();
+    for column in &catalog.columns {
+        let table = catalog.name_of(column.table_id, "my_table");
+        let is_table = |id| {
+            catalog
+                .objects
+                .get(&id)
+                .is_some_and(|o| ["table", "materialized_view"].contains(&o.r#type.as_str()))
+        };
+        if !catalog.tables.contains(&column.table_id) {
+            let problem = match catalog.objects.get(&column.table_id) {
+                // Fixed by inserting the `my_table` row.
+                Some(_) if is_table(column.table_id) => continue,
+                Some(object) => format!(
+                    "column `{}` belongs to a {}",
+                    column.name,
+                    object_kind(&object.r#type)
+                ),
+                None => format!("column `{}` belongs to a missing table", column.name),
+            };
+            report(table, problem, Some(Repair::DeleteColumn(column.id)));
+            continue;
+        }
+        if !is_table(column.table_id) {
+            continue;
+        }
+
+        let name = column.name.to_lowercase();
+        if let Some(first) = names.insert((column.table_id, name), column.id) {
+            let problem = format!("column `{}` duplicates column #{first}", column.name);
+            report(table.clone(), problem, None);
+        }
+        if column.is_primary_key && column.is_nullable {
+            let problem = format!("primary key column `{}` is nullable", column.name);
+            report(table.clone(), problem, Some(Repair::SetNotNull(column.id)));
+        }
+        if column.ast_data_type().is_err() {
+            let problem = format!(
+                "column `{}` has an invalid type `{}`",
+                column.name, column.data_type
+            );
+            report(table.clone(), problem, None);
+        }
+        let user_type = column.user_type_id.and_then(|id| catalog.objects.get(&id));
+        let table_object = catalog.objects.get(&column.table_id);
+        if let (Some(user_type), Some(table_object)) = (user_type, table_object) {
+            if user_type.database_id != table_object.database_id {
+                let problem = format!(
+                    "column `{}` uses type `{}` of another database",
+                    column.name,
+                    catalog.name(user_type)
+                );
+                report(table, problem, None);
+            }
+        }
+    }
+
+    let mut dependencies = BTreeMap::<i32, BTreeSet<i32>>::new();
+    for dependency in &catalog.dependencies {
+        let (view_id, object_id) = (dependency.view_id, dependency.dependent_object_id);
+        let view = catalog.name_of(view_id, "my_view");
+        let repair = Some(Repair::DeleteDependency { view_id, object_id });
+
+        let (Some(view_object), Some(object)) = (
+            catalog.objects.get(&view_id),
+            catalog.objects.get(&object_id),
+        ) else {
+            let problem = format!("depends on missing object #{object_id}");
+            report(view, problem, repair);
+            continue;
+        };
+        if !catalog.views.contains_key(&view_id) {
+            // Reported with its `my_view` row or object.
+            continue;
+        }
+        if view_object.database_id != object.database_id {
+            let problem = format!("depends on `{}` of another database", catalog.name(object));
+            report(view, problem, None);
+        }
+        dependencies.entry(view_id).or_default().insert(object_id);
+    }
+
+    // The dependencies must be the objects referenced by the definition.
+    for (&id, view) in &catalog.views {
+        let is_view =
+            |o: &&my_object::Model| ["view", "materialized_view"].contains(&o.r#type.as_str());
+        let Some(object) = catalog.objects.get(&id).filter(is_view) else {
+            continue;
+        };
+        let name = catalog.name(object);
+
+        let query = Parser::new(&GenericDialect {})
+            .try_with_sql(&view.definition)
+            .and_then(|mut parser| parser.parse_query());
+        let Ok(query) = query else {
+            report(name, "definition is invalid".to_owned(), None);
+            continue;
+        };
+        let mut references = BTreeSet::new();
+        let _ = visit_relations(&query, |r| {
+            references.insert(r.clone());
+            ControlFlow::<()>::Continue(())
+        });
+
+        let recorded = dependencies.get(&id).cloned().unwrap_or_default();
+        let mut expected = BTreeSet::new();
+        for reference in references {
+            match catalog.resolve(object, &reference) {
+                // Reported with the dependencies if recorded, and never created otherwise.
+                Some(found) if found.database_id != object.database_id => {}
+                Some(found) => {
+                    expected.insert(found.id);
+                }
+                None => report(
+                    name.clone(),
+                    format!("references missing object `{reference}`"),
+                    None,
+                ),
+            }
+        }
+
+        for &object_id in expected.difference(&recorded) {
+            let problem = format!(
+                "missing dependency on `{}`",
+                catalog.name(&catalog.objects[&object_id])
+            );
+            let repair = Repair::InsertDependency {
+                view_id: id,
+                object_id,
+            };
+            report(name.clone(), problem, Some(repair));
+        }
+        for &object_id in recorded.difference(&expected) {
+            // Cross-database dependencies are reported above.
+            if catalog.objects[&object_id].database_id != object.database_id {
+                continue;
+            }
+            let problem = format!(
+                "stale dependency on `{}`",
+                catalog.name(&catalog.objects[&object_id])
+            );
+            let repair = Repair::DeleteDependency {
+                view_id: id,
+                object_id,
+            };
+            report(name.clone(), problem, Some(repair));
+        }
+    }
+
+    for cycle in cycles(&dependencies) {
+        let names = cycle
+            .iter()
+            .map(|id| catalog.name_of(*id, "my_view"))
+            .collect::<Vec<_>>();
+        let problem = format!("dependency cycle: {}", names.join(" -> "));
+        report(names[0].clone(), problem, None);
+    }
+
+    Ok(violations)
+}
+
+fn by_id<T>(models: Vec<T>, id: impl Fn(&T) -> i32) -> BTreeMap<i32, T> {
+    models.into_iter().map(|m| (id(&m), m)).collect()
+}
+
+/// Cycles of the dependency graph, each starting and ending with the same view.
+fn cycles(dependencies: &BTreeMap<i32, BTreeSet<i32>>) -> Vec<Vec<i32>> {
+    fn visit(
+        id: i32,
+        dependencies: &BTreeMap<i32, BTreeSet<i32>>,
+        path: &mut Vec<i32>,
+        done: &mut BTreeSet<i32>,
+        cycles: &mut Vec<Vec<i32>>,
+    ) {
+        if let Some(start) = path.iter().position(|&p| p == id) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(id);
+            cycles.push(cycle);
+            return;
+        }
+        if !done.insert(id) {
+            return;
+        }
+
+        path.push(id);
+        for &dependency in dependencies.get(&id).into_iter().flatten() {
+            visit(dependency, dependencies, path, done, cycles);
+        }
+        path.pop();
+    }
+
+    let mut cycles = Vec::new();
+    let mut done = BTreeSet::new();
+    for &id in dependencies.keys() {
+        visit(id, dependencies, &mut Vec::new(), &mut done, &mut cycles);
+    }
+    cycles
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+    use sea_orm::{ConnectionTrait, DatabaseConnection};
+
+    use crate::catalog::Catalog;
+    use crate::metastore;
+    use crate::output::Format;
+    use crate::session::Session;
+
+    async fn query(session: &mut Session, sql: &str) -> String {
+        let stmt = crate::parser::parse(sql).unwrap().remove(0);
+        let result = session.handle(stmt).await.unwrap().unwrap();
+        result.render(Format::Table, false)
+    }
+
+    /// Break the invariants behind the back of the catalog, like an old version or a hand edit.
+    async fn corrupt(meta: &DatabaseConnection) {
+        let users = "(SELECT id FROM my_object WHERE name = 'users')";
+        let orders = "(SELECT id FROM my_object WHERE name = 'orders')";
+        let v = "(SELECT id FROM my_object WHERE name = 'v')";
+        let w = "(SELECT id FROM my_object WHERE name = 'w')";
+        let items = "(SELECT id FROM my_object WHERE name = 'items')";
+        let s = "(SELECT id FROM my_object WHERE name = 's')";
+        let mood = "(SELECT id FROM my_object WHERE name = 'mood')";
+        let color = "(SELECT id FROM my_object WHERE name = 'color')";
+        let shop = "(SELECT id FROM my_database WHERE name = 'shop')";
+        let shop_public = format!("(SELECT id FROM my_schema WHERE database_id = {shop})");
+        let other_public = "(SELECT id FROM my_schema WHERE database_id = \
+            (SELECT id FROM my_database WHERE name = 'other'))";
+        let sql = format!(
+            "PRAGMA foreign_keys = OFF;
+            DELETE FROM my_catalog;
+            INSERT INTO my_object (type, name, database_id, version, schema_id)
+                VALUES ('index', 'idx', {shop}, 1, {shop_public});
+            INSERT INTO my_object (type, name, database_id, version, schema_id)
+                VALUES ('table', 'lost', 42, 1, 42);
+            UPDATE my_object SET schema_id = {other_public} WHERE name = 'orders';
+            DELETE FROM my_table WHERE object_id = {orders};
+            INSERT INTO my_table (object_id) VALUES ({v});
+            INSERT INTO my_view (object_id, definition) VALUES ({users}, 'SELECT 1');
+            UPDATE my_column SET is_nullable = true WHERE table_id = {users} AND name = 'id';
+            INSERT INTO my_column (table_id, name, data_type, is_primary_key, is_nullable)
+                VALUES ({users}, 'NAME', 'TEXT', false, true);
+            INSERT INTO my_column (table_id, name, data_type, is_primary_key, is_nullable)
+                VALUES ({users}, 'age', 'INT(', false, true);
+            INSERT INTO my_column (table_id, name, data_type, is_primary_key, is_nullable)
+                VALUES (1000, 'ghost', 'INT', false, true);
+            DELETE FROM my_view_dependency WHERE view_id = {v};
+            INSERT INTO my_view_dependency (view_id, dependent_object_id) VALUES ({v}, {w});
+            INSERT INTO my_view_dependency (view_id, dependent_object_id) VALUES ({w}, {items});
+            INSERT INTO my_view_dependency (view_id, dependent_object_id) VALUES ({w}, 1000);
+            UPDATE my_sequence SET current_value = 0 WHERE object_id = {s};
+            INSERT INTO my_sequence (object_id, data_type, start_value, increment, min_value,
+                max_value, cycle, current_value) VALUES ({orders}, 'BIGINT', 1, 1, 1, 10, false, 1);
+            INSERT INTO my_object (type, name, database_id, version, schema_id)
+                VALUES ('type', 'lonely', {shop}, 1, {shop_public});
+            UPDATE my_type SET kind = 'range' WHERE object_id = {mood};
+            INSERT INTO my_type (object_id, kind, is_nullable) VALUES ({w}, 'enum', true);
+            UPDATE my_column SET user_type_id = {color} WHERE name = 'feeling';
+            PRAGMA foreign_keys = ON;"
+        );
+        meta.execute_unprepared(&sql).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_check_catalog() {
+        let meta = metastore::connect(metastore::MEMORY_URL).await.unwrap();
+        let mut session = Catalog::new(meta.clone()).session("test");
+        session
+            .execute(
+                "CREATE DATABASE other; USE other; CREATE TABLE items (id INT PRIMARY KEY);
+                CREATE TYPE color AS ENUM ('red');
+                CREATE DATABASE shop; USE shop;
+                CREATE TABLE users (id INT PRIMARY KEY, name TEXT);
+                CREATE TABLE orders (id INT PRIMARY KEY);
+                CREATE VIEW v AS SELECT * FROM users;
+                CREATE VIEW w AS SELECT * FROM v;
+                CREATE SEQUENCE s;
+                CREATE TYPE mood AS ENUM ('sad', 'happy');
+                CREATE TABLE diary (feeling mood)",
+            )
+            .await
+            .unwrap();
+        expect![[r#"
+             Object | Problem | Repair
+            --------+---------+--------
+            (0 rows)
+        "#]]
+        .assert_eq(&query(&mut session, "CHECK CATALOG").await);
+
+        corrupt(&meta).await;
+        expect![[r#"
+             Object         | Problem                                                      | Repair
+            ----------------+--------------------------------------------------------------+--------
+             my_catalog     | catalog version is missing                                   | safe
+             shop.orders    | schema `public` belongs to another database                  | manual
+             shop.orders    | table has no my_table row                                    | safe
+             shop.idx       | unknown object type `index`                                  | manual
+             lost           | database #42 does not exist                                  | manual
+             lost           | schema #42 does not exist                                    | manual
+             lost           | table has no my_table row                                    | safe
+             shop.lonely    | type has no my_type row                                      | manual
+             shop.v         | my_table row of a view                                       | safe
+             shop.users     | my_view row of a table                                       | safe
+             shop.orders    | my_sequence row of a table                                   | safe
+             shop.s         | current value (0) is out of [1, 9223372036854775807]         | manual
+             shop.w         | my_type row of a view                                        | safe
+             shop.mood      | unknown type kind `range`                                    | manual
+             shop.users     | primary key column `id` is nullable                          | safe
+             shop.diary     | column `feeling` uses type `other.color` of another database | manual
+             shop.users     | column `NAME` duplicates column #3                           | manual
+             shop.users     | column `age` has an invalid type `INT(`                      | manual
+             my_table #1000 | column `ghost` belongs to a missing table                    | safe
+             shop.w         | depends on `other.items` of another database                 | manual
+             shop.w         | depends on missing object #1000                              | safe
+             shop.v         | missing dependency on `shop.users`                           | safe
+             shop.v         | stale dependency on `shop.w`                                 | safe
+             shop.v         | dependency cycle: shop.v -> shop.w -> shop.v                 | manual
+            (24 rows)
+        "#]]
+        .assert_eq(&query(&mut session, "CHECK CATALOG").await);
+        expect![[r#"
+             Object         | Problem                                                      | Repair
+            ----------------+--------------------------------------------------------------+----------
+             my_catalog     | catalog version is missing                                   | repaired
+             shop.orders    | schema `public` belongs to another database                  | manual
+             shop.orders    | table has no my_table row                                    | repaired
+             shop.idx       | unknown object type `index`                                  | manual
+             lost           | database #42 does not exist                                  | manual
+             lost           | schema #42 does not exist                                    | manual
+             lost           | table has no my_table row                                    | repaired
+             shop.lonely    | type has no my_type row                                      | manual
+             shop.v         | my_table row of a view                                       | repaired
+             shop.users     | my_view row of a table                                       | repaired
+             shop.orders    | my_sequence row of a table                                   | repaired
+             shop.s         | current value (0) is out of [1, 9223372036854775807]         | manual
+             shop.w         | my_type row of a view                                        | repaired
+             shop.mood      | unknown type kind `range`                                    | manual
+             shop.users     | primary key column `id` is nullable                          | repaired
+             shop.diary     | column `feeling` uses type `other.color` of another database | manual
+             shop.users     | column `NAME` duplicates column #3                           | manual
+             shop.users     | column `age` has an invalid type `INT(`                      | manual
+             my_table #1000 | column `ghost` belongs to a missing table                    | repaired
+             shop.w         | depends on `other.items` of another database                 | manual
+             shop.w         | depends on missing object #1000                              | repaired
+             shop.v         | missing dependency on `shop.users`                           | repaired
+             shop.v         | stale dependency on `shop.w`                                 | repaired
+             shop.v         | dependency cycle: shop.v -> shop.w -> shop.v                 | manual
+            (24 rows)
+        "#]]
+        .assert_eq(&query(&mut session, "CHECK CATALOG REPAIR").await);
+        // Only the manual ones are left.
+        expect![[r#"
+             Object      | Problem                                                      | Repair
+            -------------+--------------------------------------------------------------+--------
+             shop.orders | schema `public` belongs to another database                  | manual
+             shop.idx    | unknown object type `index`                                  | manual
+             lost        | database #42 does not exist                                  | manual
+             lost        | schema #42 does not exist                                    | manual
+             shop.lonely | type has no my_type row                                      | manual
+             shop.s      | current value (0) is out of [1, 9223372036854775807]         | manual
+             shop.mood   | unknown type kind `range`                                    | manual
+             shop.diary  | column `feeling` uses type `other.color` of another database | manual
+             shop.users  | column `NAME` duplicates column #3                           | manual
+             shop.users  | column `age` has an invalid type `INT(`                      | manual
+             shop.w      | depends on `other.items` of another database                 | manual
+            (11 rows)
+        "#]]
+        .assert_eq(&query(&mut session, "CHECK CATALOG").await);
+    }
+}
@@ -0,0 +1,286 @@
+//! Generate SeaORM entity source code for user-defined tables, in the same shape as the output of
+//! `sea-orm-codegen` that lives in `src/entities`.
+
+use std::fmt::Write;
+
+use anyhow::Result;
+use sqlparser::ast::{DataType, TimezoneInfo};
+
+use crate::entities::my_column;
+
+/// Rust type of a column in the generated `Model`.
+struct RustType {
+    ty: String,
+    /// Explicit `column_type` attribute, if the Rust type alone is not enough to recover the SQL
+    /// type.
+    column_type: Option<String>,
+    /// Whether the type implements `Eq`.
+    is_eq: bool,
+}
+
+impl RustType {
+    fn new(ty: &str) -> Self {
+        Self {
+            ty: ty.to_owned(),
+            column_type: None,
+            is_eq: true,
+        }
+    }
+
+    fn float(ty: &str) -> Self {
+        Self {
+            is_eq: false,
+            ..Self::new(ty)
+        }
+    }
+
+    fn custom(data_type: &DataType) -> Self {
+        Self {
+            column_type: Some(format!("custom(\"{data_type}\")")),
+            ..Self::new("String")
+        }
+    }
+
+    fn from_data_type(data_type: &DataType) -> Self {
+        use DataType::*;
+
+        match data_type {
+            Character(_)
+            | Char(_)
+            | CharacterVarying(_)
+            | CharVarying(_)
+            | Varchar(_)
+            | Nvarchar(_)
+            | CharacterLargeObject(_)
+            | CharLargeObject(_)
+            | Clob(_)
+            | Text
+            | String => Self::new("String"),
+            Uuid => Self::new("Uuid"),
+            Binary(_) | Varbinary(_) | Blob(_) | Bytea => Self::new("Vec<u8>"),
+            Numeric(_) | Decimal(_) | BigNumeric(_) | BigDecimal(_) | Dec(_) => {
+                Self::new("Decimal")
+            }
+            TinyInt(_) => Self::new("i8"),
+            UnsignedTinyInt(_) => Self::new("u8"),
+            Int2(_) | SmallInt(_) => Self::new("i16"),
+            UnsignedInt2(_) | UnsignedSmallInt(_) => Self::new("u16"),
+            MediumInt(_) | Int(_) | Int4(_) | Integer(_) => Self::new("i32"),
+            UnsignedMediumInt(_) | UnsignedInt(_) | UnsignedInt4(_) | UnsignedInteger(_) => {
+                Self::new("u32")
+            }
+            BigInt(_) | Int8(_) => Self::new("i64"),
+            UnsignedBigInt(_) | UnsignedInt8(_) => Self::new("u64"),
+            Float(Some(p)) if *p > 24 => Self::float("f64"),
+            Float(_) | Float4 | Real => Self::float("f32"),
+            Float8 | Double | DoublePrecision => Self::float("f64"),
+            Bool | Boolean => Self::new("bool"),
+            Date => Self::new("Date"),
+            Time(..) => Self::new("Time"),
+            Timestamp(_, TimezoneInfo::Tz | TimezoneInfo::WithTimeZone) => {
+                Self::new("DateTimeWithTimeZone")
+            }
+            Datetime(_) | Timestamp(..) => Self::new("DateTime"),
+            JSON => Self::float("Json"), // `serde_json::Value` is not `Eq` either
+            Array(Some(inner)) => {
+                let inner = Self::from_data_type(inner);
+                if inner.column_type.is_some() {
+                    return Self::custom(data_type);
+                }
+                Self {
+                    ty: format!("Vec<{}>", inner.ty),
+                    ..inner
+                }
+            }
+            Array(None) | Interval | Regclass | Enum(_) | Set(_) | Custom(..) => {
+                Self::custom(data_type)
+            }
+        }
+    }
+}
+
+/// Convert an identifier like `UserId` or `user-id` to `user_id`.
+fn to_snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            if prev_lower {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+            prev_lower = false;
+        } else if c.is_ascii_alphanumeric() {
+            snake.push(c);
+            prev_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+        } else {
+            snake.push('_');
+            prev_lower = false;
+        }
+    }
+    if snake.starts_with(|c: char| c.is_ascii_digit()) {
+        snake.insert(0, '_');
+    }
+    snake
+}
+
+fn to_field_name(name: &str) -> String {
+    const KEYWORDS: &[&str] = &[
+        "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern",
+        "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+        "pub", "ref", "return", "static", "struct", "trait", "true", "type", "unsafe", "use",
+        "where", "while", "abstract", "become", "box", "do", "final", "macro", "override", "priv",
+        "try", "typeof", "unsized", "virtual", "yield",
+    ];
+
+    let snake = to_snake_case(name);
+    if KEYWORDS.contains(&snake.as_str()) {
+        format!("r#{snake}")
+    } else {
+        snake
+    }
+}
+
+/// Generate an entity module for table `table_name` with the given columns.
+///
+/// Relationships between user tables are not tracked by the catalog, so `Relation` is always empty.
+pub fn generate_entity(table_name: &str, columns: &[my_column::Model]) -> Result<String> {
+    let mut fields = String::new();
+    let mut is_eq = true;
+
+    for column in columns {
+        let rust_type = RustType::from_data_type(&column.ast_data_type()?);
+        is_eq &= rust_type.is_eq;
+
+        let field_name = to_field_name(&column.name);
+
+        let mut attrs = Vec::new();
+        if column.is_primary_key {
+            // Whether the key is generated is not tracked by the catalog.
+            attrs.push("primary_key".to_owned());
+            attrs.push("auto_increment = false".to_owned());
+        }
+        if field_name.trim_start_matches("r#") != column.name {
+            attrs.push(format!("column_name = \"{}\"", column.name));
+        }
+        if let Some(column_type) = &rust_type.column_type {
+            attrs.push(format!(
+                "column_type = \"{}\"",
+                column_type.escape_default()
+            ));
+        }
+
+        if !attrs.is_empty() {
+            writeln!(fields, "    #[sea_orm({})]", attrs.join(", "))?;
+        }
+        if column.is_nullable {
+            writeln!(fields, "    pub {field_name}: Option<{}>,", rust_type.ty)?;
+        } else {
+            writeln!(fields, "    pub {field_name}: {},", rust_type.ty)?;
+        }
+    }
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        "//! `SeaORM` Entity. Generated by {} {}",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    )?;
+    writeln!(out)?;
+    writeln!(out, "use sea_orm::entity::prelude::*;")?;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "#[derive(Clone, Debug, PartialEq, DeriveEntityModel{})]",
+        if is_eq { ", Eq" } else { "" }
+    )?;
+    writeln!(out, "#[sea_orm(table_name = \"{table_name}\")]")?;
+    writeln!(out, "pub struct Model {{")?;
+    write!(out, "{fields}")?;
+    writeln!(out, "}}")?;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]"
+    )?;
+    writeln!(out, "pub enum Relation {{}}")?;
+    writeln!(out)?;
+    writeln!(out, "impl ActiveModelBehavior for ActiveModel {{}}")?;
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+    use sea_orm::ActiveValue::*;
+    use sqlparser::ast::Statement;
+    use sqlparser::dialect::GenericDialect;
+    use sqlparser::parser::Parser;
+
+    use super::*;
+
+    fn columns(sql: &str) -> Vec<my_column::Model> {
+        let Statement::CreateTable { columns, .. } = Parser::parse_sql(&GenericDialect {}, sql)
+            .unwrap()
+            .remove(0)
+        else {
+            panic!("expect CREATE TABLE")
+        };
+
+        columns
+            .into_iter()
+            .enumerate()
+            .map(|(i, col)| {
+                let model = my_column::ActiveModel {
+                    id: Set(i as i32),
+                    ..my_column::ActiveModel::from_ast(col, 1)
+                };
+                model.try_into().unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_generate_entity() {
+        let columns = columns(
+            "CREATE TABLE users (
+                id BIGINT PRIMARY KEY,
+                name VARCHAR(255) NOT NULL,
+                type TEXT,
+                CreatedAt TIMESTAMP WITH TIME ZONE NOT NULL,
+                score DOUBLE,
+                tags TEXT[],
+                mood mood
+            )",
+        );
+
+        expect![[r#"
+            //! `SeaORM` Entity. Generated by sea-orm-db-catalog-demo 0.1.0
+
+            use sea_orm::entity::prelude::*;
+
+            #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+            #[sea_orm(table_name = "users")]
+            pub struct Model {
+                #[sea_orm(primary_key, auto_increment = false)]
+                pub id: i64,
+                pub name: String,
+                pub r#type: Option<String>,
+                #[sea_orm(column_name = "CreatedAt")]
+                pub created_at: DateTimeWithTimeZone,
+                pub score: Option<f64>,
+                pub tags: Option<Vec<String>>,
+                #[sea_orm(column_type = "custom(\"mood\")")]
+                pub mood: Option<String>,
+            }
+
+            #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+            pub enum Relation {}
+
+            impl ActiveModelBehavior for ActiveModel {}
+        "#]]
+        .assert_eq(&generate_entity("users", &columns).unwrap());
+    }
+}
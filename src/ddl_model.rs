@@ -0,0 +1,379 @@
+//! Property test running random DDL sequences against both a session and a simple reference model
+//! of the catalog, checking that they agree after each statement.
+//!
+//! Names are drawn from small pools, so that statements often collide with existing objects and
+//! fail. On failure, proptest shrinks the sequence to a minimal one.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use proptest::prelude::*;
+use sea_orm::{EntityTrait, PaginatorTrait};
+
+use crate::catalog::Catalog;
+use crate::entities::prelude::*;
+use crate::session::Session;
+use crate::{metastore, parser};
+
+const TABLES: &[&str] = &["t1", "t2", "t3"];
+const VIEWS: &[&str] = &["v1", "v2", "v3"];
+const COLUMNS: &[&str] = &["a", "b", "c"];
+const TYPES: &[&str] = &["INT", "TEXT"];
+
+#[derive(Debug, Clone)]
+enum Op {
+    CreateTable {
+        name: &'static str,
+        /// The first one is the primary key.
+        columns: Vec<(&'static str, &'static str)>,
+    },
+    DropTables(Vec<&'static str>),
+    AddColumn {
+        table: &'static str,
+        column: &'static str,
+        data_type: &'static str,
+    },
+    DropColumn {
+        table: &'static str,
+        column: &'static str,
+    },
+    SetDataType {
+        table: &'static str,
+        column: &'static str,
+        data_type: &'static str,
+    },
+    CreateView {
+        name: &'static str,
+        or_replace: bool,
+        references: Vec<&'static str>,
+    },
+    DropViews(Vec<&'static str>),
+}
+
+impl Op {
+    fn sql(&self) -> String {
+        match self {
+            Op::CreateTable { name, columns } => {
+                let columns = columns
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (column, data_type))| match i {
+                        0 => format!("{column} {data_type} PRIMARY KEY"),
+                        _ => format!("{column} {data_type}"),
+                    })
+                    .collect::<Vec<_>>();
+                format!("CREATE TABLE {name} ({})", columns.join(", "))
+            }
+            Op::DropTables(names) => format!("DROP TABLE {}", names.join(", ")),
+            Op::AddColumn {
+                table,
+                column,
+                data_type,
+            } => format!("ALTER TABLE {table} ADD COLUMN {column} {data_type}"),
+            Op::DropColumn { table, column } => format!("ALTER TABLE {table} DROP COLUMN {column}"),
+            Op::SetDataType {
+                table,
+                column,
+                data_type,
+            } => format!("ALTER TABLE {table} ALTER COLUMN {column} SET DATA TYPE {data_type}"),
+            Op::CreateView {
+                name,
+                or_replace,
+                references,
+            } => format!(
+                "CREATE {}VIEW {name} AS SELECT * FROM {}",
+                if *or_replace { "OR REPLACE " } else { "" },
+                references.join(", ")
+            ),
+            Op::DropViews(names) => format!("DROP VIEW {}", names.join(", ")),
+        }
+    }
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    let table = proptest::sample::select(TABLES);
+    let view = proptest::sample::select(VIEWS);
+    let column = proptest::sample::select(COLUMNS);
+    let data_type = proptest::sample::select(TYPES);
+    let object = proptest::sample::select([TABLES, VIEWS].concat());
+
+    prop_oneof![
+        (
+            table.clone(),
+            proptest::sample::subsequence(COLUMNS, 1..=COLUMNS.len()),
+            proptest::collection::vec(data_type.clone(), COLUMNS.len()),
+        )
+            .prop_map(|(name, columns, types)| Op::CreateTable {
+                name,
+                columns: columns.into_iter().zip(types).collect(),
+            }),
+        proptest::collection::vec(table.clone(), 1..=2).prop_map(Op::DropTables),
+        (table.clone(), column.clone(), data_type.clone()).prop_map(
+            |(table, column, data_type)| Op::AddColumn {
+                table,
+                column,
+                data_type,
+            }
+        ),
+        (table.clone(), column.clone())
+            .prop_map(|(table, column)| Op::DropColumn { table, column }),
+        (table, column, data_type).prop_map(|(table, column, data_type)| Op::SetDataType {
+            table,
+            column,
+            data_type,
+        }),
+        (
+            view.clone(),
+            any::<bool>(),
+            proptest::collection::vec(object, 1..=2)
+        )
+            .prop_map(|(name, or_replace, references)| Op::CreateView {
+                name,
+                or_replace,
+                references,
+            }),
+        proptest::collection::vec(view, 1..=2).prop_map(Op::DropViews),
+    ]
+}
+
+/// What the catalog should contain.
+#[derive(Debug, Clone, Default)]
+struct Model {
+    /// Columns with their types, the first one being the primary key unless dropped.
+    tables: BTreeMap<String, Vec<Column>>,
+    /// Names of the objects each view depends on.
+    views: BTreeMap<String, BTreeSet<String>>,
+}
+
+#[derive(Debug, Clone)]
+struct Column {
+    name: String,
+    data_type: String,
+    is_primary_key: bool,
+}
+
+impl Model {
+    /// Apply the statement, or leave the model unchanged and return `false` if it should fail.
+    fn apply(&mut self, op: &Op) -> bool {
+        let mut next = self.clone();
+        let ok = next.try_apply(op).is_some();
+        if ok {
+            *self = next;
+        }
+        ok
+    }
+
+    fn try_apply(&mut self, op: &Op) -> Option<()> {
+        match op {
+            Op::CreateTable { name, columns } => {
+                if self.tables.contains_key(*name) {
+                    return None;
+                }
+                let columns = columns
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (name, data_type))| Column {
+                        name: name.to_string(),
+                        data_type: data_type.to_string(),
+                        is_primary_key: i == 0,
+                    })
+                    .collect();
+                self.tables.insert(name.to_string(), columns);
+            }
+            Op::DropTables(names) => {
+                for name in names {
+                    self.tables.remove(*name)?;
+                    self.check_unused(name)?;
+                }
+            }
+            Op::AddColumn {
+                table,
+                column,
+                data_type,
+            } => {
+                let columns = self.tables.get_mut(*table)?;
+                if columns.iter().any(|c| c.name == *column) {
+                    return None;
+                }
+                columns.push(Column {
+                    name: column.to_string(),
+                    data_type: data_type.to_string(),
+                    is_primary_key: false,
+                });
+            }
+            Op::DropColumn { table, column } => {
+                let columns = self.tables.get_mut(*table)?;
+                let i = columns.iter().position(|c| c.name == *column)?;
+                columns.remove(i);
+            }
+            Op::SetDataType {
+                table,
+                column,
+                data_type,
+            } => {
+                let columns = self.tables.get_mut(*table)?;
+                let column = columns.iter_mut().find(|c| c.name == *column)?;
+                column.data_type = data_type.to_string();
+            }
+            Op::CreateView {
+                name,
+                or_replace,
+                references,
+            } => {
+                let references = references
+                    .iter()
+                    .map(|r| r.to_string())
+                    .collect::<BTreeSet<_>>();
+                for reference in &references {
+                    if !self.tables.contains_key(reference) && !self.views.contains_key(reference) {
+                        return None;
+                    }
+                }
+                if self.views.contains_key(*name) {
+                    // Neither the view nor those depending on it can become its dependencies.
+                    if !or_replace || references.iter().any(|r| self.depends_on(r, name)) {
+                        return None;
+                    }
+                }
+                self.views.insert(name.to_string(), references);
+            }
+            Op::DropViews(names) => {
+                for name in names {
+                    self.views.remove(*name)?;
+                    self.check_unused(name)?;
+                }
+            }
+        }
+        Some(())
+    }
+
+    fn check_unused(&self, name: &str) -> Option<()> {
+        match self.views.values().any(|deps| deps.contains(name)) {
+            true => None,
+            false => Some(()),
+        }
+    }
+
+    /// Whether `object` is `other` or depends on it, directly or not.
+    fn depends_on(&self, object: &str, other: &str) -> bool {
+        object == other
+            || self
+                .views
+                .get(object)
+                .is_some_and(|deps| deps.iter().any(|d| self.depends_on(d, other)))
+    }
+}
+
+/// Everything compared between the session and the model, rendered the same way for both.
+async fn state_of_session(session: &Session) -> String {
+    let mut out = String::new();
+
+    let tables = session.list_objects(&["table"]).await.unwrap();
+    for row in &tables.rows {
+        let columns = session.describe(&row[0]).await.unwrap();
+        let columns = columns
+            .rows
+            .iter()
+            .map(|c| {
+                format!(
+                    "{} {}{}",
+                    c[0],
+                    c[1],
+                    if c[2] == "PRI" { " PK" } else { "" }
+                )
+            })
+            .collect::<Vec<_>>();
+        out += &format!("table {}: {}\n", row[0], columns.join(", "));
+    }
+
+    let views = session.list_objects(&["view"]).await.unwrap();
+    for row in &views.rows {
+        let view = session.describe(&row[0]).await.unwrap();
+        out += &format!("view {}: {}\n", row[0], view.rows[0][1]);
+    }
+
+    // Nothing left behind by dropped objects.
+    let meta = session.meta();
+    let count = |n: u64| n.to_string();
+    out += &format!(
+        "{} tables, {} views, {} columns, {} dependencies\n",
+        count(MyTable::find().count(meta).await.unwrap()),
+        count(MyView::find().count(meta).await.unwrap()),
+        count(MyColumn::find().count(meta).await.unwrap()),
+        count(MyViewDependency::find().count(meta).await.unwrap()),
+    );
+
+    out
+}
+
+fn state_of_model(model: &Model) -> String {
+    let mut out = String::new();
+
+    for (name, columns) in &model.tables {
+        let columns = columns
+            .iter()
+            .map(|c| {
+                let pk = if c.is_primary_key { " PK" } else { "" };
+                format!("{} {}{pk}", c.name, c.data_type)
+            })
+            .collect::<Vec<_>>();
+        out += &format!("table {name}: {}\n", columns.join(", "));
+    }
+
+    for (name, deps) in &model.views {
+        let deps = deps.iter().cloned().collect::<Vec<_>>();
+        out += &format!("view {name}: {}\n", deps.join(", "));
+    }
+
+    out += &format!(
+        "{} tables, {} views, {} columns, {} dependencies\n",
+        model.tables.len(),
+        model.views.len(),
+        model.tables.values().map(|c| c.len()).sum::<usize>(),
+        model.views.values().map(|d| d.len()).sum::<usize>(),
+    );
+
+    out
+}
+
+async fn check(ops: Vec<Op>) -> Result<(), TestCaseError> {
+    let meta = metastore::connect(metastore::MEMORY_URL).await.unwrap();
+    let mut session = Catalog::new(meta).session("proptest");
+    session.execute("CREATE DATABASE db").await.unwrap();
+    let mut model = Model::default();
+
+    for op in ops {
+        let sql = op.sql();
+        let stmt = parser::parse(&sql).unwrap().remove(0);
+        let result = session.handle(stmt).await;
+        let expected = model.apply(&op);
+
+        prop_assert_eq!(
+            result.is_ok(),
+            expected,
+            "`{}` returned {:?}",
+            sql,
+            result.err()
+        );
+        prop_assert_eq!(
+            state_of_session(&session).await,
+            state_of_model(&model),
+            "after `{}`",
+            sql
+        );
+    }
+
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn test_ddl_model(ops in proptest::collection::vec(op(), 1..40)) {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(check(ops))?;
+    }
+}
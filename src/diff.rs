@@ -0,0 +1,603 @@
+//! Compare the schemas of two databases in the catalog.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use anyhow::{Context, Result};
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder};
+use sqlparser::ast::helpers::stmt_create_table::CreateTableBuilder;
+use sqlparser::ast::{self, Ident, ObjectName};
+use sqlparser::dialect::GenericDialect;
+use sqlparser::parser::Parser;
+
+use crate::catalog::display_name;
+use crate::entities::prelude::*;
+use crate::entities::*;
+
+/// A view as seen by the diff: its definition and the names of the objects it depends on.
+#[derive(Debug, Clone)]
+pub struct View {
+    pub definition: String,
+    pub dependencies: BTreeSet<String>,
+}
+
+/// Snapshot of all tables and views in a database, keyed by name, qualified by the schema unless
+/// it's the default one.
+#[derive(Debug, Default)]
+pub struct Schema {
+    pub tables: BTreeMap<String, Vec<my_column::Model>>,
+    pub views: BTreeMap<String, View>,
+}
+
+impl Schema {
+    /// Fails on rows referencing objects that don't exist, which `CHECK CATALOG` reports and
+    /// repairs.
+    pub async fn load(conn: &impl ConnectionTrait, database_id: i32) -> Result<Self> {
+        let objects = MyObject::find()
+            .filter(my_object::Column::DatabaseId.eq(database_id))
+            .all(conn)
+            .await?;
+        let schemas = MySchema::find()
+            .filter(my_schema::Column::DatabaseId.eq(database_id))
+            .all(conn)
+            .await?
+            .into_iter()
+            .map(|s| (s.id, s.name))
+            .collect::<BTreeMap<_, _>>();
+        let mut schema = Self::default();
+        let mut names = BTreeMap::new();
+        for object in objects {
+            let schema_name = schemas.get(&object.schema_id).with_context(|| {
+                format!("{} `{}` is in a missing schema", object.r#type, object.name)
+            })?;
+            let name = display_name(schema_name, &object.name);
+            match object.r#type.as_str() {
+                "table" => {
+                    schema.tables.insert(name.clone(), Vec::new());
+                }
+                "view" => {}
+                // Materialized views and other objects are left out of the diff, along with their
+                // columns and definitions.
+                _ => continue,
+            }
+            names.insert(object.id, name);
+        }
+        let ids = names.keys().copied().collect::<Vec<_>>();
+
+        let columns = MyColumn::find()
+            .filter(my_column::Column::TableId.is_in(ids.clone()))
+            .order_by_asc(my_column::Column::Id) // keep the order of creation
+            .all(conn)
+            .await?;
+        for column in columns {
+            // Views have no columns.
+            let table = names
+                .get(&column.table_id)
+                .and_then(|name| schema.tables.get_mut(name))
+                .with_context(|| format!("column `{}` is in a missing table", column.name))?;
+            table.push(column);
+        }
+
+        let dependencies = MyViewDependency::find()
+            .filter(my_view_dependency::Column::ViewId.is_in(ids.clone()))
+            .all(conn)
+            .await?;
+        // Dependencies are not guaranteed to live in the same database, look up their names
+        // separately.
+        let dependency_names = MyObject::find()
+            .filter(my_object::Column::Id.is_in(dependencies.iter().map(|d| d.dependent_object_id)))
+            .find_also_related(MySchema)
+            .all(conn)
+            .await?
+            .into_iter()
+            .map(|(o, s)| {
+                let name = match s {
+                    Some(s) => display_name(&s.name, &o.name),
+                    None => o.name,
+                };
+                (o.id, name)
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        let views = MyView::find()
+            .filter(my_view::Column::ObjectId.is_in(ids))
+            .all(conn)
+            .await?;
+        for view in views {
+            let name = &names[&view.object_id];
+            let dependencies = dependencies
+                .iter()
+                .filter(|d| d.view_id == view.object_id)
+                .map(|d| {
+                    dependency_names
+                        .get(&d.dependent_object_id)
+                        .cloned()
+                        .with_context(|| format!("view `{name}` depends on a missing object"))
+                })
+                .collect::<Result<_>>()?;
+
+            schema.views.insert(
+                name.clone(),
+                View {
+                    definition: view.definition,
+                    dependencies,
+                },
+            );
+        }
+
+        Ok(schema)
+    }
+
+    /// Compute the changes and the statements that transform `self` into `other`.
+    pub fn diff(&self, other: &Schema) -> Result<SchemaDiff> {
+        let mut changes = Vec::new();
+        let mut statements = Vec::new();
+
+        // Views that must be dropped before the tables are changed, and created again afterwards.
+        let mut dropped_views = BTreeSet::new();
+        for (name, view) in &self.views {
+            match other.views.get(name) {
+                None => {
+                    changes.push(Change::ViewRemoved { name: name.clone() });
+                    dropped_views.insert(name.clone());
+                }
+                Some(new) if new.definition != view.definition => {
+                    changes.push(Change::ViewChanged {
+                        name: name.clone(),
+                        from: view.definition.clone(),
+                        to: new.definition.clone(),
+                    });
+                    dropped_views.insert(name.clone());
+                }
+                Some(_) => {}
+            }
+        }
+        for (name, view) in &other.views {
+            if !self.views.contains_key(name) {
+                changes.push(Change::ViewAdded {
+                    name: name.clone(),
+                    definition: view.definition.clone(),
+                });
+            }
+        }
+        // Unchanged views on top of a dropped view have to be dropped as well, otherwise the
+        // dependency restricts the deletion.
+        loop {
+            let dependents = self
+                .views
+                .iter()
+                .filter(|(name, view)| {
+                    !dropped_views.contains(*name) && !view.dependencies.is_disjoint(&dropped_views)
+                })
+                .map(|(name, _)| name.clone())
+                .collect::<Vec<_>>();
+            if dependents.is_empty() {
+                break;
+            }
+            dropped_views.extend(dependents);
+        }
+
+        for name in topological_order(&self.views, &dropped_views)
+            .into_iter()
+            .rev()
+        {
+            statements.push(ast::Statement::Drop {
+                object_type: ast::ObjectType::View,
+                if_exists: false,
+                names: vec![object_name(&name)],
+                cascade: false,
+                restrict: false,
+                purge: false,
+                temporary: false,
+            });
+        }
+
+        for name in self.tables.keys() {
+            if !other.tables.contains_key(name) {
+                changes.push(Change::TableRemoved { name: name.clone() });
+                statements.push(ast::Statement::Drop {
+                    object_type: ast::ObjectType::Table,
+                    if_exists: false,
+                    names: vec![object_name(name)],
+                    cascade: false,
+                    restrict: false,
+                    purge: false,
+                    temporary: false,
+                });
+            }
+        }
+
+        for (name, columns) in &other.tables {
+            let Some(old_columns) = self.tables.get(name) else {
+                changes.push(Change::TableAdded {
+                    name: name.clone(),
+                    columns: columns.clone(),
+                });
+                statements.push(
+                    CreateTableBuilder::new(object_name(name))
+                        .columns(columns.iter().map(|c| c.to_ast()).collect::<Result<_>>()?)
+                        .build(),
+                );
+                continue;
+            };
+
+            let mut operations = Vec::new();
+            for old in old_columns {
+                if !columns.iter().any(|c| c.name == old.name) {
+                    changes.push(Change::ColumnRemoved {
+                        table: name.clone(),
+                        column: old.clone(),
+                    });
+                    operations.push(drop_column(old));
+                }
+            }
+            for new in columns {
+                let Some(old) = old_columns.iter().find(|c| c.name == new.name) else {
+                    changes.push(Change::ColumnAdded {
+                        table: name.clone(),
+                        column: new.clone(),
+                    });
+                    operations.push(ast::AlterTableOperation::AddColumn {
+                        column_keyword: true,
+                        if_not_exists: false,
+                        column_def: new.to_ast()?,
+                    });
+                    continue;
+                };
+
+                if (&old.data_type, old.is_nullable, old.is_primary_key)
+                    == (&new.data_type, new.is_nullable, new.is_primary_key)
+                {
+                    continue;
+                }
+                changes.push(Change::ColumnChanged {
+                    table: name.clone(),
+                    from: old.clone(),
+                    to: new.clone(),
+                });
+
+                // There's no `ALTER COLUMN` for primary keys, so the column is recreated.
+                if old.is_primary_key != new.is_primary_key {
+                    operations.push(drop_column(old));
+                    operations.push(ast::AlterTableOperation::AddColumn {
+                        column_keyword: true,
+                        if_not_exists: false,
+                        column_def: new.to_ast()?,
+                    });
+                    continue;
+                }
+
+                let column_name = Ident::new(&new.name);
+                if old.data_type != new.data_type {
+                    operations.push(ast::AlterTableOperation::AlterColumn {
+                        column_name: column_name.clone(),
+                        op: ast::AlterColumnOperation::SetDataType {
+                            data_type: new.ast_data_type()?,
+                            using: None,
+                        },
+                    });
+                }
+                if old.is_nullable != new.is_nullable {
+                    operations.push(ast::AlterTableOperation::AlterColumn {
+                        column_name,
+                        op: if new.is_nullable {
+                            ast::AlterColumnOperation::DropNotNull
+                        } else {
+                            ast::AlterColumnOperation::SetNotNull
+                        },
+                    });
+                }
+            }
+
+            if !operations.is_empty() {
+                statements.push(ast::Statement::AlterTable {
+                    name: object_name(name),
+                    if_exists: false,
+                    only: false,
+                    operations,
+                });
+            }
+        }
+
+        let created_views = other
+            .views
+            .keys()
+            .filter(|name| !self.views.contains_key(*name) || dropped_views.contains(*name))
+            .cloned()
+            .collect();
+        for name in topological_order(&other.views, &created_views) {
+            let query = Parser::new(&GenericDialect {})
+                .try_with_sql(&other.views[&name].definition)?
+                .parse_query()?;
+
+            statements.push(ast::Statement::CreateView {
+                or_replace: false,
+                materialized: false,
+                name: object_name(&name),
+                columns: vec![],
+                query: Box::new(query),
+                with_options: vec![],
+                cluster_by: vec![],
+            });
+        }
+
+        Ok(SchemaDiff {
+            changes,
+            statements,
+        })
+    }
+}
+
+/// Order the views in `names` so that each view comes after the views it depends on.
+fn topological_order(views: &BTreeMap<String, View>, names: &BTreeSet<String>) -> Vec<String> {
+    let mut order = Vec::new();
+    let mut remaining = names.clone();
+
+    while !remaining.is_empty() {
+        let ready = remaining
+            .iter()
+            .filter(|name| views[*name].dependencies.is_disjoint(&remaining))
+            .cloned()
+            .collect::<Vec<_>>();
+        if ready.is_empty() {
+            // Cycles are not possible in a valid catalog, keep the rest as is anyway.
+            order.extend(std::mem::take(&mut remaining));
+            break;
+        }
+        for name in ready {
+            remaining.remove(&name);
+            order.push(name);
+        }
+    }
+
+    order
+}
+
+/// The name as shown in [`Schema`], i.e., `name` or `schema.name`.
+fn object_name(name: &str) -> ObjectName {
+    ObjectName(name.split('.').map(Ident::new).collect())
+}
+
+fn drop_column(column: &my_column::Model) -> ast::AlterTableOperation {
+    ast::AlterTableOperation::DropColumn {
+        column_name: Ident::new(&column.name),
+        if_exists: false,
+        cascade: false,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    TableAdded {
+        name: String,
+        columns: Vec<my_column::Model>,
+    },
+    TableRemoved {
+        name: String,
+    },
+    ColumnAdded {
+        table: String,
+        column: my_column::Model,
+    },
+    ColumnRemoved {
+        table: String,
+        column: my_column::Model,
+    },
+    ColumnChanged {
+        table: String,
+        from: my_column::Model,
+        to: my_column::Model,
+    },
+    ViewAdded {
+        name: String,
+        definition: String,
+    },
+    ViewRemoved {
+        name: String,
+    },
+    ViewChanged {
+        name: String,
+        from: String,
+        to: String,
+    },
+}
+
+/// Describe a column like `INT NOT NULL PRIMARY KEY`.
+struct ColumnSpec<'a>(&'a my_column::Model);
+
+impl fmt::Display for ColumnSpec<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.data_type)?;
+        if !self.0.is_nullable {
+            write!(f, " NOT NULL")?;
+        }
+        if self.0.is_primary_key {
+            write!(f, " PRIMARY KEY")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::TableAdded { name, columns } => {
+                write!(f, "+ table {name} (")?;
+                for (i, column) in columns.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{} {}", column.name, ColumnSpec(column))?;
+                }
+                write!(f, ")")
+            }
+            Change::TableRemoved { name } => write!(f, "- table {name}"),
+            Change::ColumnAdded { table, column } => {
+                write!(f, "+ column {table}.{} {}", column.name, ColumnSpec(column))
+            }
+            Change::ColumnRemoved { table, column } => {
+                write!(f, "- column {table}.{}", column.name)
+            }
+            Change::ColumnChanged { table, from, to } => write!(
+                f,
+                "~ column {table}.{}: {} -> {}",
+                to.name,
+                ColumnSpec(from),
+                ColumnSpec(to)
+            ),
+            Change::ViewAdded { name, definition } => write!(f, "+ view {name} AS {definition}"),
+            Change::ViewRemoved { name } => write!(f, "- view {name}"),
+            Change::ViewChanged { name, from, to } => {
+                write!(f, "~ view {name}: AS {from} -> AS {to}")
+            }
+        }
+    }
+}
+
+/// Result of [`Schema::diff`].
+#[derive(Debug)]
+pub struct SchemaDiff {
+    /// Human-readable list of changes.
+    pub changes: Vec<Change>,
+    /// Statements that transform the first schema into the second, in the order they should be
+    /// executed.
+    pub statements: Vec<ast::Statement>,
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+    use sea_orm::{ConnectionTrait, DatabaseConnection};
+
+    use super::*;
+    use crate::catalog::Catalog;
+    use crate::session::Session;
+    use crate::{metastore, parser};
+
+    async fn run(session: &mut Session, sql: &str) {
+        for stmt in parser::parse(sql).unwrap() {
+            session.handle(stmt).await.unwrap();
+        }
+    }
+
+    async fn diff(meta: &DatabaseConnection, from: &str, to: &str) -> SchemaDiff {
+        let load = |name: &str| {
+            let name = name.to_owned();
+            async move {
+                let db = MyDatabase::find()
+                    .filter(my_database::Column::Name.eq(name))
+                    .one(meta)
+                    .await
+                    .unwrap()
+                    .unwrap();
+                Schema::load(meta, db.id).await.unwrap()
+            }
+        };
+        load(from).await.diff(&load(to).await).unwrap()
+    }
+
+    fn render(diff: &SchemaDiff) -> String {
+        let mut out = String::new();
+        for change in &diff.changes {
+            out += &format!("{change}\n");
+        }
+        for stmt in &diff.statements {
+            out += &format!("{stmt};\n");
+        }
+        out
+    }
+
+    #[tokio::test]
+    async fn test_diff_database() {
+        let meta = metastore::connect(metastore::MEMORY_URL).await.unwrap();
+        let mut session = Catalog::new(meta.clone()).session("test");
+
+        run(
+            &mut session,
+            "CREATE DATABASE staging;
+             CREATE TABLE t (k INT PRIMARY KEY, v INT, w INT);
+             CREATE TABLE old (a INT);
+             CREATE VIEW v1 AS SELECT * FROM t;
+             CREATE VIEW v2 AS SELECT * FROM v1;
+             CREATE VIEW v3 AS SELECT * FROM old;
+             CREATE DATABASE production;
+             USE production;
+             CREATE TABLE t (k INT PRIMARY KEY, v BIGINT NOT NULL, x TEXT);
+             CREATE TABLE new (b INT NOT NULL);
+             CREATE VIEW v1 AS SELECT k FROM t;
+             CREATE VIEW v2 AS SELECT * FROM v1;
+             CREATE VIEW v4 AS SELECT * FROM new;",
+        )
+        .await;
+
+        let diff = diff(&meta, "staging", "production").await;
+        expect![[r#"
+            ~ view v1: AS SELECT * FROM public.t -> AS SELECT k FROM public.t
+            - view v3
+            + view v4 AS SELECT * FROM public.new
+            - table old
+            + table new (b INT NOT NULL)
+            - column t.w
+            ~ column t.v: INT -> BIGINT NOT NULL
+            + column t.x TEXT
+            DROP VIEW v2;
+            DROP VIEW v3;
+            DROP VIEW v1;
+            DROP TABLE old;
+            CREATE TABLE new (b INT NOT NULL);
+            ALTER TABLE t DROP COLUMN w, ALTER COLUMN v SET DATA TYPE BIGINT, ALTER COLUMN v SET NOT NULL, ADD COLUMN x TEXT;
+            CREATE VIEW v1 AS SELECT k FROM public.t;
+            CREATE VIEW v4 AS SELECT * FROM public.new;
+            CREATE VIEW v2 AS SELECT * FROM public.v1;
+        "#]]
+        .assert_eq(&render(&diff));
+
+        // Applying the statements to `staging` should make it identical to `production`.
+        run(&mut session, "USE staging").await;
+        for stmt in diff.statements {
+            session.handle(stmt.into()).await.unwrap();
+        }
+        let diff = super::tests::diff(&meta, "staging", "production").await;
+        expect![""].assert_eq(&render(&diff));
+    }
+
+    /// Rows referencing missing objects, which `CHECK CATALOG` reports, fail the load.
+    #[tokio::test]
+    async fn test_load_inconsistent() {
+        let meta = metastore::connect(metastore::MEMORY_URL).await.unwrap();
+        let mut session = Catalog::new(meta.clone()).session("test");
+        run(
+            &mut session,
+            "CREATE DATABASE shop;
+             CREATE TABLE users (id INT PRIMARY KEY);
+             CREATE VIEW v AS SELECT * FROM users",
+        )
+        .await;
+        let db_id = session.current_db_id().unwrap();
+
+        for (corruption, error) in [
+            (
+                "INSERT INTO my_view_dependency (view_id, dependent_object_id) \
+                 VALUES ((SELECT id FROM my_object WHERE name = 'v'), 1000)",
+                expect!["view `v` depends on a missing object"],
+            ),
+            (
+                "INSERT INTO my_column (table_id, name, data_type, is_primary_key, is_nullable) \
+                 VALUES ((SELECT id FROM my_object WHERE name = 'v'), 'ghost', 'INT', false, true)",
+                expect!["column `ghost` is in a missing table"],
+            ),
+            (
+                "UPDATE my_object SET schema_id = 1000 WHERE name = 'users'",
+                expect!["table `users` is in a missing schema"],
+            ),
+        ] {
+            meta.execute_unprepared(&format!(
+                "PRAGMA foreign_keys = OFF; {corruption}; PRAGMA foreign_keys = ON"
+            ))
+            .await
+            .unwrap();
+            let e = Schema::load(&meta, db_id).await.unwrap_err();
+            error.assert_eq(&e.to_string());
+        }
+    }
+}
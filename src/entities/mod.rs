@@ -2,9 +2,16 @@
 
 pub mod prelude;
 
+pub mod my_catalog;
 pub mod my_column;
 pub mod my_database;
+pub mod my_enum_label;
+pub mod my_materialized_view;
 pub mod my_object;
+pub mod my_schema;
+pub mod my_sequence;
 pub mod my_table;
+pub mod my_type;
+pub mod my_type_attribute;
 pub mod my_view;
 pub mod my_view_dependency;
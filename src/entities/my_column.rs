@@ -11,6 +11,8 @@ pub struct Model {
     pub name: String,
     pub data_type: String,
     pub is_primary_key: bool,
+    pub is_nullable: bool,
+    pub user_type_id: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -23,6 +25,22 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     MyTable,
+    #[sea_orm(has_many = "super::my_sequence::Entity")]
+    MySequence,
+    #[sea_orm(
+        belongs_to = "super::my_type::Entity",
+        from = "Column::UserTypeId",
+        to = "super::my_type::Column::ObjectId",
+        on_update = "Cascade",
+        on_delete = "NoAction"
+    )]
+    MyType,
+}
+
+impl Related<super::my_sequence::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MySequence.def()
+    }
 }
 
 impl Related<super::my_table::Entity> for Entity {
@@ -31,4 +49,10 @@ impl Related<super::my_table::Entity> for Entity {
     }
 }
 
+impl Related<super::my_type::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MyType.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -11,6 +11,15 @@ pub struct Model {
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(has_many = "super::my_schema::Entity")]
+    MySchema,
+}
+
+impl Related<super::my_schema::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MySchema.def()
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,31 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "my_enum_label")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub type_id: i32,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub position: i32,
+    pub label: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::my_type::Entity",
+        from = "Column::TypeId",
+        to = "super::my_type::Column::ObjectId",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    MyType,
+}
+
+impl Related<super::my_type::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MyType.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -10,24 +10,64 @@ pub struct Model {
     pub r#type: String,
     pub name: String,
     pub database_id: i32,
+    pub version: i32,
+    pub schema_id: i32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::my_schema::Entity",
+        from = "Column::SchemaId",
+        to = "super::my_schema::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    MySchema,
+    #[sea_orm(has_many = "super::my_materialized_view::Entity")]
+    MyMaterializedView,
+    #[sea_orm(has_many = "super::my_sequence::Entity")]
+    MySequence,
     #[sea_orm(has_many = "super::my_table::Entity")]
     MyTable,
+    #[sea_orm(has_many = "super::my_type::Entity")]
+    MyType,
     #[sea_orm(has_many = "super::my_view::Entity")]
     MyView,
     #[sea_orm(has_many = "super::my_view_dependency::Entity")]
     MyViewDependency,
 }
 
+impl Related<super::my_materialized_view::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MyMaterializedView.def()
+    }
+}
+
+impl Related<super::my_schema::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MySchema.def()
+    }
+}
+
+impl Related<super::my_sequence::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MySequence.def()
+    }
+}
+
 impl Related<super::my_table::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::MyTable.def()
     }
 }
 
+impl Related<super::my_type::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MyType.def()
+    }
+}
+
 impl Related<super::my_view_dependency::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::MyViewDependency.def()
@@ -0,0 +1,38 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "my_schema")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub database_id: i32,
+    pub name: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::my_database::Entity",
+        from = "Column::DatabaseId",
+        to = "super::my_database::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    MyDatabase,
+    #[sea_orm(has_many = "super::my_object::Entity")]
+    MyObject,
+}
+
+impl Related<super::my_database::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MyDatabase.def()
+    }
+}
+
+impl Related<super::my_object::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MyObject.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
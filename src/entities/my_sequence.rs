@@ -0,0 +1,51 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "my_sequence")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub object_id: i32,
+    pub data_type: String,
+    pub start_value: i64,
+    pub increment: i64,
+    pub min_value: i64,
+    pub max_value: i64,
+    pub cycle: bool,
+    pub current_value: i64,
+    #[sea_orm(unique)]
+    pub owned_by_column_id: Option<i32>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::my_column::Entity",
+        from = "Column::OwnedByColumnId",
+        to = "super::my_column::Column::Id",
+        on_update = "Cascade",
+        on_delete = "SetNull"
+    )]
+    MyColumn,
+    #[sea_orm(
+        belongs_to = "super::my_object::Entity",
+        from = "Column::ObjectId",
+        to = "super::my_object::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    MyObject,
+}
+
+impl Related<super::my_column::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MyColumn.def()
+    }
+}
+
+impl Related<super::my_object::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MyObject.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
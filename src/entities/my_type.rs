@@ -0,0 +1,58 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "my_type")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub object_id: i32,
+    pub kind: String,
+    pub base_type: Option<String>,
+    pub base_type_id: Option<i32>,
+    pub is_nullable: bool,
+    pub default_expr: Option<String>,
+    pub check_expr: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "Entity",
+        from = "Column::BaseTypeId",
+        to = "Column::ObjectId",
+        on_update = "Cascade",
+        on_delete = "NoAction"
+    )]
+    SelfRef,
+    #[sea_orm(has_many = "super::my_column::Entity")]
+    MyColumn,
+    #[sea_orm(has_many = "super::my_enum_label::Entity")]
+    MyEnumLabel,
+    #[sea_orm(
+        belongs_to = "super::my_object::Entity",
+        from = "Column::ObjectId",
+        to = "super::my_object::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    MyObject,
+}
+
+impl Related<super::my_column::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MyColumn.def()
+    }
+}
+
+impl Related<super::my_enum_label::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MyEnumLabel.def()
+    }
+}
+
+impl Related<super::my_object::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MyObject.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
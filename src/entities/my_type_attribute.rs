@@ -0,0 +1,35 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "my_type_attribute")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub type_id: i32,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub position: i32,
+    pub name: String,
+    pub data_type: String,
+    pub user_type_id: Option<i32>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::my_type::Entity",
+        from = "Column::UserTypeId",
+        to = "super::my_type::Column::ObjectId",
+        on_update = "Cascade",
+        on_delete = "NoAction"
+    )]
+    MyType2,
+    #[sea_orm(
+        belongs_to = "super::my_type::Entity",
+        from = "Column::TypeId",
+        to = "super::my_type::Column::ObjectId",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    MyType1,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
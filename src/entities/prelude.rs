@@ -1,8 +1,15 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.3
 
+pub use super::my_catalog::Entity as MyCatalog;
 pub use super::my_column::Entity as MyColumn;
 pub use super::my_database::Entity as MyDatabase;
+pub use super::my_enum_label::Entity as MyEnumLabel;
+pub use super::my_materialized_view::Entity as MyMaterializedView;
 pub use super::my_object::Entity as MyObject;
+pub use super::my_schema::Entity as MySchema;
+pub use super::my_sequence::Entity as MySequence;
 pub use super::my_table::Entity as MyTable;
+pub use super::my_type::Entity as MyType;
+pub use super::my_type_attribute::Entity as MyTypeAttribute;
 pub use super::my_view::Entity as MyView;
 pub use super::my_view_dependency::Entity as MyViewDependency;
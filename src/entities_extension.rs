@@ -1,19 +1,66 @@
+use anyhow::Result;
 use sea_orm::ActiveValue::*;
 use sqlparser::ast;
+use sqlparser::dialect::GenericDialect;
+use sqlparser::parser::Parser;
 
 use crate::entities::my_column;
+use crate::sequence;
 
 impl my_column::ActiveModel {
+    /// Identity columns are `NOT NULL`, and a `SERIAL` type is stored as the integer type behind
+    /// it. The caller creates their sequences, see [`sequence::identity`], and sets the
+    /// user-defined type, if any.
     pub fn from_ast(col: ast::ColumnDef, table_id: i32) -> Self {
+        let is_primary_key = col
+            .options
+            .iter()
+            .any(|c| matches!(c.option, ast::ColumnOption::Unique { is_primary: true }));
+        let is_not_null = col
+            .options
+            .iter()
+            .any(|c| matches!(c.option, ast::ColumnOption::NotNull))
+            || sequence::identity(&col).is_some();
+        let data_type = sequence::serial_type(&col.data_type).unwrap_or(col.data_type);
+
         my_column::ActiveModel {
             id: NotSet, // auto increment
             name: Set(col.name.value.to_owned()),
             table_id: Set(table_id),
-            data_type: Set(col.data_type.to_string()),
-            is_primary_key: Set(col
-                .options
-                .iter()
-                .any(|c| matches!(c.option, ast::ColumnOption::Unique { is_primary: true }))),
+            data_type: Set(data_type.to_string()),
+            is_primary_key: Set(is_primary_key),
+            is_nullable: Set(!is_primary_key && !is_not_null),
+            user_type_id: Set(None),
+        }
+    }
+}
+
+impl my_column::Model {
+    /// Parse the data type, which is stored as a string in the catalog.
+    pub fn ast_data_type(&self) -> Result<ast::DataType> {
+        let data_type = Parser::new(&GenericDialect {})
+            .try_with_sql(&self.data_type)?
+            .parse_data_type()?;
+        Ok(data_type)
+    }
+
+    /// The reverse of [`my_column::ActiveModel::from_ast`].
+    pub fn to_ast(&self) -> Result<ast::ColumnDef> {
+        let mut options = Vec::new();
+        if self.is_primary_key {
+            options.push(ast::ColumnOption::Unique { is_primary: true });
+        } else if !self.is_nullable {
+            options.push(ast::ColumnOption::NotNull);
         }
+
+        Ok(ast::ColumnDef {
+            name: ast::Ident::new(&self.name),
+            data_type: self.ast_data_type()?,
+            collation: None,
+            options: options
+                .into_iter()
+                .map(|option| ast::ColumnOptionDef { name: None, option })
+                .collect(),
+        })
     }
 }
@@ -0,0 +1,151 @@
+//! Errors of the catalog that callers may want to tell apart, e.g., to pick an HTTP status code.
+//! Everything else is a plain [`anyhow::Error`].
+
+use std::fmt;
+
+use sea_orm::{DbErr, SqlErr};
+
+use crate::parser::Statement;
+
+/// Databases, tables, etc. referenced by name do not exist.
+#[derive(Debug)]
+pub struct NotFound {
+    pub kind: &'static str,
+    pub names: Vec<String>,
+}
+
+impl NotFound {
+    pub fn new(kind: &'static str, name: impl Into<String>) -> Self {
+        Self::many(kind, vec![name.into()])
+    }
+
+    /// Several objects of the same kind, e.g., all those referenced by a view.
+    pub fn many(kind: &'static str, names: Vec<String>) -> Self {
+        Self { kind, names }
+    }
+}
+
+impl fmt::Display for NotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = self
+            .names
+            .iter()
+            .map(|n| format!("`{n}`"))
+            .collect::<Vec<_>>();
+        match names.as_slice() {
+            [name] => write!(f, "{} {name} not found", self.kind),
+            _ => write!(f, "{}s {} not found", self.kind, names.join(", ")),
+        }
+    }
+}
+
+impl std::error::Error for NotFound {}
+
+/// An object with the same name exists.
+#[derive(Debug)]
+pub struct AlreadyExists {
+    pub kind: &'static str,
+    pub name: String,
+}
+
+impl fmt::Display for AlreadyExists {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} `{}` already exists", self.kind, self.name)
+    }
+}
+
+impl std::error::Error for AlreadyExists {}
+
+/// Turn unique constraint violations on inserting an object into [`AlreadyExists`].
+pub fn already_exists(
+    kind: &'static str,
+    name: impl Into<String>,
+) -> impl FnOnce(DbErr) -> anyhow::Error {
+    move |e| match e.sql_err() {
+        Some(SqlErr::UniqueConstraintViolation(_)) => AlreadyExists {
+            kind,
+            name: name.into(),
+        }
+        .into(),
+        _ => e.into(),
+    }
+}
+
+/// An object was changed by another session since it was read, so the statement should be
+/// retried.
+#[derive(Debug)]
+pub struct ConcurrentModification {
+    pub kind: String,
+    pub name: String,
+}
+
+impl fmt::Display for ConcurrentModification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "concurrent modification of {} `{}`, please retry",
+            self.kind, self.name
+        )
+    }
+}
+
+impl std::error::Error for ConcurrentModification {}
+
+/// An object cannot be dropped because views depend on it.
+#[derive(Debug)]
+pub struct InUse {
+    pub kind: &'static str,
+    pub name: String,
+    pub dependents: Vec<String>,
+}
+
+impl InUse {
+    pub fn new(kind: &'static str, name: impl Into<String>, dependents: Vec<String>) -> Self {
+        Self {
+            kind,
+            name: name.into(),
+            dependents,
+        }
+    }
+}
+
+impl fmt::Display for InUse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cannot drop {} `{}` because other objects depend on it: {}",
+            self.kind,
+            self.name,
+            self.dependents.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for InUse {}
+
+/// A statement reads or writes files on the server, which is only allowed from the command line,
+/// not on behalf of network clients.
+#[derive(Debug)]
+pub struct LocalOnly {
+    pub statement: String,
+}
+
+impl LocalOnly {
+    pub fn new(statement: &Statement) -> Self {
+        Self {
+            statement: statement.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for LocalOnly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` accesses files on the server and is only allowed from the command line",
+            self.statement
+        )
+    }
+}
+
+impl std::error::Error for LocalOnly {}
@@ -0,0 +1,207 @@
+//! Changes to the catalog, as published to subscribers by [`Catalog::subscribe`].
+//!
+//! [`Catalog::subscribe`]: crate::catalog::Catalog::subscribe
+
+use std::fmt;
+
+use sqlparser::ast;
+
+/// A change that has been committed to the metastore.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CatalogEvent {
+    DatabaseCreated {
+        db: String,
+    },
+    SchemaCreated {
+        db: String,
+        name: String,
+    },
+    /// Dropped after the objects in it, each with its own event.
+    SchemaDropped {
+        db: String,
+        name: String,
+    },
+    TableCreated {
+        db: String,
+        schema: String,
+        name: String,
+        columns: Vec<ast::ColumnDef>,
+    },
+    TableDropped {
+        db: String,
+        schema: String,
+        name: String,
+    },
+    ColumnAdded {
+        db: String,
+        schema: String,
+        table: String,
+        column: ast::ColumnDef,
+    },
+    ColumnDropped {
+        db: String,
+        schema: String,
+        table: String,
+        column: String,
+    },
+    /// The column has a new type or nullability.
+    ColumnAltered {
+        db: String,
+        schema: String,
+        table: String,
+        column: ast::ColumnDef,
+    },
+    ViewCreated {
+        db: String,
+        schema: String,
+        name: String,
+        definition: String,
+    },
+    /// `CREATE OR REPLACE VIEW` on an existing view.
+    ViewReplaced {
+        db: String,
+        schema: String,
+        name: String,
+        definition: String,
+    },
+    ViewDropped {
+        db: String,
+        schema: String,
+        name: String,
+    },
+    MaterializedViewCreated {
+        db: String,
+        schema: String,
+        name: String,
+        definition: String,
+    },
+    /// Its columns may have changed.
+    MaterializedViewRefreshed {
+        db: String,
+        schema: String,
+        name: String,
+    },
+    MaterializedViewDropped {
+        db: String,
+        schema: String,
+        name: String,
+    },
+    /// Also published for the sequence of a new identity column.
+    SequenceCreated {
+        db: String,
+        schema: String,
+        name: String,
+    },
+    SequenceAltered {
+        db: String,
+        schema: String,
+        name: String,
+    },
+    /// Also published for the sequence of a dropped identity column, or of a column of a dropped
+    /// table.
+    SequenceDropped {
+        db: String,
+        schema: String,
+        name: String,
+    },
+    /// An enum, composite type or domain.
+    TypeCreated {
+        db: String,
+        schema: String,
+        name: String,
+    },
+    TypeDropped {
+        db: String,
+        schema: String,
+        name: String,
+    },
+    /// The subscriber didn't keep up and missed some events, so anything derived from the catalog
+    /// should be considered stale.
+    Lagged {
+        missed: u64,
+    },
+}
+
+impl fmt::Display for CatalogEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use CatalogEvent::*;
+
+        match self {
+            DatabaseCreated { db } => write!(f, "database `{db}` created"),
+            SchemaCreated { db, name } => write!(f, "schema `{db}.{name}` created"),
+            SchemaDropped { db, name } => write!(f, "schema `{db}.{name}` dropped"),
+            TableCreated {
+                db,
+                schema,
+                name,
+                columns,
+            } => {
+                let columns = columns.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+                write!(
+                    f,
+                    "table `{db}.{schema}.{name}` created ({})",
+                    columns.join(", ")
+                )
+            }
+            TableDropped { db, schema, name } => write!(f, "table `{db}.{schema}.{name}` dropped"),
+            ColumnAdded {
+                db,
+                schema,
+                table,
+                column,
+            } => write!(f, "column `{column}` added to `{db}.{schema}.{table}`"),
+            ColumnDropped {
+                db,
+                schema,
+                table,
+                column,
+            } => write!(f, "column `{column}` dropped from `{db}.{schema}.{table}`"),
+            ColumnAltered {
+                db,
+                schema,
+                table,
+                column,
+            } => write!(f, "column of `{db}.{schema}.{table}` altered to `{column}`"),
+            ViewCreated {
+                db,
+                schema,
+                name,
+                definition,
+            } => write!(f, "view `{db}.{schema}.{name}` created as {definition}"),
+            ViewReplaced {
+                db,
+                schema,
+                name,
+                definition,
+            } => write!(f, "view `{db}.{schema}.{name}` replaced with {definition}"),
+            ViewDropped { db, schema, name } => write!(f, "view `{db}.{schema}.{name}` dropped"),
+            MaterializedViewCreated {
+                db,
+                schema,
+                name,
+                definition,
+            } => write!(
+                f,
+                "materialized view `{db}.{schema}.{name}` created as {definition}"
+            ),
+            MaterializedViewRefreshed { db, schema, name } => {
+                write!(f, "materialized view `{db}.{schema}.{name}` refreshed")
+            }
+            MaterializedViewDropped { db, schema, name } => {
+                write!(f, "materialized view `{db}.{schema}.{name}` dropped")
+            }
+            SequenceCreated { db, schema, name } => {
+                write!(f, "sequence `{db}.{schema}.{name}` created")
+            }
+            SequenceAltered { db, schema, name } => {
+                write!(f, "sequence `{db}.{schema}.{name}` altered")
+            }
+            SequenceDropped { db, schema, name } => {
+                write!(f, "sequence `{db}.{schema}.{name}` dropped")
+            }
+            TypeCreated { db, schema, name } => write!(f, "type `{db}.{schema}.{name}` created"),
+            TypeDropped { db, schema, name } => write!(f, "type `{db}.{schema}.{name}` dropped"),
+            Lagged { missed } => write!(f, "missed {missed} events"),
+        }
+    }
+}
@@ -0,0 +1,411 @@
+//! HTTP API returning the catalog as JSON, for services that don't speak SQL.
+//!
+//! - `GET /databases`
+//! - `GET /databases/{db}/tables` and `GET /databases/{db}/views`, materialized views included
+//! - `GET /databases/{db}/tables/{table}`: columns and the views depending on it
+//! - `GET /databases/{db}/views/{view}`: definition and dependencies, and the refresh mode of a
+//!   materialized view, `null` for others
+//! - `POST /sql` with `{"sql": "...", "database": "..."}`: execute statements, `database` is
+//!   optional. Statements are committed one by one, so those before a failing one stay applied.
+//!   Statements accessing files on the server, e.g., `IMPORT SCHEMA`, are rejected.
+
+use std::net::SocketAddr;
+
+use anyhow::Context;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sqlparser::ast;
+use sqlparser::parser::ParserError;
+use tokio::net::TcpListener;
+
+use crate::catalog::{display_name, Catalog, DEFAULT_SCHEMA};
+use crate::entities::prelude::*;
+use crate::entities::*;
+use crate::error::{AlreadyExists, ConcurrentModification, InUse, LocalOnly, NotFound};
+use crate::parser;
+use crate::session::object_names;
+
+pub fn router(catalog: Catalog) -> Router {
+    Router::new()
+        .route("/databases", get(list_databases))
+        .route("/databases/:db/tables", get(list_tables))
+        .route("/databases/:db/views", get(list_views))
+        .route("/databases/:db/tables/:table", get(get_table))
+        .route("/databases/:db/views/:view", get(get_view))
+        .route("/sql", post(execute_sql))
+        .with_state(catalog)
+}
+
+/// Bind to `addr` and serve until Ctrl-C.
+pub async fn listen(catalog: Catalog, addr: SocketAddr) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("failed to listen on {addr}"))?;
+    println!("HTTP API listening on {}", listener.local_addr()?);
+
+    axum::serve(listener, router(catalog))
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Errors as `{"error": "..."}`, with the status code picked by the cause.
+struct ApiError(anyhow::Error);
+
+impl<E: Into<anyhow::Error>> From<E> for ApiError {
+    fn from(e: E) -> Self {
+        Self(e.into())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = if self.0.downcast_ref::<NotFound>().is_some() {
+            StatusCode::NOT_FOUND
+        } else if self.0.downcast_ref::<AlreadyExists>().is_some()
+            || self.0.downcast_ref::<InUse>().is_some()
+            || self.0.downcast_ref::<ConcurrentModification>().is_some()
+        {
+            StatusCode::CONFLICT
+        } else if self.0.downcast_ref::<LocalOnly>().is_some() {
+            StatusCode::FORBIDDEN
+        } else if self.0.downcast_ref::<ParserError>().is_some() {
+            StatusCode::BAD_REQUEST
+        } else if self.0.downcast_ref::<DbErr>().is_some() {
+            StatusCode::INTERNAL_SERVER_ERROR
+        } else {
+            // Everything else is rejected by the session, e.g., unsupported statements.
+            StatusCode::BAD_REQUEST
+        };
+
+        let body = json!({ "error": format!("{:#}", self.0) });
+        (status, Json(body)).into_response()
+    }
+}
+
+type ApiResult = Result<Json<Value>, ApiError>;
+
+async fn list_databases(State(catalog): State<Catalog>) -> ApiResult {
+    let meta = catalog.meta();
+
+    let dbs = MyDatabase::find()
+        .order_by_asc(my_database::Column::Name)
+        .all(meta)
+        .await?;
+
+    let dbs = dbs
+        .into_iter()
+        .map(|db| json!({ "name": db.name }))
+        .collect();
+    Ok(Json(Value::Array(dbs)))
+}
+
+async fn list_tables(State(catalog): State<Catalog>, Path(db): Path<String>) -> ApiResult {
+    let meta = catalog.meta();
+
+    list_objects(meta, &db, &["table"]).await
+}
+
+async fn list_views(State(catalog): State<Catalog>, Path(db): Path<String>) -> ApiResult {
+    let meta = catalog.meta();
+
+    list_objects(meta, &db, VIEW_TYPES).await
+}
+
+/// Views are served along with materialized views.
+const VIEW_TYPES: &[&str] = &["view", "materialized_view"];
+
+async fn list_objects(meta: &DatabaseConnection, db: &str, object_types: &[&str]) -> ApiResult {
+    let db = find_database(meta, db).await?;
+
+    let objects = MyObject::find()
+        .filter(
+            (my_object::Column::DatabaseId.eq(db.id))
+                .and(my_object::Column::Type.is_in(object_types.iter().copied())),
+        )
+        .find_also_related(MySchema)
+        .all(meta)
+        .await?;
+
+    let mut names = objects
+        .into_iter()
+        .map(|(o, s)| match s {
+            Some(s) => display_name(&s.name, &o.name),
+            None => o.name,
+        })
+        .collect::<Vec<_>>();
+    names.sort();
+    let objects = names
+        .into_iter()
+        .map(|name| json!({ "name": name }))
+        .collect();
+    Ok(Json(Value::Array(objects)))
+}
+
+async fn get_table(
+    State(catalog): State<Catalog>,
+    Path((db, table)): Path<(String, String)>,
+) -> ApiResult {
+    let meta = catalog.meta();
+
+    let object = find_object(meta, &db, "table", &["table"], &table).await?;
+
+    let columns = MyColumn::find()
+        .filter(my_column::Column::TableId.eq(object.id))
+        .order_by_asc(my_column::Column::Id)
+        .all(meta)
+        .await?;
+    let columns = columns
+        .into_iter()
+        .map(|c| {
+            json!({
+                "name": c.name,
+                "data_type": c.data_type,
+                "is_primary_key": c.is_primary_key,
+                "is_nullable": c.is_nullable,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    Ok(Json(json!({
+        "name": object.name,
+        "columns": columns,
+        "referenced_by": dependents(meta, object.id).await?,
+    })))
+}
+
+async fn get_view(
+    State(catalog): State<Catalog>,
+    Path((db, view)): Path<(String, String)>,
+) -> ApiResult {
+    let meta = catalog.meta();
+
+    let object = find_object(meta, &db, "view", VIEW_TYPES, &view).await?;
+
+    let definition = MyView::find_by_id(object.id)
+        .one(meta)
+        .await?
+        .context("view definition not found")?
+        .definition;
+    let refresh_mode = MyMaterializedView::find_by_id(object.id)
+        .one(meta)
+        .await?
+        .map(|m| m.refresh_mode);
+
+    let dependency_ids = MyViewDependency::find()
+        .filter(my_view_dependency::Column::ViewId.eq(object.id))
+        .all(meta)
+        .await?
+        .into_iter()
+        .map(|d| d.dependent_object_id);
+
+    Ok(Json(json!({
+        "name": object.name,
+        "definition": definition,
+        "refresh_mode": refresh_mode,
+        "dependencies": object_names(meta, dependency_ids).await?,
+        "referenced_by": dependents(meta, object.id).await?,
+    })))
+}
+
+#[derive(Deserialize)]
+struct SqlRequest {
+    sql: String,
+    database: Option<String>,
+}
+
+/// Execute the statements in a fresh session, returning the rows of each, or `null` for those
+/// returning none.
+///
+/// Each statement is committed on its own. On the first error, the error is returned and the
+/// remaining statements are skipped, but the previous ones are not rolled back.
+async fn execute_sql(State(catalog): State<Catalog>, Json(request): Json<SqlRequest>) -> ApiResult {
+    let mut session = catalog.session("http");
+
+    if let Some(database) = request.database {
+        let stmt = ast::Statement::Use {
+            db_name: ast::Ident::new(database),
+        };
+        session.handle(stmt.into()).await?;
+    }
+
+    let stmts = parser::parse(&request.sql)?;
+    // Checked upfront, so that no statement is run if any is rejected.
+    if let Some(stmt) = stmts.iter().find(|s| s.is_local_only()) {
+        return Err(LocalOnly::new(stmt).into());
+    }
+
+    let mut results = Vec::new();
+    for stmt in stmts {
+        let result = session.handle(stmt).await?.map(|result| {
+            json!({
+                "columns": result.columns,
+                "rows": result.rows,
+            })
+        });
+        results.push(result.unwrap_or(Value::Null));
+    }
+
+    Ok(Json(json!({ "results": results })))
+}
+
+async fn find_database(
+    meta: &DatabaseConnection,
+    name: &str,
+) -> Result<my_database::Model, ApiError> {
+    let db = MyDatabase::find()
+        .filter(my_database::Column::Name.eq(name))
+        .one(meta)
+        .await?
+        .with_context(|| NotFound::new("database", name))?;
+
+    Ok(db)
+}
+
+/// `name` is qualified by its schema unless it's the default one, as in the listings. `kind` names
+/// the object in the error if it's not found among the `object_types`.
+async fn find_object(
+    meta: &DatabaseConnection,
+    db: &str,
+    kind: &'static str,
+    object_types: &[&str],
+    name: &str,
+) -> Result<my_object::Model, ApiError> {
+    let db = find_database(meta, db).await?;
+    let (schema_name, object_name) = name.split_once('.').unwrap_or((DEFAULT_SCHEMA, name));
+
+    let object = MyObject::find()
+        .inner_join(MySchema)
+        .filter(
+            (my_schema::Column::DatabaseId.eq(db.id))
+                .and(my_schema::Column::Name.eq(schema_name))
+                .and(my_object::Column::Type.is_in(object_types.iter().copied()))
+                .and(my_object::Column::Name.eq(object_name)),
+        )
+        .one(meta)
+        .await?
+        .with_context(|| NotFound::new(kind, name))?;
+
+    Ok(object)
+}
+
+/// Names of the views depending on the object.
+async fn dependents(meta: &impl ConnectionTrait, object_id: i32) -> anyhow::Result<Vec<String>> {
+    let view_ids = MyViewDependency::find()
+        .filter(my_view_dependency::Column::DependentObjectId.eq(object_id))
+        .all(meta)
+        .await?
+        .into_iter()
+        .map(|d| d.view_id);
+
+    object_names(meta, view_ids).await
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::Request;
+    use expect_test::expect;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::metastore;
+
+    #[tokio::test]
+    async fn test_http() {
+        let meta = metastore::connect(metastore::MEMORY_URL).await.unwrap();
+        let app = router(Catalog::new(meta));
+
+        let mut out = String::new();
+        for (method, uri, body) in [
+            (
+                "POST",
+                "/sql",
+                r#"{"sql": "CREATE DATABASE shop; USE shop; CREATE TABLE users (id INT PRIMARY KEY, name TEXT); CREATE VIEW names AS SELECT name FROM users; CREATE MATERIALIZED VIEW ids AS SELECT id FROM users; SHOW TABLES"}"#,
+            ),
+            ("GET", "/databases", ""),
+            ("GET", "/databases/shop/tables", ""),
+            ("GET", "/databases/shop/tables/users", ""),
+            ("GET", "/databases/shop/views", ""),
+            ("GET", "/databases/shop/views/names", ""),
+            ("GET", "/databases/shop/views/ids", ""),
+            ("GET", "/databases/shop/views/nope", ""),
+            ("GET", "/databases/nope/tables", ""),
+            (
+                "POST",
+                "/sql",
+                r#"{"sql": "DROP TABLE users", "database": "shop"}"#,
+            ),
+            ("POST", "/sql", r#"{"sql": "CREATE DATABASE shop"}"#),
+            (
+                "POST",
+                "/sql",
+                r#"{"sql": "CREATE TABLE", "database": "shop"}"#,
+            ),
+            (
+                "POST",
+                "/sql",
+                r#"{"sql": "CREATE DATABASE other; GENERATE ENTITY users INTO '/tmp/users.rs'", "database": "shop"}"#,
+            ),
+            ("GET", "/databases", ""),
+        ] {
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body))
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            let status = response.status();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            out += &format!(
+                "{method} {uri}: {status}\n{}\n",
+                String::from_utf8_lossy(&body)
+            );
+        }
+
+        expect![[r#"
+            POST /sql: 200 OK
+            {"results":[null,null,null,null,null,{"columns":["Name"],"rows":[["users"]]}]}
+            GET /databases: 200 OK
+            [{"name":"shop"}]
+            GET /databases/shop/tables: 200 OK
+            [{"name":"users"}]
+            GET /databases/shop/tables/users: 200 OK
+            {"name":"users","columns":[{"name":"id","data_type":"INT","is_primary_key":true,"is_nullable":false},{"name":"name","data_type":"TEXT","is_primary_key":false,"is_nullable":true}],"referenced_by":["ids","names"]}
+            GET /databases/shop/views: 200 OK
+            [{"name":"ids"},{"name":"names"}]
+            GET /databases/shop/views/names: 200 OK
+            {"name":"names","definition":"SELECT name FROM public.users","refresh_mode":null,"dependencies":["users"],"referenced_by":[]}
+            GET /databases/shop/views/ids: 200 OK
+            {"name":"ids","definition":"SELECT id FROM public.users","refresh_mode":"manual","dependencies":["users"],"referenced_by":[]}
+            GET /databases/shop/views/nope: 404 Not Found
+            {"error":"view `nope` not found"}
+            GET /databases/nope/tables: 404 Not Found
+            {"error":"database `nope` not found"}
+            POST /sql: 409 Conflict
+            {"error":"cannot drop table `users` because other objects depend on it: ids, names"}
+            POST /sql: 409 Conflict
+            {"error":"database `shop` already exists"}
+            POST /sql: 400 Bad Request
+            {"error":"sql parser error: Expected identifier, found: EOF"}
+            POST /sql: 403 Forbidden
+            {"error":"`GENERATE ENTITY users INTO '/tmp/users.rs'` accesses files on the server and is only allowed from the command line"}
+            GET /databases: 200 OK
+            [{"name":"shop"}]
+        "#]].assert_eq(&out);
+    }
+}
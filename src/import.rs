@@ -0,0 +1,317 @@
+//! Read the schema of an existing SQLite database file, so that it can be registered in the
+//! catalog.
+
+use anyhow::{bail, Context, Result};
+use sqlparser::ast;
+use sqlparser::dialect::SQLiteDialect;
+use sqlparser::parser::Parser;
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{ConnectOptions, Connection};
+
+/// An object found in `sqlite_master`.
+#[derive(Debug)]
+pub enum SchemaObject {
+    Table {
+        name: String,
+        columns: Vec<ast::ColumnDef>,
+        /// Parts of the definition that are not tracked by the catalog and thus dropped.
+        ignored: Vec<String>,
+    },
+    View {
+        name: String,
+        query: Box<ast::Query>,
+    },
+    Unsupported {
+        r#type: String,
+        name: String,
+        reason: String,
+    },
+}
+
+/// Read all tables and views from the SQLite database file at `path`, in the order of creation.
+///
+/// Objects that cannot be represented in the catalog are returned as [`SchemaObject::Unsupported`]
+/// instead of failing the whole import.
+pub async fn read_sqlite_schema(path: &str) -> Result<Vec<SchemaObject>> {
+    let mut conn = SqliteConnectOptions::new()
+        .filename(path)
+        .read_only(true)
+        .connect()
+        .await
+        .with_context(|| format!("failed to open `{path}`"))?;
+
+    let entries: Vec<(String, String, Option<String>)> = sqlx::query_as(
+        "SELECT type, name, sql FROM sqlite_master WHERE name NOT LIKE 'sqlite_%' ORDER BY rowid",
+    )
+    .fetch_all(&mut conn)
+    .await?;
+
+    // Tables storing the content of virtual tables, e.g., of `fts5`.
+    let shadow_tables: Vec<String> =
+        sqlx::query_scalar("SELECT name FROM pragma_table_list WHERE type = 'shadow'")
+            .fetch_all(&mut conn)
+            .await?;
+
+    let mut objects = Vec::new();
+    for (r#type, name, sql) in entries {
+        let object = match (r#type.as_str(), sql) {
+            // Skipped along with their virtual table.
+            ("table", _) if shadow_tables.contains(&name) => continue,
+            ("table", Some(sql)) => {
+                let pragma: Vec<(String, String, bool, i64)> = sqlx::query_as(
+                    r#"SELECT name, type, "notnull", pk FROM pragma_table_info(?) ORDER BY cid"#,
+                )
+                .bind(&name)
+                .fetch_all(&mut conn)
+                .await?;
+
+                read_table(&sql, &pragma)
+                    .map(|(columns, ignored)| SchemaObject::Table {
+                        name: name.clone(),
+                        columns,
+                        ignored,
+                    })
+                    .unwrap_or_else(|e| SchemaObject::Unsupported {
+                        r#type: r#type.clone(),
+                        name: name.clone(),
+                        reason: format!("{e:#}"),
+                    })
+            }
+            ("view", Some(sql)) => match parse_one(&sql) {
+                Ok(ast::Statement::CreateView { query, .. }) => SchemaObject::View {
+                    name: name.clone(),
+                    query,
+                },
+                Ok(stmt) => SchemaObject::Unsupported {
+                    r#type: r#type.clone(),
+                    name: name.clone(),
+                    reason: format!("unexpected definition `{stmt}`"),
+                },
+                Err(e) => SchemaObject::Unsupported {
+                    r#type: r#type.clone(),
+                    name: name.clone(),
+                    reason: format!("{e:#}"),
+                },
+            },
+            // Implicit indexes for constraints have no SQL.
+            ("index", None) => continue,
+            _ => SchemaObject::Unsupported {
+                reason: format!("{} is not supported by the catalog", r#type),
+                r#type,
+                name,
+            },
+        };
+
+        objects.push(object);
+    }
+
+    conn.close().await?;
+
+    Ok(objects)
+}
+
+fn parse_one(sql: &str) -> Result<ast::Statement> {
+    let mut stmts = Parser::parse_sql(&SQLiteDialect {}, sql)?;
+    if stmts.len() != 1 {
+        bail!("expected exactly one statement");
+    }
+    Ok(stmts.remove(0))
+}
+
+/// Get the column definitions of a table, from its `CREATE TABLE` statement if possible, otherwise
+/// from `PRAGMA table_info`. The latter is also used to pick up table-level primary keys.
+fn read_table(
+    sql: &str,
+    pragma: &[(String, String, bool, i64)],
+) -> Result<(Vec<ast::ColumnDef>, Vec<String>)> {
+    let mut ignored = Vec::new();
+
+    let mut columns = match parse_one(sql) {
+        Ok(ast::Statement::CreateTable {
+            columns,
+            constraints,
+            without_rowid,
+            ..
+        }) => {
+            for constraint in constraints {
+                // Primary keys are picked up from `PRAGMA table_info` below.
+                if !matches!(
+                    constraint,
+                    ast::TableConstraint::Unique {
+                        is_primary: true,
+                        ..
+                    }
+                ) {
+                    ignored.push(format!("constraint `{constraint}`"));
+                }
+            }
+            if without_rowid {
+                ignored.push("WITHOUT ROWID".to_owned());
+            }
+            columns
+        }
+        // They are tables in `sqlite_master` too.
+        Ok(ast::Statement::CreateVirtualTable { .. }) => bail!("virtual tables are not supported"),
+        Ok(stmt) => bail!("unexpected definition `{stmt}`"),
+
+        // SQLite is much more permissive than `sqlparser`, e.g., the type of columns can be
+        // omitted.
+        Err(e) => {
+            ignored.push(format!(
+                "everything but column names and types (cannot parse: {e})"
+            ));
+            pragma
+                .iter()
+                .map(|(name, data_type, not_null, _)| {
+                    if data_type.is_empty() {
+                        bail!("column `{name}` has no type");
+                    }
+                    let data_type = Parser::new(&SQLiteDialect {})
+                        .try_with_sql(data_type)?
+                        .parse_data_type()?;
+                    let options = if *not_null {
+                        vec![ast::ColumnOptionDef {
+                            name: None,
+                            option: ast::ColumnOption::NotNull,
+                        }]
+                    } else {
+                        vec![]
+                    };
+
+                    Ok(ast::ColumnDef {
+                        name: ast::Ident::new(name),
+                        data_type,
+                        collation: None,
+                        options,
+                    })
+                })
+                .collect::<Result<_>>()?
+        }
+    };
+
+    for column in &mut columns {
+        let is_primary_key = pragma
+            .iter()
+            .any(|(name, _, _, pk)| *name == column.name.value && *pk > 0);
+        let has_primary_key = column
+            .options
+            .iter()
+            .any(|o| matches!(o.option, ast::ColumnOption::Unique { is_primary: true }));
+
+        if is_primary_key && !has_primary_key {
+            column.options.push(ast::ColumnOptionDef {
+                name: None,
+                option: ast::ColumnOption::Unique { is_primary: true },
+            });
+        }
+    }
+
+    Ok((columns, ignored))
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+    use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+    use sqlx::sqlite::SqliteConnectOptions;
+    use sqlx::{ConnectOptions, Connection, Executor};
+
+    use crate::catalog::Catalog;
+    use crate::diff::Schema;
+    use crate::entities::prelude::*;
+    use crate::entities::*;
+    use crate::output::Format;
+    use crate::{metastore, parser};
+
+    #[tokio::test]
+    async fn test_import_schema() {
+        let path = std::env::temp_dir().join(format!("import-{}.db", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        let mut conn = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true)
+            .connect()
+            .await
+            .unwrap();
+        conn.execute(
+            r#"CREATE TABLE "users" (id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT NOT NULL);
+               CREATE TABLE orders (
+                   user_id INT, seq INT, note VARCHAR(20),
+                   PRIMARY KEY (user_id, seq),
+                   FOREIGN KEY (user_id) REFERENCES users (id)
+               );
+               CREATE TABLE loose (a, b INT);
+               CREATE TABLE moods (id INT PRIMARY KEY, mood mood);
+               CREATE TABLE flagged (id INT PRIMARY KEY, flag INT, CHECK (flag IN (0, 1))) WITHOUT ROWID;
+               CREATE VIRTUAL TABLE docs USING fts5(title, body);
+               CREATE INDEX idx_note ON orders (note);
+               CREATE VIEW user_orders AS SELECT * FROM users JOIN orders ON users.id = orders.user_id;"#,
+        )
+        .await
+        .unwrap();
+        conn.close().await.unwrap();
+
+        let meta = metastore::connect(metastore::MEMORY_URL).await.unwrap();
+        let mut session = Catalog::new(meta.clone()).session("test");
+
+        let sql = format!("IMPORT SCHEMA FROM '{path}' INTO imported");
+        let stmt = parser::parse(&sql).unwrap().remove(0);
+        let report = session.handle(stmt).await.unwrap().unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        expect![[r#"
+             Object             | Status   | Detail
+            --------------------+----------+---------------------------------------------------------
+             table `users`      | imported |
+             table `orders`     | imported |
+             table `orders`     | ignored  | constraint `FOREIGN KEY (user_id) REFERENCES users(id)`
+             table `loose`      | skipped  | column `a` has no type
+             table `moods`      | imported |
+             table `flagged`    | imported |
+             table `flagged`    | ignored  | constraint `CHECK (flag IN (0, 1))`
+             table `flagged`    | ignored  | WITHOUT ROWID
+             table `docs`       | skipped  | virtual tables are not supported
+             index `idx_note`   | skipped  | index is not supported by the catalog
+             view `user_orders` | imported |
+            (11 rows)
+            imported 5 objects into `imported`, skipped 3
+        "#]]
+        .assert_eq(&report.render(Format::Table, false));
+
+        let db = MyDatabase::find()
+            .filter(my_database::Column::Name.eq("imported"))
+            .one(&meta)
+            .await
+            .unwrap()
+            .unwrap();
+        let schema = Schema::load(&meta, db.id).await.unwrap();
+
+        let mut out = String::new();
+        for (name, columns) in &schema.tables {
+            out += &format!("{name}:");
+            for c in columns {
+                out += &format!(
+                    " {} {}{}{},",
+                    c.name,
+                    c.data_type,
+                    if c.is_nullable { "" } else { " NOT NULL" },
+                    if c.is_primary_key { " PK" } else { "" }
+                );
+            }
+            out += "\n";
+        }
+        for (name, view) in &schema.views {
+            out += &format!("{name}: {:?}\n", view.dependencies);
+        }
+
+        expect![[r#"
+            flagged: id INT NOT NULL PK, flag INT,
+            moods: id INT NOT NULL PK, mood mood,
+            orders: user_id INT NOT NULL PK, seq INT NOT NULL PK, note VARCHAR(20),
+            users: id INTEGER NOT NULL PK, name TEXT NOT NULL,
+            user_orders: {"orders", "users"}
+        "#]]
+        .assert_eq(&out);
+    }
+}
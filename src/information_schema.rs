@@ -0,0 +1,738 @@
+//! The read-only relations of `information_schema`, mapped onto the metastore, and the evaluation
+//! of `SELECT` queries over them, so that tools can introspect the catalog with standard SQL.
+//!
+//! Unlike in Postgres, the relations cover all databases, which are the `*_catalog` columns, as
+//! sessions need not have one selected. Queries read a single relation: `WHERE`, `ORDER BY`,
+//! `LIMIT`, `OFFSET` and `DISTINCT` are supported, but not joins, grouping or subqueries.
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{bail, Context, Result};
+use sea_orm::{ConnectionTrait, EntityTrait, QueryOrder};
+use sqlparser::ast;
+
+use crate::entities::prelude::*;
+use crate::entities::*;
+use crate::error::NotFound;
+use crate::output::ResultSet;
+
+/// Name of the schema the relations are in, in every database.
+pub const SCHEMA: &str = "information_schema";
+
+/// Relations and their columns, as in the SQL standard.
+const RELATIONS: [(&str, &[&str]); 6] = [
+    ("schemata", &["catalog_name", "schema_name"]),
+    (
+        "tables",
+        &["table_catalog", "table_schema", "table_name", "table_type"],
+    ),
+    (
+        "columns",
+        &[
+            "table_catalog",
+            "table_schema",
+            "table_name",
+            "column_name",
+            "ordinal_position",
+            "is_nullable",
+            "data_type",
+            "column_default",
+        ],
+    ),
+    (
+        "views",
+        &[
+            "table_catalog",
+            "table_schema",
+            "table_name",
+            "view_definition",
+        ],
+    ),
+    (
+        "view_table_usage",
+        &[
+            "view_catalog",
+            "view_schema",
+            "view_name",
+            "table_catalog",
+            "table_schema",
+            "table_name",
+        ],
+    ),
+    (
+        "sequences",
+        &[
+            "sequence_catalog",
+            "sequence_schema",
+            "sequence_name",
+            "data_type",
+            "start_value",
+            "minimum_value",
+            "maximum_value",
+            "increment",
+            "cycle_option",
+        ],
+    ),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Text(String),
+}
+
+impl Value {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::Null => "null",
+            Value::Bool(_) => "boolean",
+            Value::Int(_) => "integer",
+            Value::Text(_) => "text",
+        }
+    }
+
+    fn render(&self) -> String {
+        match self {
+            Value::Null => String::new(),
+            Value::Bool(b) => b.to_string(),
+            Value::Int(i) => i.to_string(),
+            Value::Text(s) => s.clone(),
+        }
+    }
+
+    /// `None` if either is null, as comparisons with null are unknown.
+    fn compare(&self, other: &Value) -> Result<Option<Ordering>> {
+        Ok(match (self, other) {
+            (Value::Null, _) | (_, Value::Null) => None,
+            (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+            (Value::Int(a), Value::Int(b)) => Some(a.cmp(b)),
+            (Value::Text(a), Value::Text(b)) => Some(a.cmp(b)),
+            (a, b) => bail!("cannot compare {} with {}", a.type_name(), b.type_name()),
+        })
+    }
+
+    /// Total order for `ORDER BY`, with nulls last.
+    fn sort_cmp(&self, other: &Value) -> Ordering {
+        let rank = |v: &Value| match v {
+            Value::Bool(_) => 0,
+            Value::Int(_) => 1,
+            Value::Text(_) => 2,
+            Value::Null => 3,
+        };
+        match self.compare(other) {
+            Ok(Some(ordering)) => ordering,
+            _ => rank(self).cmp(&rank(other)),
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::Text(s.to_owned())
+    }
+}
+
+/// A relation materialized from the metastore.
+struct Relation {
+    /// Empty for queries without `FROM`.
+    name: &'static str,
+    columns: &'static [&'static str],
+    rows: Vec<Vec<Value>>,
+}
+
+/// Evaluate a `SELECT` over one of the relations, reading the metastore through `conn`.
+pub async fn query(conn: &impl ConnectionTrait, query: &ast::Query) -> Result<ResultSet> {
+    let select = match &*query.body {
+        ast::SetExpr::Select(select) if query.with.is_none() && query.fetch.is_none() => select,
+        _ => bail!("unsupported query: {query}"),
+    };
+    if select.top.is_some()
+        || select.into.is_some()
+        || !select.lateral_views.is_empty()
+        || select.group_by != ast::GroupByExpr::Expressions(vec![])
+        || select.having.is_some()
+        || !select.cluster_by.is_empty()
+        || !select.distribute_by.is_empty()
+        || !select.sort_by.is_empty()
+        || !select.named_window.is_empty()
+        || select.qualify.is_some()
+        || !query.locks.is_empty()
+        || matches!(select.distinct, Some(ast::Distinct::On(_)))
+    {
+        bail!("unsupported query: {query}");
+    }
+
+    let (relation, alias) = match select.from.as_slice() {
+        [] => {
+            let relation = Relation {
+                name: "",
+                columns: &[],
+                rows: vec![vec![]],
+            };
+            (relation, None)
+        }
+        [ast::TableWithJoins { relation, joins }] if joins.is_empty() => match relation {
+            ast::TableFactor::Table {
+                name,
+                alias,
+                args: None,
+                ..
+            } => (load(conn, name).await?, alias.as_ref()),
+            _ => bail!("unsupported relation: {relation}"),
+        },
+        _ => bail!("joins are not supported"),
+    };
+    let scope = Scope {
+        relation: &relation,
+        alias: alias.map(|a| a.name.value.as_str()),
+    };
+
+    // Output columns, with the expressions computing them.
+    let mut columns = Vec::new();
+    for item in &select.projection {
+        match item {
+            ast::SelectItem::UnnamedExpr(expr) => {
+                let name = match expr {
+                    ast::Expr::Identifier(ident) => normalize(ident),
+                    ast::Expr::CompoundIdentifier(idents) => normalize(idents.last().unwrap()),
+                    _ => "?column?".to_owned(),
+                };
+                columns.push((name, expr.clone()));
+            }
+            ast::SelectItem::ExprWithAlias { expr, alias } => {
+                columns.push((alias.value.clone(), expr.clone()));
+            }
+            ast::SelectItem::Wildcard(_) => {
+                for column in relation.columns {
+                    columns.push((column.to_string(), ast::Expr::Identifier(ident(column))));
+                }
+            }
+            ast::SelectItem::QualifiedWildcard(qualifier, _) => {
+                if !scope.qualifies(&qualifier.0) {
+                    bail!("missing FROM-clause entry for `{qualifier}`");
+                }
+                for column in relation.columns {
+                    columns.push((column.to_string(), ast::Expr::Identifier(ident(column))));
+                }
+            }
+        }
+    }
+
+    let mut rows = Vec::new();
+    for row in &relation.rows {
+        if let Some(selection) = &select.selection {
+            match scope.eval(selection, row)? {
+                Value::Bool(true) => {}
+                Value::Bool(false) | Value::Null => continue,
+                value => bail!(
+                    "argument of WHERE must be boolean, not {}",
+                    value.type_name()
+                ),
+            }
+        }
+        let output = columns
+            .iter()
+            .map(|(_, expr)| scope.eval(expr, row))
+            .collect::<Result<Vec<_>>>()?;
+        let mut keys = Vec::new();
+        for order_by in &query.order_by {
+            keys.push(sort_key(&scope, &order_by.expr, &columns, row, &output)?);
+        }
+        rows.push((keys, output));
+    }
+
+    if !query.order_by.is_empty() {
+        rows.sort_by(|(a, _), (b, _)| {
+            for ((a, b), order_by) in a.iter().zip(b).zip(&query.order_by) {
+                let desc = order_by.asc == Some(false);
+                // Nulls are the largest values, as in Postgres.
+                let nulls_first = order_by.nulls_first.unwrap_or(desc);
+                let ordering = match (a, b) {
+                    (Value::Null, Value::Null) => Ordering::Equal,
+                    (Value::Null, _) if nulls_first => Ordering::Less,
+                    (_, Value::Null) if nulls_first => Ordering::Greater,
+                    (Value::Null, _) => Ordering::Greater,
+                    (_, Value::Null) => Ordering::Less,
+                    (a, b) if desc => b.sort_cmp(a),
+                    (a, b) => a.sort_cmp(b),
+                };
+                if ordering.is_ne() {
+                    return ordering;
+                }
+            }
+            Ordering::Equal
+        });
+    }
+
+    let mut rows = rows.into_iter().map(|(_, row)| row).collect::<Vec<_>>();
+    if select.distinct.is_some() {
+        let mut seen = Vec::<Vec<Value>>::new();
+        rows.retain(|row| {
+            let new = !seen.contains(row);
+            if new {
+                seen.push(row.clone());
+            }
+            new
+        });
+    }
+
+    let offset = match &query.offset {
+        Some(offset) => count(&offset.value, "OFFSET")?,
+        None => 0,
+    };
+    let limit = match &query.limit {
+        Some(limit) => count(limit, "LIMIT")?,
+        None => usize::MAX,
+    };
+
+    let columns = columns
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>();
+    let rows = rows
+        .into_iter()
+        .skip(offset)
+        .take(limit)
+        .map(|row| row.iter().map(Value::render).collect())
+        .collect();
+    Ok(ResultSet::new(&columns, rows))
+}
+
+/// Materialize `information_schema.<relation>` from the metastore, ordered by name.
+async fn load(conn: &impl ConnectionTrait, name: &ast::ObjectName) -> Result<Relation> {
+    let parts = name.0.iter().map(normalize).collect::<Vec<_>>();
+    let relation = match parts.as_slice() {
+        [schema, relation] if schema == SCHEMA => RELATIONS.iter().find(|(r, _)| r == relation),
+        _ => bail!("only {SCHEMA} relations can be queried, not `{name}`"),
+    };
+    let Some(&(relation_name, columns)) = relation else {
+        return Err(NotFound::new("relation", name.to_string()).into());
+    };
+
+    let databases = MyDatabase::find()
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|d| (d.id, d.name))
+        .collect::<HashMap<_, _>>();
+    let schemas = MySchema::find()
+        .all(conn)
+        .await?
+        .into_iter()
+        .filter_map(|s| Some((s.id, (databases.get(&s.database_id)?.clone(), s.name))))
+        .collect::<HashMap<_, _>>();
+    // Tables and views may have the same name, hence the type.
+    let objects = MyObject::find()
+        .all(conn)
+        .await?
+        .into_iter()
+        .filter_map(|o| {
+            let (db, schema) = schemas.get(&o.schema_id)?.clone();
+            Some(((db, schema, o.name.clone(), o.r#type.clone()), o))
+        })
+        .collect::<BTreeMap<_, _>>();
+    let qualified = |(db, schema, name, _): &(String, String, String, String)| {
+        [db, schema, name].map(|s| Value::from(s.as_str()))
+    };
+
+    let mut rows = Vec::new();
+    match relation_name {
+        "schemata" => {
+            let mut schemas = schemas.into_values().collect::<Vec<_>>();
+            schemas.sort();
+            for (db, schema) in schemas {
+                rows.push(vec![Value::Text(db), Value::Text(schema)]);
+            }
+        }
+        "tables" => {
+            for (key, object) in &objects {
+                let table_type = match object.r#type.as_str() {
+                    "table" => "BASE TABLE",
+                    "view" => "VIEW",
+                    _ => continue,
+                };
+                rows.push([qualified(key).to_vec(), vec![table_type.into()]].concat());
+            }
+        }
+        "columns" => {
+            let mut columns = HashMap::<_, Vec<_>>::new();
+            for column in MyColumn::find()
+                .order_by_asc(my_column::Column::Id)
+                .all(conn)
+                .await?
+            {
+                columns.entry(column.table_id).or_default().push(column);
+            }
+            // Identity columns take their values from the sequence they own.
+            let names = objects
+                .values()
+                .map(|o| (o.id, o.name.as_str()))
+                .collect::<HashMap<_, _>>();
+            let mut defaults = HashMap::new();
+            for sequence in MySequence::find().all(conn).await? {
+                if let (Some(column_id), Some(name)) =
+                    (sequence.owned_by_column_id, names.get(&sequence.object_id))
+                {
+                    defaults.insert(column_id, format!("nextval('{name}')"));
+                }
+            }
+            for (key, object) in objects.iter().filter(|(_, o)| o.r#type == "table") {
+                let table_columns = columns.remove(&object.id).unwrap_or_default();
+                for (position, column) in table_columns.into_iter().enumerate() {
+                    let is_nullable = if column.is_nullable { "YES" } else { "NO" };
+                    let default = match defaults.remove(&column.id) {
+                        Some(default) => Value::Text(default),
+                        None => Value::Null,
+                    };
+                    let values = [
+                        Value::Text(column.name),
+                        Value::Int(position as i64 + 1),
+                        is_nullable.into(),
+                        Value::Text(column.data_type),
+                        default,
+                    ];
+                    rows.push([qualified(key).to_vec(), values.to_vec()].concat());
+                }
+            }
+        }
+        "views" => {
+            let mut views = MyView::find()
+                .all(conn)
+                .await?
+                .into_iter()
+                .map(|v| (v.object_id, v.definition))
+                .collect::<HashMap<_, _>>();
+            for (key, object) in objects.iter().filter(|(_, o)| o.r#type == "view") {
+                if let Some(definition) = views.remove(&object.id) {
+                    rows.push([qualified(key).to_vec(), vec![Value::Text(definition)]].concat());
+                }
+            }
+        }
+        "view_table_usage" => {
+            // Like in Postgres, materialized views are left out on both sides.
+            let keys = objects
+                .iter()
+                .filter(|(_, o)| o.r#type != "materialized_view")
+                .map(|(key, o)| (o.id, key))
+                .collect::<HashMap<_, _>>();
+            let mut usages = BTreeMap::<_, Vec<_>>::new();
+            for dependency in MyViewDependency::find().all(conn).await? {
+                let view = keys.get(&dependency.view_id);
+                let table = keys.get(&dependency.dependent_object_id);
+                if let (Some(view), Some(table)) = (view, table) {
+                    usages.entry(*view).or_default().push(*table);
+                }
+            }
+            for (view, mut tables) in usages {
+                tables.sort();
+                for table in tables {
+                    rows.push([qualified(view), qualified(table)].concat());
+                }
+            }
+        }
+        "sequences" => {
+            let mut sequences = MySequence::find()
+                .all(conn)
+                .await?
+                .into_iter()
+                .map(|s| (s.object_id, s))
+                .collect::<HashMap<_, _>>();
+            for (key, object) in objects.iter().filter(|(_, o)| o.r#type == "sequence") {
+                let Some(sequence) = sequences.remove(&object.id) else {
+                    continue;
+                };
+                let values = [
+                    Value::Text(sequence.data_type),
+                    Value::Int(sequence.start_value),
+                    Value::Int(sequence.min_value),
+                    Value::Int(sequence.max_value),
+                    Value::Int(sequence.increment),
+                    if sequence.cycle { "YES" } else { "NO" }.into(),
+                ];
+                rows.push([qualified(key).to_vec(), values.to_vec()].concat());
+            }
+        }
+        _ => unreachable!(),
+    }
+
+    Ok(Relation {
+        name: relation_name,
+        columns,
+        rows,
+    })
+}
+
+/// The value of a row to sort it by: the output column at a position or with a name, as in
+/// `ORDER BY 1` or `ORDER BY alias`, or else the expression evaluated over the input row.
+fn sort_key(
+    scope: &Scope,
+    expr: &ast::Expr,
+    columns: &[(String, ast::Expr)],
+    row: &[Value],
+    output: &[Value],
+) -> Result<Value> {
+    match expr {
+        ast::Expr::Value(ast::Value::Number(n, _)) => {
+            let position = n
+                .parse::<usize>()
+                .ok()
+                .filter(|p| (1..=output.len()).contains(p));
+            match position {
+                Some(position) => Ok(output[position - 1].clone()),
+                None => bail!("ORDER BY position {n} is not in select list"),
+            }
+        }
+        ast::Expr::Identifier(ident) => {
+            let name = normalize(ident);
+            match columns.iter().position(|(c, _)| *c == name) {
+                Some(i) => Ok(output[i].clone()),
+                None => scope.eval(expr, row),
+            }
+        }
+        _ => scope.eval(expr, row),
+    }
+}
+
+/// A non-negative constant, e.g., of `LIMIT`.
+fn count(expr: &ast::Expr, clause: &str) -> Result<usize> {
+    let scope = Scope {
+        relation: &Relation {
+            name: "",
+            columns: &[],
+            rows: vec![],
+        },
+        alias: None,
+    };
+    match scope.eval(expr, &[])? {
+        Value::Int(n) if n >= 0 => Ok(n as usize),
+        _ => bail!("{clause} must be a non-negative integer, not `{expr}`"),
+    }
+}
+
+/// Identifiers are case-insensitive unless quoted.
+fn normalize(ident: &ast::Ident) -> String {
+    match ident.quote_style {
+        Some(_) => ident.value.clone(),
+        None => ident.value.to_lowercase(),
+    }
+}
+
+fn ident(name: &str) -> ast::Ident {
+    ast::Ident::with_quote('"', name)
+}
+
+/// What names in expressions refer to.
+struct Scope<'a> {
+    relation: &'a Relation,
+    alias: Option<&'a str>,
+}
+
+impl Scope<'_> {
+    /// Whether `qualifier` names the relation, e.g., `c` in `c.table_name` if it's the alias, or
+    /// `information_schema.columns` otherwise.
+    fn qualifies(&self, qualifier: &[ast::Ident]) -> bool {
+        let qualifier = qualifier.iter().map(normalize).collect::<Vec<_>>();
+        match self.alias {
+            Some(alias) => qualifier == [alias],
+            None => qualifier == [self.relation.name] || qualifier == [SCHEMA, self.relation.name],
+        }
+    }
+
+    fn column(&self, idents: &[ast::Ident], row: &[Value]) -> Result<Value> {
+        let (name, qualifier) = idents.split_last().unwrap();
+        let name = normalize(name);
+        let position = self.relation.columns.iter().position(|c| *c == name);
+        match position {
+            Some(i) if qualifier.is_empty() || self.qualifies(qualifier) => Ok(row[i].clone()),
+            _ => {
+                let name = idents.iter().map(|i| i.value.as_str()).collect::<Vec<_>>();
+                Err(NotFound::new("column", name.join(".")).into())
+            }
+        }
+    }
+
+    fn eval(&self, expr: &ast::Expr, row: &[Value]) -> Result<Value> {
+        use ast::Expr;
+
+        let value = match expr {
+            Expr::Identifier(ident) => self.column(std::slice::from_ref(ident), row)?,
+            Expr::CompoundIdentifier(idents) => self.column(idents, row)?,
+            Expr::Value(value) => match value {
+                ast::Value::Number(n, _) => Value::Int(
+                    n.parse()
+                        .with_context(|| format!("unsupported number `{n}`"))?,
+                ),
+                ast::Value::SingleQuotedString(s) => Value::Text(s.clone()),
+                ast::Value::Boolean(b) => Value::Bool(*b),
+                ast::Value::Null => Value::Null,
+                _ => bail!("unsupported value `{value}`"),
+            },
+            Expr::Nested(expr) => self.eval(expr, row)?,
+            Expr::IsNull(expr) => Value::Bool(self.eval(expr, row)? == Value::Null),
+            Expr::IsNotNull(expr) => Value::Bool(self.eval(expr, row)? != Value::Null),
+            Expr::UnaryOp { op, expr } => match (op, self.eval(expr, row)?) {
+                (_, Value::Null) => Value::Null,
+                (ast::UnaryOperator::Not, Value::Bool(b)) => Value::Bool(!b),
+                (ast::UnaryOperator::Minus, Value::Int(i)) => Value::Int(-i),
+                (op, value) => bail!("operator does not exist: {op} {}", value.type_name()),
+            },
+            Expr::BinaryOp { left, op, right } => {
+                let (left, right) = (self.eval(left, row)?, self.eval(right, row)?);
+                binary_op(op, left, right)?
+            }
+            Expr::InList {
+                expr,
+                list,
+                negated,
+            } => {
+                let value = self.eval(expr, row)?;
+                // Like `=` for each element, `OR`ed.
+                let mut result = Value::Bool(false);
+                for item in list {
+                    let equal = binary_op(
+                        &ast::BinaryOperator::Eq,
+                        value.clone(),
+                        self.eval(item, row)?,
+                    )?;
+                    result = binary_op(&ast::BinaryOperator::Or, result, equal)?;
+                }
+                not_if(*negated, result)
+            }
+            Expr::Between {
+                expr,
+                negated,
+                low,
+                high,
+            } => {
+                let value = self.eval(expr, row)?;
+                let low = binary_op(
+                    &ast::BinaryOperator::GtEq,
+                    value.clone(),
+                    self.eval(low, row)?,
+                )?;
+                let high = binary_op(&ast::BinaryOperator::LtEq, value, self.eval(high, row)?)?;
+                not_if(*negated, binary_op(&ast::BinaryOperator::And, low, high)?)
+            }
+            Expr::Like {
+                negated,
+                expr,
+                pattern,
+                escape_char,
+            } => {
+                let (value, pattern) = (self.eval(expr, row)?, self.eval(pattern, row)?);
+                not_if(*negated, like(value, pattern, *escape_char, false)?)
+            }
+            Expr::ILike {
+                negated,
+                expr,
+                pattern,
+                escape_char,
+            } => {
+                let (value, pattern) = (self.eval(expr, row)?, self.eval(pattern, row)?);
+                not_if(*negated, like(value, pattern, *escape_char, true)?)
+            }
+            _ => bail!("unsupported expression `{expr}`"),
+        };
+        Ok(value)
+    }
+}
+
+fn not_if(negated: bool, value: Value) -> Value {
+    match value {
+        Value::Bool(b) => Value::Bool(b != negated),
+        value => value,
+    }
+}
+
+/// With the three-valued logic of SQL for nulls.
+fn binary_op(op: &ast::BinaryOperator, left: Value, right: Value) -> Result<Value> {
+    use ast::BinaryOperator::*;
+
+    let value = match (op, &left, &right) {
+        (And, Value::Bool(false), _) | (And, _, Value::Bool(false)) => Value::Bool(false),
+        (Or, Value::Bool(true), _) | (Or, _, Value::Bool(true)) => Value::Bool(true),
+        (And | Or, Value::Bool(_) | Value::Null, Value::Bool(_) | Value::Null) => {
+            match (&left, &right) {
+                (Value::Null, _) | (_, Value::Null) => Value::Null,
+                _ => Value::Bool(*op == And),
+            }
+        }
+        (Eq | NotEq | Lt | LtEq | Gt | GtEq, left, right) => match left.compare(right)? {
+            Some(ordering) => Value::Bool(match op {
+                Eq => ordering.is_eq(),
+                NotEq => ordering.is_ne(),
+                Lt => ordering.is_lt(),
+                LtEq => ordering.is_le(),
+                Gt => ordering.is_gt(),
+                _ => ordering.is_ge(),
+            }),
+            None => Value::Null,
+        },
+        (StringConcat, Value::Null, _) | (StringConcat, _, Value::Null) => Value::Null,
+        (StringConcat, left, right) => Value::Text(left.render() + &right.render()),
+        (op, left, right) => bail!(
+            "operator does not exist: {} {op} {}",
+            left.type_name(),
+            right.type_name()
+        ),
+    };
+    Ok(value)
+}
+
+/// `LIKE`, or `ILIKE` if `case_insensitive`: `%` matches any string and `_` any character,
+/// unless escaped by `escape_char`, a backslash by default.
+fn like(
+    value: Value,
+    pattern: Value,
+    escape_char: Option<char>,
+    case_insensitive: bool,
+) -> Result<Value> {
+    #[derive(PartialEq)]
+    enum Token {
+        Any,
+        One,
+        Char(char),
+    }
+
+    fn matches(value: &[char], pattern: &[Token]) -> bool {
+        match pattern.split_first() {
+            None => value.is_empty(),
+            Some((Token::Any, rest)) => (0..=value.len()).any(|i| matches(&value[i..], rest)),
+            Some((Token::One, rest)) => !value.is_empty() && matches(&value[1..], rest),
+            Some((Token::Char(c), rest)) => value.first() == Some(c) && matches(&value[1..], rest),
+        }
+    }
+
+    let (value, pattern) = match (value, pattern) {
+        (Value::Null, _) | (_, Value::Null) => return Ok(Value::Null),
+        (Value::Text(value), Value::Text(pattern)) if case_insensitive => {
+            (value.to_lowercase(), pattern.to_lowercase())
+        }
+        (Value::Text(value), Value::Text(pattern)) => (value, pattern),
+        (value, pattern) => bail!(
+            "operator does not exist: {} LIKE {}",
+            value.type_name(),
+            pattern.type_name()
+        ),
+    };
+
+    let escape_char = escape_char.unwrap_or('\\');
+    let mut tokens = Vec::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        tokens.push(match c {
+            c if c == escape_char => match chars.next() {
+                Some(c) => Token::Char(c),
+                None => bail!("LIKE pattern must not end with escape character"),
+            },
+            '%' => Token::Any,
+            '_' => Token::One,
+            c => Token::Char(c),
+        });
+    }
+    let value = value.chars().collect::<Vec<_>>();
+    Ok(Value::Bool(matches(&value, &tokens)))
+}
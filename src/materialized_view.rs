@@ -0,0 +1,407 @@
+//! Materialized views: views whose rows are stored, so that they have columns like a table, kept
+//! in `my_table` and `my_column`. The catalog holds no rows, hence refreshing a materialized view
+//! infers its columns again from the definition, as the objects it reads may have changed, and
+//! records when it happened.
+
+use std::collections::HashMap;
+use std::iter;
+use std::time::SystemTime;
+
+use anyhow::{bail, Context, Result};
+use sea_orm::prelude::{DateTimeUtc, DateTimeWithTimeZone};
+use sea_orm::sea_query::Expr;
+use sea_orm::ActiveValue::*;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder,
+};
+use sqlparser::ast;
+use sqlparser::dialect::GenericDialect;
+use sqlparser::parser::Parser;
+
+use crate::entities::prelude::*;
+use crate::entities::*;
+use crate::error::NotFound;
+
+/// Refreshed by `REFRESH MATERIALIZED VIEW` only. The default.
+pub const MANUAL: &str = "manual";
+/// Also refreshed when an object it reads, directly or through views, is altered.
+pub const AUTO: &str = "auto";
+
+/// The refresh mode set by `WITH (refresh_mode = '...')`.
+pub fn refresh_mode(options: &[ast::SqlOption]) -> Result<&'static str> {
+    let mut mode = MANUAL;
+    for option in options {
+        if !option.name.value.eq_ignore_ascii_case("refresh_mode") {
+            bail!("unknown option `{}`", option.name);
+        }
+        mode = match &option.value {
+            ast::Value::SingleQuotedString(s) if s == MANUAL => MANUAL,
+            ast::Value::SingleQuotedString(s) if s == AUTO => AUTO,
+            value => bail!("invalid refresh mode {value}, expected '{MANUAL}' or '{AUTO}'"),
+        };
+    }
+    Ok(mode)
+}
+
+/// A column of the rows returned by a query.
+#[derive(Debug, Clone)]
+struct OutputColumn {
+    name: String,
+    data_type: String,
+    /// The user-defined type, kept for columns selected as they are.
+    user_type_id: Option<i32>,
+}
+
+/// Store the columns and refresh mode of a new materialized view, whose object and definition
+/// exist already.
+pub async fn create(
+    conn: &impl ConnectionTrait,
+    object: &my_object::Model,
+    query: &ast::Query,
+    refresh_mode: &str,
+) -> Result<()> {
+    let columns = infer_columns(conn, object.database_id, query).await?;
+
+    my_table::ActiveModel {
+        object_id: Set(object.id),
+    }
+    .insert(conn)
+    .await?;
+    insert_columns(conn, object.id, columns).await?;
+    my_materialized_view::ActiveModel {
+        object_id: Set(object.id),
+        refresh_mode: Set(refresh_mode.to_owned()),
+        last_refreshed_at: Set(now()),
+    }
+    .insert(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Infer the columns of the materialized view again, and record that it's been refreshed.
+pub async fn refresh(conn: &impl ConnectionTrait, object: &my_object::Model) -> Result<()> {
+    let view = MyView::find_by_id(object.id)
+        .one(conn)
+        .await?
+        .context("materialized view definition not found")?;
+    let query = Parser::new(&GenericDialect {})
+        .try_with_sql(&view.definition)?
+        .parse_query()?;
+    let columns = infer_columns(conn, object.database_id, &query).await?;
+
+    MyColumn::delete_many()
+        .filter(my_column::Column::TableId.eq(object.id))
+        .exec(conn)
+        .await?;
+    insert_columns(conn, object.id, columns).await?;
+    MyMaterializedView::update_many()
+        .col_expr(
+            my_materialized_view::Column::LastRefreshedAt,
+            Expr::value(now()),
+        )
+        .filter(my_materialized_view::Column::ObjectId.eq(object.id))
+        .exec(conn)
+        .await?;
+
+    Ok(())
+}
+
+fn now() -> DateTimeWithTimeZone {
+    DateTimeUtc::from(SystemTime::now()).into()
+}
+
+/// Columns are stored nullable and without a primary key, as neither can be told from the query
+/// in general, e.g., with outer joins.
+async fn insert_columns(
+    conn: &impl ConnectionTrait,
+    object_id: i32,
+    columns: Vec<OutputColumn>,
+) -> Result<()> {
+    let columns = columns
+        .into_iter()
+        .map(|c| my_column::ActiveModel {
+            id: NotSet,
+            table_id: Set(object_id),
+            name: Set(c.name),
+            data_type: Set(c.data_type),
+            is_primary_key: Set(false),
+            is_nullable: Set(true),
+            user_type_id: Set(c.user_type_id),
+        })
+        .collect::<Vec<_>>();
+    if !columns.is_empty() {
+        MyColumn::insert_many(columns).exec(conn).await?;
+    }
+    Ok(())
+}
+
+/// The columns of the rows of `query`, whose references are qualified by their schema, as in the
+/// definitions of views. Their names must be unique.
+async fn infer_columns(
+    conn: &impl ConnectionTrait,
+    db_id: i32,
+    query: &ast::Query,
+) -> Result<Vec<OutputColumn>> {
+    let relations = Relations::load(conn, db_id).await?;
+    let columns = relations.infer(query, 0)?;
+
+    let mut names = Vec::new();
+    for column in &columns {
+        if names.contains(&&column.name) {
+            bail!("column `{}` specified more than once", column.name);
+        }
+        names.push(&column.name);
+    }
+    Ok(columns)
+}
+
+/// How deep views may be nested in each other, against cycles in a corrupt catalog.
+const MAX_DEPTH: usize = 64;
+
+/// What a relation of a database is made of.
+enum Source {
+    /// The columns of a table or materialized view.
+    Columns(Vec<OutputColumn>),
+    /// The definition of a view, whose columns are inferred when needed.
+    View(String),
+}
+
+/// Relations of a database, by schema and name.
+struct Relations(HashMap<(String, String), Source>);
+
+/// A relation in the `FROM` clause of a query: the name its columns can be qualified with, and
+/// the columns.
+type FromItem = (String, Vec<OutputColumn>);
+
+impl Relations {
+    async fn load(conn: &impl ConnectionTrait, db_id: i32) -> Result<Self> {
+        let objects = MyObject::find()
+            .filter(my_object::Column::DatabaseId.eq(db_id))
+            .find_also_related(MySchema)
+            .all(conn)
+            .await?;
+        let ids = objects.iter().map(|(o, _)| o.id).collect::<Vec<_>>();
+
+        let mut columns = HashMap::<_, Vec<_>>::new();
+        for column in MyColumn::find()
+            .filter(my_column::Column::TableId.is_in(ids.clone()))
+            .order_by_asc(my_column::Column::Id)
+            .all(conn)
+            .await?
+        {
+            columns
+                .entry(column.table_id)
+                .or_default()
+                .push(OutputColumn {
+                    name: column.name,
+                    data_type: column.data_type,
+                    user_type_id: column.user_type_id,
+                });
+        }
+        let mut views = MyView::find()
+            .filter(my_view::Column::ObjectId.is_in(ids))
+            .all(conn)
+            .await?
+            .into_iter()
+            .map(|v| (v.object_id, v.definition))
+            .collect::<HashMap<_, _>>();
+
+        let mut relations = HashMap::new();
+        for (object, schema) in objects {
+            let Some(schema) = schema else { continue };
+            let source = match object.r#type.as_str() {
+                "table" | "materialized_view" => {
+                    Source::Columns(columns.remove(&object.id).unwrap_or_default())
+                }
+                "view" => match views.remove(&object.id) {
+                    Some(definition) => Source::View(definition),
+                    None => continue,
+                },
+                _ => continue,
+            };
+            relations.insert((schema.name, object.name), source);
+        }
+        Ok(Self(relations))
+    }
+
+    fn columns(&self, name: &ast::ObjectName, depth: usize) -> Result<Vec<OutputColumn>> {
+        let source = match &name.0[..] {
+            [schema, name] => self.0.get(&(schema.value.clone(), name.value.clone())),
+            _ => None,
+        };
+        match source.with_context(|| NotFound::new("relation", name.to_string()))? {
+            Source::Columns(columns) => Ok(columns.clone()),
+            Source::View(definition) => {
+                let query = Parser::new(&GenericDialect {})
+                    .try_with_sql(definition)?
+                    .parse_query()?;
+                self.infer(&query, depth + 1)
+                    .with_context(|| format!("cannot infer the columns of view `{name}`"))
+            }
+        }
+    }
+
+    fn infer(&self, query: &ast::Query, depth: usize) -> Result<Vec<OutputColumn>> {
+        if depth > MAX_DEPTH {
+            bail!("views are nested too deeply");
+        }
+        self.infer_body(&query.body, depth)
+    }
+
+    fn infer_body(&self, body: &ast::SetExpr, depth: usize) -> Result<Vec<OutputColumn>> {
+        match body {
+            ast::SetExpr::Select(select) => self.infer_select(select, depth),
+            ast::SetExpr::Query(query) => self.infer(query, depth),
+            // Named and typed after the first query, as in Postgres.
+            ast::SetExpr::SetOperation { left, .. } => self.infer_body(left, depth),
+            _ => bail!("cannot infer the columns of `{body}`"),
+        }
+    }
+
+    fn infer_select(&self, select: &ast::Select, depth: usize) -> Result<Vec<OutputColumn>> {
+        let mut from = Vec::new();
+        for table in &select.from {
+            let joined = table.joins.iter().map(|j| &j.relation);
+            for relation in iter::once(&table.relation).chain(joined) {
+                from.push(self.relation(relation, depth)?);
+            }
+        }
+
+        let mut columns = Vec::new();
+        for item in &select.projection {
+            match item {
+                ast::SelectItem::Wildcard(_) => {
+                    columns.extend(from.iter().flat_map(|(_, c)| c.iter().cloned()));
+                }
+                ast::SelectItem::QualifiedWildcard(name, _) => {
+                    let qualifier = &name.0.last().unwrap().value;
+                    let (_, item) = from
+                        .iter()
+                        .find(|(q, _)| q == qualifier)
+                        .with_context(|| NotFound::new("relation", name.to_string()))?;
+                    columns.extend(item.iter().cloned());
+                }
+                ast::SelectItem::UnnamedExpr(expr) => {
+                    columns.push(output_column(&from, column_name(expr), expr)?)
+                }
+                ast::SelectItem::ExprWithAlias { expr, alias } => {
+                    columns.push(output_column(&from, alias.value.clone(), expr)?)
+                }
+            }
+        }
+        Ok(columns)
+    }
+
+    fn relation(&self, relation: &ast::TableFactor, depth: usize) -> Result<FromItem> {
+        match relation {
+            ast::TableFactor::Table { name, alias, .. } => {
+                let qualifier = match alias {
+                    Some(alias) => alias.name.value.clone(),
+                    None => name.0.last().unwrap().value.clone(),
+                };
+                Ok((qualifier, self.columns(name, depth)?))
+            }
+            ast::TableFactor::Derived {
+                subquery,
+                alias: Some(alias),
+                ..
+            } => Ok((alias.name.value.clone(), self.infer(subquery, depth)?)),
+            _ => bail!("cannot infer the columns of `{relation}`"),
+        }
+    }
+}
+
+/// The name of the column computed by `expr`, as chosen by Postgres.
+fn column_name(expr: &ast::Expr) -> String {
+    match expr {
+        ast::Expr::Identifier(ident) => ident.value.clone(),
+        ast::Expr::CompoundIdentifier(idents) => idents.last().unwrap().value.clone(),
+        ast::Expr::Cast { expr, .. } | ast::Expr::Nested(expr) => column_name(expr),
+        ast::Expr::Function(function) => function.name.0.last().unwrap().value.to_lowercase(),
+        _ => "?column?".to_owned(),
+    }
+}
+
+fn output_column(from: &[FromItem], name: String, expr: &ast::Expr) -> Result<OutputColumn> {
+    Ok(OutputColumn {
+        name,
+        data_type: data_type(from, expr)?,
+        user_type_id: column(from, expr)?.and_then(|c| c.user_type_id),
+    })
+}
+
+/// The column `expr` refers to, if it's a column reference.
+fn column<'a>(from: &'a [FromItem], expr: &ast::Expr) -> Result<Option<&'a OutputColumn>> {
+    match expr {
+        ast::Expr::Identifier(ident) => {
+            let found = from
+                .iter()
+                .filter_map(|(_, columns)| columns.iter().find(|c| c.name == ident.value))
+                .collect::<Vec<_>>();
+            match found.as_slice() {
+                [column] => Ok(Some(column)),
+                [] => Err(NotFound::new("column", &ident.value).into()),
+                _ => bail!("column reference `{ident}` is ambiguous"),
+            }
+        }
+        ast::Expr::CompoundIdentifier(idents) => {
+            let [.., qualifier, name] = idents.as_slice() else {
+                unreachable!("compound identifiers have several parts")
+            };
+            from.iter()
+                .find(|(q, _)| *q == qualifier.value)
+                .and_then(|(_, columns)| columns.iter().find(|c| c.name == name.value))
+                .map(Some)
+                .with_context(|| NotFound::new("column", expr.to_string()))
+        }
+        ast::Expr::Nested(expr) => column(from, expr),
+        _ => Ok(None),
+    }
+}
+
+/// The type of the values of `expr`, when it can be told without evaluating it.
+fn data_type(from: &[FromItem], expr: &ast::Expr) -> Result<String> {
+    use ast::BinaryOperator::*;
+    use ast::Expr;
+
+    if let Some(column) = column(from, expr)? {
+        return Ok(column.data_type.clone());
+    }
+    let data_type = match expr {
+        Expr::Value(ast::Value::Number(n, _)) if n.contains(['.', 'e', 'E']) => "NUMERIC".into(),
+        Expr::Value(ast::Value::Number(..)) => "INT".into(),
+        Expr::Value(ast::Value::SingleQuotedString(_)) => "TEXT".into(),
+        Expr::Value(ast::Value::Boolean(_)) => "BOOLEAN".into(),
+        Expr::Cast { data_type, .. } | Expr::TryCast { data_type, .. } => data_type.to_string(),
+        Expr::Nested(expr) => data_type(from, expr)?,
+        Expr::Function(function) => {
+            let name = function.name.to_string().to_lowercase();
+            match (name.as_str(), function.args.as_slice()) {
+                ("count", _) => "BIGINT".into(),
+                ("min" | "max", [ast::FunctionArg::Unnamed(ast::FunctionArgExpr::Expr(arg))]) => {
+                    data_type(from, arg)?
+                }
+                _ => bail!("cannot infer the type of `{expr}`, add a CAST"),
+            }
+        }
+        Expr::BinaryOp { left, op, .. } => match op {
+            Eq | NotEq | Lt | LtEq | Gt | GtEq | And | Or => "BOOLEAN".into(),
+            StringConcat => "TEXT".into(),
+            Plus | Minus | Multiply | Divide | Modulo => data_type(from, left)?,
+            _ => bail!("cannot infer the type of `{expr}`, add a CAST"),
+        },
+        Expr::IsNull(_)
+        | Expr::IsNotNull(_)
+        | Expr::InList { .. }
+        | Expr::Between { .. }
+        | Expr::Like { .. }
+        | Expr::ILike { .. }
+        | Expr::UnaryOp {
+            op: ast::UnaryOperator::Not,
+            ..
+        } => "BOOLEAN".into(),
+        _ => bail!("cannot infer the type of `{expr}`, add a CAST"),
+    };
+    Ok(data_type)
+}
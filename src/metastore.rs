@@ -0,0 +1,78 @@
+//! Connection to the metastore, i.e., the database where the catalog itself is stored.
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use sea_orm::{Database, DatabaseConnection, SqlxSqliteConnector};
+use sea_orm_migration::MigratorTrait;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+
+use crate::migrator::Migrator;
+
+/// The SQLite file next to the executable, created if missing.
+pub const DEFAULT_URL: &str = "sqlite:./sqlite.db?mode=rwc";
+
+/// A fresh metastore that lives only as long as the connection. Mostly for tests.
+#[cfg_attr(not(test), allow(dead_code))]
+pub const MEMORY_URL: &str = "sqlite::memory:";
+
+/// How long SQLite waits for a lock held by another connection, e.g., of another process, before
+/// failing with `SQLITE_BUSY`.
+const SQLITE_BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Connections to a SQLite file. Only one of them writes at a time, see
+/// [`Catalog::write_lock`](crate::catalog::Catalog::write_lock), the others serve readers.
+const SQLITE_MAX_CONNECTIONS: u32 = 4;
+
+/// Connect to the metastore at `url` and bring its schema up to date.
+pub async fn connect(url: &str) -> Result<DatabaseConnection> {
+    let db = open(url).await?;
+
+    Migrator::up(&db, None).await?; // this is idempotent and used in production
+
+    Ok(db)
+}
+
+/// Connect to the metastore at `url` as is, without applying any migrations.
+///
+/// The backend is chosen by the scheme of the URL. Backends other than SQLite must be enabled with
+/// the `sqlx-postgres` or `sqlx-mysql` features.
+pub async fn open(url: &str) -> Result<DatabaseConnection> {
+    let scheme = url.split_once(':').map_or(url, |(scheme, _)| scheme);
+    match scheme {
+        "sqlite" => return open_sqlite(url).await,
+        "postgres" | "postgresql" if cfg!(feature = "sqlx-postgres") => {}
+        "mysql" if cfg!(feature = "sqlx-mysql") => {}
+        "postgres" | "postgresql" | "mysql" => bail!(
+            "metastore backend `{scheme}` is not enabled, rebuild with `--features sqlx-{}`",
+            if scheme == "mysql" {
+                "mysql"
+            } else {
+                "postgres"
+            }
+        ),
+        _ => bail!("unsupported metastore URL `{url}`"),
+    }
+
+    Ok(Database::connect(url).await?)
+}
+
+async fn open_sqlite(url: &str) -> Result<DatabaseConnection> {
+    let options = SqliteConnectOptions::from_str(url)?.busy_timeout(SQLITE_BUSY_TIMEOUT);
+
+    // Each connection to `:memory:` would be a different database, so there must be exactly one,
+    // kept for the lifetime of the pool.
+    let pool = if url.contains(":memory:") || url.contains("mode=memory") {
+        SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+    } else {
+        SqlitePoolOptions::new().max_connections(SQLITE_MAX_CONNECTIONS)
+    };
+
+    Ok(SqlxSqliteConnector::from_sqlx_sqlite_pool(
+        pool.connect_with(options).await?,
+    ))
+}
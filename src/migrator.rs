@@ -1,12 +1,459 @@
 use sea_orm_migration::prelude::*;
 
 mod m20231019_114514_toydb_init;
+mod m20261018_093000_column_nullability;
+mod m20261018_140000_object_version;
+mod m20261018_160000_catalog_version;
+mod m20261018_180000_schema;
+mod m20261018_200000_materialized_view;
+mod m20261018_210000_sequence;
+mod m20261018_220000_user_type;
+mod m20261018_230000_view_definition_text;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20231019_114514_toydb_init::Migration)]
+        vec![
+            Box::new(m20231019_114514_toydb_init::Migration),
+            Box::new(m20261018_093000_column_nullability::Migration),
+            Box::new(m20261018_140000_object_version::Migration),
+            Box::new(m20261018_160000_catalog_version::Migration),
+            Box::new(m20261018_180000_schema::Migration),
+            Box::new(m20261018_200000_materialized_view::Migration),
+            Box::new(m20261018_210000_sequence::Migration),
+            Box::new(m20261018_220000_user_type::Migration),
+            Box::new(m20261018_230000_view_definition_text::Migration),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use std::sync::{Arc, Mutex};
+
+    use expect_test::{expect, Expect};
+    use sea_orm::{
+        ColumnTrait, ConnectionTrait, Database, DatabaseBackend, DatabaseConnection, DbErr,
+        EntityTrait, Iterable, PrimaryKeyToColumn, ProxyDatabaseTrait, ProxyExecResult, ProxyRow,
+        Statement,
+    };
+
+    use super::*;
+    use crate::entities::prelude::*;
+    use crate::metastore;
+
+    /// Records the statements instead of executing them.
+    #[derive(Debug, Default, Clone)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl ProxyDatabaseTrait for Recorder {
+        fn query(&self, statement: Statement) -> Result<Vec<ProxyRow>, DbErr> {
+            self.0.lock().unwrap().push(statement.to_string());
+            Ok(vec![])
+        }
+
+        fn execute(&self, statement: Statement) -> Result<ProxyExecResult, DbErr> {
+            self.0.lock().unwrap().push(statement.to_string());
+            Ok(ProxyExecResult::default())
+        }
+    }
+
+    /// Check the DDL generated by all migrations for the given backend.
+    async fn check(backend: DatabaseBackend, expect: Expect) {
+        let recorder = Recorder::default();
+        let db = Database::connect_proxy(backend, Arc::new(Mutex::new(Box::new(recorder.clone()))))
+            .await
+            .unwrap();
+
+        let manager = SchemaManager::new(&db);
+        for migration in Migrator::migrations() {
+            migration.up(&manager).await.unwrap();
+        }
+
+        let statements = recorder.0.lock().unwrap().join(";\n");
+        expect.assert_eq(&statements);
+    }
+
+    #[tokio::test]
+    async fn test_sqlite() {
+        check(DatabaseBackend::Sqlite, expect![[r#"
+            CREATE TABLE "my_object" ( "id" integer NOT NULL PRIMARY KEY AUTOINCREMENT, "type" text NOT NULL, "name" text NOT NULL, "database_id" integer NOT NULL, UNIQUE ("database_id", "type", "name") );
+            CREATE TABLE "my_database" ( "id" integer NOT NULL PRIMARY KEY AUTOINCREMENT, "name" text NOT NULL UNIQUE );
+            CREATE TABLE "my_table" ( "object_id" integer NOT NULL PRIMARY KEY, FOREIGN KEY ("object_id") REFERENCES "my_object" ("id") ON DELETE CASCADE ON UPDATE CASCADE );
+            CREATE TABLE "my_view" ( "object_id" integer NOT NULL PRIMARY KEY, "definition" text NOT NULL, FOREIGN KEY ("object_id") REFERENCES "my_object" ("id") ON DELETE CASCADE ON UPDATE CASCADE );
+            CREATE TABLE "my_column" ( "id" integer NOT NULL PRIMARY KEY AUTOINCREMENT, "table_id" integer NOT NULL, "name" text NOT NULL, "data_type" text NOT NULL, "is_primary_key" boolean NOT NULL, UNIQUE ("table_id", "name"), FOREIGN KEY ("table_id") REFERENCES "my_table" ("object_id") ON DELETE CASCADE ON UPDATE CASCADE );
+            CREATE TABLE "my_view_dependency" ( "view_id" integer NOT NULL, "dependent_object_id" integer NOT NULL, PRIMARY KEY ("view_id", "dependent_object_id"), FOREIGN KEY ("view_id") REFERENCES "my_view" ("object_id") ON DELETE CASCADE ON UPDATE CASCADE, FOREIGN KEY ("dependent_object_id") REFERENCES "my_object" ("id") ON DELETE RESTRICT ON UPDATE CASCADE );
+            ALTER TABLE "my_column" ADD COLUMN "is_nullable" boolean NOT NULL DEFAULT TRUE;
+            UPDATE "my_column" SET "is_nullable" = FALSE WHERE "is_primary_key" = TRUE;
+            ALTER TABLE "my_object" ADD COLUMN "version" integer NOT NULL DEFAULT 1;
+            CREATE TABLE "my_catalog" ( "id" integer NOT NULL PRIMARY KEY, "version" integer NOT NULL DEFAULT 0 );
+            INSERT INTO "my_catalog" ("id") VALUES (1);
+            CREATE TABLE "my_schema" ( "id" integer NOT NULL PRIMARY KEY AUTOINCREMENT, "database_id" integer NOT NULL, "name" text NOT NULL, UNIQUE ("database_id", "name"), FOREIGN KEY ("database_id") REFERENCES "my_database" ("id") ON DELETE CASCADE ON UPDATE CASCADE );
+            INSERT INTO "my_schema" ("database_id", "name") SELECT "id", 'public' FROM "my_database";
+            PRAGMA foreign_keys = OFF;
+            BEGIN;
+            CREATE TABLE "my_object_new" ( "id" integer NOT NULL PRIMARY KEY AUTOINCREMENT, "type" text NOT NULL, "name" text NOT NULL, "database_id" integer NOT NULL, "version" integer NOT NULL DEFAULT 1, "schema_id" integer NOT NULL, UNIQUE ("schema_id", "type", "name"), FOREIGN KEY ("schema_id") REFERENCES "my_schema" ("id") ON DELETE CASCADE ON UPDATE CASCADE );
+            INSERT INTO "my_object_new" ("id", "type", "name", "database_id", "version", "schema_id") SELECT "my_object"."id", "my_object"."type", "my_object"."name", "my_object"."database_id", "my_object"."version", (SELECT "id" FROM "my_schema" WHERE "my_schema"."database_id" = "my_object"."database_id" AND "my_schema"."name" = 'public') FROM "my_object";
+            DROP TABLE "my_object";
+            ALTER TABLE "my_object_new" RENAME TO "my_object";
+            COMMIT;
+            PRAGMA foreign_keys = ON;
+            CREATE TABLE "my_materialized_view" ( "object_id" integer NOT NULL PRIMARY KEY, "refresh_mode" text NOT NULL, "last_refreshed_at" text NOT NULL, FOREIGN KEY ("object_id") REFERENCES "my_object" ("id") ON DELETE CASCADE ON UPDATE CASCADE );
+            CREATE TABLE "my_sequence" ( "object_id" integer NOT NULL PRIMARY KEY, "data_type" text NOT NULL, "start_value" bigint NOT NULL, "increment" bigint NOT NULL, "min_value" bigint NOT NULL, "max_value" bigint NOT NULL, "cycle" boolean NOT NULL, "current_value" bigint NOT NULL, "owned_by_column_id" integer, UNIQUE ("owned_by_column_id"), FOREIGN KEY ("object_id") REFERENCES "my_object" ("id") ON DELETE CASCADE ON UPDATE CASCADE, FOREIGN KEY ("owned_by_column_id") REFERENCES "my_column" ("id") ON DELETE SET NULL ON UPDATE CASCADE );
+            CREATE TABLE "my_type" ( "object_id" integer NOT NULL PRIMARY KEY, "kind" text NOT NULL, "base_type" text, "base_type_id" integer, "is_nullable" boolean NOT NULL DEFAULT TRUE, "default_expr" text, "check_expr" text, FOREIGN KEY ("object_id") REFERENCES "my_object" ("id") ON DELETE CASCADE ON UPDATE CASCADE, FOREIGN KEY ("base_type_id") REFERENCES "my_type" ("object_id") ON DELETE NO ACTION ON UPDATE CASCADE );
+            CREATE TABLE "my_enum_label" ( "type_id" integer NOT NULL, "position" integer NOT NULL, "label" text NOT NULL, PRIMARY KEY ("type_id", "position"), UNIQUE ("type_id", "label"), FOREIGN KEY ("type_id") REFERENCES "my_type" ("object_id") ON DELETE CASCADE ON UPDATE CASCADE );
+            CREATE TABLE "my_type_attribute" ( "type_id" integer NOT NULL, "position" integer NOT NULL, "name" text NOT NULL, "data_type" text NOT NULL, "user_type_id" integer, PRIMARY KEY ("type_id", "position"), UNIQUE ("type_id", "name"), FOREIGN KEY ("type_id") REFERENCES "my_type" ("object_id") ON DELETE CASCADE ON UPDATE CASCADE, FOREIGN KEY ("user_type_id") REFERENCES "my_type" ("object_id") ON DELETE NO ACTION ON UPDATE CASCADE );
+            ALTER TABLE "my_column" ADD COLUMN "user_type_id" integer REFERENCES "my_type" ("object_id") ON DELETE NO ACTION ON UPDATE CASCADE;
+            PRAGMA foreign_keys = OFF;
+            BEGIN;
+            CREATE TABLE "my_view_new" ( "object_id" integer NOT NULL PRIMARY KEY, "definition" text NOT NULL, FOREIGN KEY ("object_id") REFERENCES "my_object" ("id") ON DELETE CASCADE ON UPDATE CASCADE );
+            INSERT INTO "my_view_new" ("object_id", "definition") SELECT "object_id", "definition" FROM "my_view";
+            DROP TABLE "my_view";
+            ALTER TABLE "my_view_new" RENAME TO "my_view";
+            COMMIT;
+            PRAGMA foreign_keys = ON"#]]).await;
+    }
+
+    #[tokio::test]
+    async fn test_postgres() {
+        check(DatabaseBackend::Postgres, expect![[r#"
+            CREATE TABLE "my_object" ( "id" serial NOT NULL PRIMARY KEY, "type" varchar NOT NULL, "name" varchar NOT NULL, "database_id" integer NOT NULL, UNIQUE ("database_id", "type", "name") );
+            CREATE TABLE "my_database" ( "id" serial NOT NULL PRIMARY KEY, "name" varchar NOT NULL UNIQUE );
+            CREATE TABLE "my_table" ( "object_id" integer NOT NULL PRIMARY KEY, CONSTRAINT "fk_table_object_id" FOREIGN KEY ("object_id") REFERENCES "my_object" ("id") ON DELETE CASCADE ON UPDATE CASCADE );
+            CREATE TABLE "my_view" ( "object_id" integer NOT NULL PRIMARY KEY, "definition" varchar NOT NULL, CONSTRAINT "fk_view_object_id" FOREIGN KEY ("object_id") REFERENCES "my_object" ("id") ON DELETE CASCADE ON UPDATE CASCADE );
+            CREATE TABLE "my_column" ( "id" serial NOT NULL PRIMARY KEY, "table_id" integer NOT NULL, "name" varchar NOT NULL, "data_type" varchar NOT NULL, "is_primary_key" bool NOT NULL, UNIQUE ("table_id", "name"), CONSTRAINT "fk_column_table_id" FOREIGN KEY ("table_id") REFERENCES "my_table" ("object_id") ON DELETE CASCADE ON UPDATE CASCADE );
+            CREATE TABLE "my_view_dependency" ( "view_id" integer NOT NULL, "dependent_object_id" integer NOT NULL, PRIMARY KEY ("view_id", "dependent_object_id"), CONSTRAINT "fk_view_dependency_view_id" FOREIGN KEY ("view_id") REFERENCES "my_view" ("object_id") ON DELETE CASCADE ON UPDATE CASCADE, CONSTRAINT "fk_view_dependency_dependenct_object_id" FOREIGN KEY ("dependent_object_id") REFERENCES "my_object" ("id") ON DELETE RESTRICT ON UPDATE CASCADE );
+            ALTER TABLE "my_column" ADD COLUMN "is_nullable" bool NOT NULL DEFAULT TRUE;
+            UPDATE "my_column" SET "is_nullable" = FALSE WHERE "is_primary_key" = TRUE;
+            ALTER TABLE "my_object" ADD COLUMN "version" integer NOT NULL DEFAULT 1;
+            CREATE TABLE "my_catalog" ( "id" integer NOT NULL PRIMARY KEY, "version" integer NOT NULL DEFAULT 0 );
+            INSERT INTO "my_catalog" ("id") VALUES (1);
+            CREATE TABLE "my_schema" ( "id" serial NOT NULL PRIMARY KEY, "database_id" integer NOT NULL, "name" varchar NOT NULL, UNIQUE ("database_id", "name"), CONSTRAINT "fk_schema_database_id" FOREIGN KEY ("database_id") REFERENCES "my_database" ("id") ON DELETE CASCADE ON UPDATE CASCADE );
+            INSERT INTO "my_schema" ("database_id", "name") SELECT "id", 'public' FROM "my_database";
+            ALTER TABLE "my_object" ADD COLUMN "schema_id" integer;
+            UPDATE "my_object" SET "schema_id" = (SELECT "id" FROM "my_schema" WHERE "my_schema"."database_id" = "my_object"."database_id" AND "my_schema"."name" = 'public');
+            ALTER TABLE "my_object" ALTER COLUMN "schema_id" TYPE integer, ALTER COLUMN "schema_id" SET NOT NULL, ADD CONSTRAINT "fk_object_schema_id" FOREIGN KEY ("schema_id") REFERENCES "my_schema" ("id") ON DELETE CASCADE ON UPDATE CASCADE;
+            ALTER TABLE "my_object" DROP CONSTRAINT "my_object_database_id_type_name_key";
+            CREATE UNIQUE INDEX "idx_object_schema_id_type_name" ON "my_object" ("schema_id", "type", "name");
+            CREATE TABLE "my_materialized_view" ( "object_id" integer NOT NULL PRIMARY KEY, "refresh_mode" varchar NOT NULL, "last_refreshed_at" timestamp with time zone NOT NULL, CONSTRAINT "fk_materialized_view_object_id" FOREIGN KEY ("object_id") REFERENCES "my_object" ("id") ON DELETE CASCADE ON UPDATE CASCADE );
+            CREATE TABLE "my_sequence" ( "object_id" integer NOT NULL PRIMARY KEY, "data_type" varchar NOT NULL, "start_value" bigint NOT NULL, "increment" bigint NOT NULL, "min_value" bigint NOT NULL, "max_value" bigint NOT NULL, "cycle" bool NOT NULL, "current_value" bigint NOT NULL, "owned_by_column_id" integer, UNIQUE ("owned_by_column_id"), CONSTRAINT "fk_sequence_object_id" FOREIGN KEY ("object_id") REFERENCES "my_object" ("id") ON DELETE CASCADE ON UPDATE CASCADE, CONSTRAINT "fk_sequence_owned_by_column_id" FOREIGN KEY ("owned_by_column_id") REFERENCES "my_column" ("id") ON DELETE SET NULL ON UPDATE CASCADE );
+            CREATE TABLE "my_type" ( "object_id" integer NOT NULL PRIMARY KEY, "kind" varchar NOT NULL, "base_type" varchar, "base_type_id" integer, "is_nullable" bool NOT NULL DEFAULT TRUE, "default_expr" varchar, "check_expr" varchar, CONSTRAINT "fk_type_object_id" FOREIGN KEY ("object_id") REFERENCES "my_object" ("id") ON DELETE CASCADE ON UPDATE CASCADE, CONSTRAINT "fk_type_base_type_id" FOREIGN KEY ("base_type_id") REFERENCES "my_type" ("object_id") ON DELETE NO ACTION ON UPDATE CASCADE );
+            CREATE TABLE "my_enum_label" ( "type_id" integer NOT NULL, "position" integer NOT NULL, "label" varchar NOT NULL, PRIMARY KEY ("type_id", "position"), UNIQUE ("type_id", "label"), CONSTRAINT "fk_enum_label_type_id" FOREIGN KEY ("type_id") REFERENCES "my_type" ("object_id") ON DELETE CASCADE ON UPDATE CASCADE );
+            CREATE TABLE "my_type_attribute" ( "type_id" integer NOT NULL, "position" integer NOT NULL, "name" varchar NOT NULL, "data_type" varchar NOT NULL, "user_type_id" integer, PRIMARY KEY ("type_id", "position"), UNIQUE ("type_id", "name"), CONSTRAINT "fk_type_attribute_type_id" FOREIGN KEY ("type_id") REFERENCES "my_type" ("object_id") ON DELETE CASCADE ON UPDATE CASCADE, CONSTRAINT "fk_type_attribute_user_type_id" FOREIGN KEY ("user_type_id") REFERENCES "my_type" ("object_id") ON DELETE NO ACTION ON UPDATE CASCADE );
+            ALTER TABLE "my_column" ADD COLUMN "user_type_id" integer, ADD CONSTRAINT "fk_column_user_type_id" FOREIGN KEY ("user_type_id") REFERENCES "my_type" ("object_id") ON DELETE NO ACTION ON UPDATE CASCADE;
+            ALTER TABLE "my_view" ALTER COLUMN "definition" TYPE text, ALTER COLUMN "definition" SET NOT NULL"#]]).await;
+    }
+
+    #[tokio::test]
+    async fn test_mysql() {
+        check(DatabaseBackend::MySql, expect![[r#"
+            CREATE TABLE `my_object` ( `id` int NOT NULL AUTO_INCREMENT PRIMARY KEY, `type` varchar(255) NOT NULL, `name` varchar(255) NOT NULL, `database_id` int NOT NULL, UNIQUE KEY (`database_id`, `type`, `name`) );
+            CREATE TABLE `my_database` ( `id` int NOT NULL AUTO_INCREMENT PRIMARY KEY, `name` varchar(255) NOT NULL UNIQUE );
+            CREATE TABLE `my_table` ( `object_id` int NOT NULL PRIMARY KEY, CONSTRAINT `fk_table_object_id` FOREIGN KEY (`object_id`) REFERENCES `my_object` (`id`) ON DELETE CASCADE ON UPDATE CASCADE );
+            CREATE TABLE `my_view` ( `object_id` int NOT NULL PRIMARY KEY, `definition` varchar(255) NOT NULL, CONSTRAINT `fk_view_object_id` FOREIGN KEY (`object_id`) REFERENCES `my_object` (`id`) ON DELETE CASCADE ON UPDATE CASCADE );
+            CREATE TABLE `my_column` ( `id` int NOT NULL AUTO_INCREMENT PRIMARY KEY, `table_id` int NOT NULL, `name` varchar(255) NOT NULL, `data_type` varchar(255) NOT NULL, `is_primary_key` bool NOT NULL, UNIQUE KEY (`table_id`, `name`), CONSTRAINT `fk_column_table_id` FOREIGN KEY (`table_id`) REFERENCES `my_table` (`object_id`) ON DELETE CASCADE ON UPDATE CASCADE );
+            CREATE TABLE `my_view_dependency` ( `view_id` int NOT NULL, `dependent_object_id` int NOT NULL, PRIMARY KEY (`view_id`, `dependent_object_id`), CONSTRAINT `fk_view_dependency_view_id` FOREIGN KEY (`view_id`) REFERENCES `my_view` (`object_id`) ON DELETE CASCADE ON UPDATE CASCADE, CONSTRAINT `fk_view_dependency_dependenct_object_id` FOREIGN KEY (`dependent_object_id`) REFERENCES `my_object` (`id`) ON DELETE RESTRICT ON UPDATE CASCADE );
+            ALTER TABLE `my_column` ADD COLUMN `is_nullable` bool NOT NULL DEFAULT TRUE;
+            UPDATE `my_column` SET `is_nullable` = FALSE WHERE `is_primary_key` = TRUE;
+            ALTER TABLE `my_object` ADD COLUMN `version` int NOT NULL DEFAULT 1;
+            CREATE TABLE `my_catalog` ( `id` int NOT NULL PRIMARY KEY, `version` int NOT NULL DEFAULT 0 );
+            INSERT INTO `my_catalog` (`id`) VALUES (1);
+            CREATE TABLE `my_schema` ( `id` int NOT NULL AUTO_INCREMENT PRIMARY KEY, `database_id` int NOT NULL, `name` varchar(255) NOT NULL, UNIQUE KEY (`database_id`, `name`), CONSTRAINT `fk_schema_database_id` FOREIGN KEY (`database_id`) REFERENCES `my_database` (`id`) ON DELETE CASCADE ON UPDATE CASCADE );
+            INSERT INTO `my_schema` (`database_id`, `name`) SELECT `id`, 'public' FROM `my_database`;
+            ALTER TABLE `my_object` ADD COLUMN `schema_id` int;
+            UPDATE `my_object` SET `schema_id` = (SELECT `id` FROM `my_schema` WHERE `my_schema`.`database_id` = `my_object`.`database_id` AND `my_schema`.`name` = 'public');
+            ALTER TABLE `my_object` MODIFY COLUMN `schema_id` int NOT NULL, ADD CONSTRAINT `fk_object_schema_id` FOREIGN KEY (`schema_id`) REFERENCES `my_schema` (`id`) ON DELETE CASCADE ON UPDATE CASCADE;
+            DROP INDEX `database_id` ON `my_object`;
+            CREATE UNIQUE INDEX `idx_object_schema_id_type_name` ON `my_object` (`schema_id`, `type`, `name`);
+            CREATE TABLE `my_materialized_view` ( `object_id` int NOT NULL PRIMARY KEY, `refresh_mode` varchar(255) NOT NULL, `last_refreshed_at` timestamp NOT NULL, CONSTRAINT `fk_materialized_view_object_id` FOREIGN KEY (`object_id`) REFERENCES `my_object` (`id`) ON DELETE CASCADE ON UPDATE CASCADE );
+            CREATE TABLE `my_sequence` ( `object_id` int NOT NULL PRIMARY KEY, `data_type` varchar(255) NOT NULL, `start_value` bigint NOT NULL, `increment` bigint NOT NULL, `min_value` bigint NOT NULL, `max_value` bigint NOT NULL, `cycle` bool NOT NULL, `current_value` bigint NOT NULL, `owned_by_column_id` int, UNIQUE KEY (`owned_by_column_id`), CONSTRAINT `fk_sequence_object_id` FOREIGN KEY (`object_id`) REFERENCES `my_object` (`id`) ON DELETE CASCADE ON UPDATE CASCADE, CONSTRAINT `fk_sequence_owned_by_column_id` FOREIGN KEY (`owned_by_column_id`) REFERENCES `my_column` (`id`) ON DELETE SET NULL ON UPDATE CASCADE );
+            CREATE TABLE `my_type` ( `object_id` int NOT NULL PRIMARY KEY, `kind` varchar(255) NOT NULL, `base_type` varchar(255), `base_type_id` int, `is_nullable` bool NOT NULL DEFAULT TRUE, `default_expr` varchar(255), `check_expr` varchar(255), CONSTRAINT `fk_type_object_id` FOREIGN KEY (`object_id`) REFERENCES `my_object` (`id`) ON DELETE CASCADE ON UPDATE CASCADE, CONSTRAINT `fk_type_base_type_id` FOREIGN KEY (`base_type_id`) REFERENCES `my_type` (`object_id`) ON DELETE NO ACTION ON UPDATE CASCADE );
+            CREATE TABLE `my_enum_label` ( `type_id` int NOT NULL, `position` int NOT NULL, `label` varchar(255) NOT NULL, PRIMARY KEY (`type_id`, `position`), UNIQUE KEY (`type_id`, `label`), CONSTRAINT `fk_enum_label_type_id` FOREIGN KEY (`type_id`) REFERENCES `my_type` (`object_id`) ON DELETE CASCADE ON UPDATE CASCADE );
+            CREATE TABLE `my_type_attribute` ( `type_id` int NOT NULL, `position` int NOT NULL, `name` varchar(255) NOT NULL, `data_type` varchar(255) NOT NULL, `user_type_id` int, PRIMARY KEY (`type_id`, `position`), UNIQUE KEY (`type_id`, `name`), CONSTRAINT `fk_type_attribute_type_id` FOREIGN KEY (`type_id`) REFERENCES `my_type` (`object_id`) ON DELETE CASCADE ON UPDATE CASCADE, CONSTRAINT `fk_type_attribute_user_type_id` FOREIGN KEY (`user_type_id`) REFERENCES `my_type` (`object_id`) ON DELETE NO ACTION ON UPDATE CASCADE );
+            ALTER TABLE `my_column` ADD COLUMN `user_type_id` int, ADD CONSTRAINT `fk_column_user_type_id` FOREIGN KEY (`user_type_id`) REFERENCES `my_type` (`object_id`) ON DELETE NO ACTION ON UPDATE CASCADE;
+            ALTER TABLE `my_view` MODIFY COLUMN `definition` text NOT NULL"#]]).await;
+    }
+
+    /// Tables, indexes, etc. of a SQLite database, as in `sqlite_master`.
+    async fn sqlite_schema(db: &DatabaseConnection) -> String {
+        let rows = db
+            .query_all(Statement::from_string(
+                DatabaseBackend::Sqlite,
+                "SELECT type, name, sql FROM sqlite_master \
+                 WHERE name NOT LIKE 'sqlite_%' ORDER BY type, name",
+            ))
+            .await
+            .unwrap();
+
+        rows.into_iter()
+            .map(|row| {
+                let r#type: String = row.try_get("", "type").unwrap();
+                let name: String = row.try_get("", "name").unwrap();
+                let sql: Option<String> = row.try_get("", "sql").unwrap();
+                format!("{type} {name}: {}\n", sql.unwrap_or_default())
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_up_down_up() {
+        let db = metastore::open(metastore::MEMORY_URL).await.unwrap();
+
+        Migrator::up(&db, None).await.unwrap();
+        let schema = sqlite_schema(&db).await;
+        expect![[r#"
+            table my_catalog: CREATE TABLE "my_catalog" ( "id" integer NOT NULL PRIMARY KEY, "version" integer NOT NULL DEFAULT 0 )
+            table my_column: CREATE TABLE "my_column" ( "id" integer NOT NULL PRIMARY KEY AUTOINCREMENT, "table_id" integer NOT NULL, "name" text NOT NULL, "data_type" text NOT NULL, "is_primary_key" boolean NOT NULL, "is_nullable" boolean NOT NULL DEFAULT TRUE, "user_type_id" integer REFERENCES "my_type" ("object_id") ON DELETE NO ACTION ON UPDATE CASCADE, UNIQUE ("table_id", "name"), FOREIGN KEY ("table_id") REFERENCES "my_table" ("object_id") ON DELETE CASCADE ON UPDATE CASCADE )
+            table my_database: CREATE TABLE "my_database" ( "id" integer NOT NULL PRIMARY KEY AUTOINCREMENT, "name" text NOT NULL UNIQUE )
+            table my_enum_label: CREATE TABLE "my_enum_label" ( "type_id" integer NOT NULL, "position" integer NOT NULL, "label" text NOT NULL, PRIMARY KEY ("type_id", "position"), UNIQUE ("type_id", "label"), FOREIGN KEY ("type_id") REFERENCES "my_type" ("object_id") ON DELETE CASCADE ON UPDATE CASCADE )
+            table my_materialized_view: CREATE TABLE "my_materialized_view" ( "object_id" integer NOT NULL PRIMARY KEY, "refresh_mode" text NOT NULL, "last_refreshed_at" text NOT NULL, FOREIGN KEY ("object_id") REFERENCES "my_object" ("id") ON DELETE CASCADE ON UPDATE CASCADE )
+            table my_object: CREATE TABLE "my_object" ( "id" integer NOT NULL PRIMARY KEY AUTOINCREMENT, "type" text NOT NULL, "name" text NOT NULL, "database_id" integer NOT NULL, "version" integer NOT NULL DEFAULT 1, "schema_id" integer NOT NULL, UNIQUE ("schema_id", "type", "name"), FOREIGN KEY ("schema_id") REFERENCES "my_schema" ("id") ON DELETE CASCADE ON UPDATE CASCADE )
+            table my_schema: CREATE TABLE "my_schema" ( "id" integer NOT NULL PRIMARY KEY AUTOINCREMENT, "database_id" integer NOT NULL, "name" text NOT NULL, UNIQUE ("database_id", "name"), FOREIGN KEY ("database_id") REFERENCES "my_database" ("id") ON DELETE CASCADE ON UPDATE CASCADE )
+            table my_sequence: CREATE TABLE "my_sequence" ( "object_id" integer NOT NULL PRIMARY KEY, "data_type" text NOT NULL, "start_value" bigint NOT NULL, "increment" bigint NOT NULL, "min_value" bigint NOT NULL, "max_value" bigint NOT NULL, "cycle" boolean NOT NULL, "current_value" bigint NOT NULL, "owned_by_column_id" integer, UNIQUE ("owned_by_column_id"), FOREIGN KEY ("object_id") REFERENCES "my_object" ("id") ON DELETE CASCADE ON UPDATE CASCADE, FOREIGN KEY ("owned_by_column_id") REFERENCES "my_column" ("id") ON DELETE SET NULL ON UPDATE CASCADE )
+            table my_table: CREATE TABLE "my_table" ( "object_id" integer NOT NULL PRIMARY KEY, FOREIGN KEY ("object_id") REFERENCES "my_object" ("id") ON DELETE CASCADE ON UPDATE CASCADE )
+            table my_type: CREATE TABLE "my_type" ( "object_id" integer NOT NULL PRIMARY KEY, "kind" text NOT NULL, "base_type" text, "base_type_id" integer, "is_nullable" boolean NOT NULL DEFAULT TRUE, "default_expr" text, "check_expr" text, FOREIGN KEY ("object_id") REFERENCES "my_object" ("id") ON DELETE CASCADE ON UPDATE CASCADE, FOREIGN KEY ("base_type_id") REFERENCES "my_type" ("object_id") ON DELETE NO ACTION ON UPDATE CASCADE )
+            table my_type_attribute: CREATE TABLE "my_type_attribute" ( "type_id" integer NOT NULL, "position" integer NOT NULL, "name" text NOT NULL, "data_type" text NOT NULL, "user_type_id" integer, PRIMARY KEY ("type_id", "position"), UNIQUE ("type_id", "name"), FOREIGN KEY ("type_id") REFERENCES "my_type" ("object_id") ON DELETE CASCADE ON UPDATE CASCADE, FOREIGN KEY ("user_type_id") REFERENCES "my_type" ("object_id") ON DELETE NO ACTION ON UPDATE CASCADE )
+            table my_view: CREATE TABLE "my_view" ( "object_id" integer NOT NULL PRIMARY KEY, "definition" text NOT NULL, FOREIGN KEY ("object_id") REFERENCES "my_object" ("id") ON DELETE CASCADE ON UPDATE CASCADE )
+            table my_view_dependency: CREATE TABLE "my_view_dependency" ( "view_id" integer NOT NULL, "dependent_object_id" integer NOT NULL, PRIMARY KEY ("view_id", "dependent_object_id"), FOREIGN KEY ("view_id") REFERENCES "my_view" ("object_id") ON DELETE CASCADE ON UPDATE CASCADE, FOREIGN KEY ("dependent_object_id") REFERENCES "my_object" ("id") ON DELETE RESTRICT ON UPDATE CASCADE )
+            table seaql_migrations: CREATE TABLE "seaql_migrations" ( "version" text NOT NULL PRIMARY KEY, "applied_at" bigint NOT NULL )
+        "#]]
+        .assert_eq(&schema);
+
+        // Only the table tracking migrations is left.
+        Migrator::down(&db, None).await.unwrap();
+        expect![[r#"
+            table seaql_migrations: CREATE TABLE "seaql_migrations" ( "version" text NOT NULL PRIMARY KEY, "applied_at" bigint NOT NULL )
+        "#]]
+        .assert_eq(&sqlite_schema(&db).await);
+
+        Migrator::up(&db, None).await.unwrap();
+        assert_eq!(sqlite_schema(&db).await, schema);
+    }
+
+    /// Rows of a query, one per line.
+    async fn query(db: &DatabaseConnection, sql: &str) -> String {
+        let rows = db
+            .query_all(Statement::from_string(DatabaseBackend::Sqlite, sql))
+            .await
+            .unwrap();
+        rows.into_iter()
+            .map(|row| {
+                let mut values = Vec::new();
+                for i in 0.. {
+                    let value = match row.try_get_by_index::<Option<i64>>(i) {
+                        Ok(value) => value.map(|v| v.to_string()),
+                        Err(_) => match row.try_get_by_index::<Option<String>>(i) {
+                            Ok(value) => value,
+                            Err(_) => break,
+                        },
+                    };
+                    values.push(value.unwrap_or_else(|| "NULL".to_owned()));
+                }
+                values.join(" | ") + "\n"
+            })
+            .collect()
+    }
+
+    /// `my_object` is rebuilt by the `schema` migration, which must keep the objects and what
+    /// references them.
+    #[tokio::test]
+    async fn test_schema_migration() {
+        let db = metastore::open(metastore::MEMORY_URL).await.unwrap();
+        let before = Migrator::migrations()
+            .iter()
+            .position(|m| m.name() == "schema")
+            .unwrap();
+        Migrator::up(&db, Some(before as u32)).await.unwrap();
+        db.execute_unprepared(
+            "INSERT INTO my_database (id, name) VALUES (1, 'shop'), (2, 'empty');
+            INSERT INTO my_object (id, type, name, database_id, version)
+                VALUES (1, 'table', 'users', 1, 3), (2, 'view', 'names', 1, 1);
+            INSERT INTO my_table (object_id) VALUES (1);
+            INSERT INTO my_column (table_id, name, data_type, is_primary_key, is_nullable)
+                VALUES (1, 'name', 'TEXT', false, true);
+            INSERT INTO my_view (object_id, definition) VALUES (2, 'SELECT name FROM users');
+            INSERT INTO my_view_dependency (view_id, dependent_object_id) VALUES (2, 1);",
+        )
+        .await
+        .unwrap();
+
+        Migrator::up(&db, None).await.unwrap();
+        expect![[r#"
+            1 | 1 | public
+            2 | 2 | public
+        "#]]
+        .assert_eq(&query(&db, "SELECT id, database_id, name FROM my_schema").await);
+        expect![[r#"
+            1 | table | users | 1 | 3 | 1
+            2 | view | names | 1 | 1 | 1
+        "#]]
+        .assert_eq(&query(&db, "SELECT * FROM my_object").await);
+        assert_eq!(query(&db, "PRAGMA foreign_key_check").await, "");
+
+        // Foreign keys still reference the rebuilt table.
+        db.execute_unprepared("DELETE FROM my_view_dependency; DELETE FROM my_object")
+            .await
+            .unwrap();
+        expect![[r#"
+            0 | 0 | 0
+        "#]]
+        .assert_eq(
+            &query(
+                &db,
+                "SELECT (SELECT count(*) FROM my_table), (SELECT count(*) FROM my_column), \
+                 (SELECT count(*) FROM my_view)",
+            )
+            .await,
+        );
+    }
+
+    /// `my_view` is rebuilt by the `view_definition_text` migration, which must keep the views
+    /// and their dependencies.
+    #[tokio::test]
+    async fn test_view_definition_text_migration() {
+        let db = metastore::open(metastore::MEMORY_URL).await.unwrap();
+        let before = Migrator::migrations()
+            .iter()
+            .position(|m| m.name() == "view_definition_text")
+            .unwrap();
+        Migrator::up(&db, Some(before as u32)).await.unwrap();
+        db.execute_unprepared(
+            "INSERT INTO my_database (id, name) VALUES (1, 'shop');
+            INSERT INTO my_schema (id, database_id, name) VALUES (1, 1, 'public');
+            INSERT INTO my_object (id, type, name, database_id, schema_id)
+                VALUES (1, 'table', 'users', 1, 1), (2, 'view', 'names', 1, 1);
+            INSERT INTO my_table (object_id) VALUES (1);
+            INSERT INTO my_view (object_id, definition) VALUES (2, 'SELECT name FROM users');
+            INSERT INTO my_view_dependency (view_id, dependent_object_id) VALUES (2, 1);",
+        )
+        .await
+        .unwrap();
+
+        Migrator::up(&db, None).await.unwrap();
+        expect![[r#"
+            2 | SELECT name FROM users
+        "#]]
+        .assert_eq(&query(&db, "SELECT * FROM my_view").await);
+        assert_eq!(query(&db, "PRAGMA foreign_key_check").await, "");
+
+        // Dependencies still reference the rebuilt table.
+        db.execute_unprepared("DELETE FROM my_view").await.unwrap();
+        assert_eq!(
+            query(&db, "SELECT count(*) FROM my_view_dependency").await,
+            "0\n"
+        );
+    }
+
+    /// Columns of an entity as `table.column type NULL|NOT NULL [PK]`, with SQLite type names.
+    fn entity_columns<E: EntityTrait>(entity: E) -> Vec<String> {
+        use sea_orm::sea_query::ColumnType;
+
+        let primary_key = E::PrimaryKey::iter()
+            .map(|pk| pk.into_column().to_string())
+            .collect::<Vec<_>>();
+
+        E::Column::iter()
+            .map(|column| {
+                let def = column.def();
+                let data_type = match def.get_column_type() {
+                    ColumnType::Integer => "integer",
+                    ColumnType::BigInteger => "bigint",
+                    ColumnType::String(_) | ColumnType::Text => "text",
+                    ColumnType::Boolean => "boolean",
+                    // Stored as RFC 3339 strings.
+                    ColumnType::TimestampWithTimeZone => "text",
+                    other => panic!("unmapped column type {other:?} in {}", entity.table_name()),
+                };
+                let name = column.to_string();
+                format!(
+                    "{}.{name} {data_type} {}{}",
+                    entity.table_name(),
+                    if def.is_null() { "NULL" } else { "NOT NULL" },
+                    if primary_key.contains(&name) {
+                        " PK"
+                    } else {
+                        ""
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// The entities in `src/entities` must be what `sea generate entity` would generate from the
+    /// migrated metastore.
+    #[tokio::test]
+    async fn test_entities_match_migrations() {
+        let db = metastore::connect(metastore::MEMORY_URL).await.unwrap();
+
+        let mut expected = [
+            entity_columns(MyCatalog),
+            entity_columns(MyColumn),
+            entity_columns(MyDatabase),
+            entity_columns(MyEnumLabel),
+            entity_columns(MyMaterializedView),
+            entity_columns(MyObject),
+            entity_columns(MySchema),
+            entity_columns(MySequence),
+            entity_columns(MyTable),
+            entity_columns(MyType),
+            entity_columns(MyTypeAttribute),
+            entity_columns(MyView),
+            entity_columns(MyViewDependency),
+        ]
+        .concat();
+        expected.sort();
+
+        let tables = db
+            .query_all(Statement::from_string(
+                DatabaseBackend::Sqlite,
+                "SELECT name FROM sqlite_master WHERE type = 'table' \
+                 AND name NOT LIKE 'sqlite_%' AND name != 'seaql_migrations'",
+            ))
+            .await
+            .unwrap();
+        let tables = tables
+            .into_iter()
+            .map(|table| table.try_get::<String>("", "name").unwrap())
+            .collect::<BTreeSet<_>>();
+        let entities = expected
+            .iter()
+            .map(|column| column.split_once('.').unwrap().0.to_owned())
+            .collect::<BTreeSet<_>>();
+        assert_eq!(
+            tables, entities,
+            "every table must have an entity listed here, and every entity a table"
+        );
+
+        let mut actual = Vec::new();
+        for table in tables {
+            let columns = db
+                .query_all(Statement::from_string(
+                    DatabaseBackend::Sqlite,
+                    format!("SELECT name, type, \"notnull\", pk FROM pragma_table_info('{table}')"),
+                ))
+                .await
+                .unwrap();
+            for column in columns {
+                let name: String = column.try_get("", "name").unwrap();
+                let data_type: String = column.try_get("", "type").unwrap();
+                let not_null: bool = column.try_get("", "notnull").unwrap();
+                let pk: i32 = column.try_get("", "pk").unwrap();
+                actual.push(format!(
+                    "{table}.{name} {} {}{}",
+                    data_type.to_lowercase(),
+                    if not_null { "NOT NULL" } else { "NULL" },
+                    if pk > 0 { " PK" } else { "" },
+                ));
+            }
+        }
+        actual.sort();
+
+        assert_eq!(
+            actual, expected,
+            "entities don't match the migrations, regenerate them as in src/entities/README.md"
+        );
     }
 }
@@ -157,7 +157,7 @@ impl MigrationTrait for Migration {
             )
             .to_owned();
 
-        for table in vec![
+        for table in [
             create_object,
             create_database,
             create_table,
@@ -173,7 +173,7 @@ impl MigrationTrait for Migration {
 
     // Define how to rollback this migration: Drop all tables.
     async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
-        for table in vec![
+        for table in [
             MyViewDependency::Table.into_table_ref(),
             MyColumn::Table.into_table_ref(),
             MyView::Table.into_table_ref(),
@@ -0,0 +1,61 @@
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "column_nullability"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    // Track whether a column accepts `NULL`, so that schemas can be compared and exported
+    // faithfully.
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(MyColumn::Table)
+                    .add_column(
+                        ColumnDef::new(MyColumn::IsNullable)
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Primary keys are never nullable. Fix up the columns created before this migration.
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(MyColumn::Table)
+                    .value(MyColumn::IsNullable, false)
+                    .and_where(Expr::col(MyColumn::IsPrimaryKey).eq(true))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(MyColumn::Table)
+                    .drop_column(MyColumn::IsNullable)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum MyColumn {
+    Table,
+    IsPrimaryKey,
+    IsNullable,
+}
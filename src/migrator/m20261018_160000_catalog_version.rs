@@ -0,0 +1,61 @@
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "catalog_version"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    // A single row incremented by every DDL transaction, so that caches can cheaply tell whether
+    // anything changed since they were filled.
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(MyCatalog::Table)
+                    .col(
+                        ColumnDef::new(MyCatalog::Id)
+                            .integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(MyCatalog::Version)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .exec_stmt(
+                Query::insert()
+                    .into_table(MyCatalog::Table)
+                    .columns([MyCatalog::Id])
+                    .values_panic([1.into()])
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MyCatalog::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum MyCatalog {
+    Table,
+    Id,
+    Version,
+}
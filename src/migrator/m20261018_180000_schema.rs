@@ -0,0 +1,360 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DatabaseBackend};
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "schema"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    // Schemas between databases and objects, like in Postgres. Objects keep their `database_id`,
+    // which must be the one of their schema, so that whole databases can still be listed or
+    // invalidated at once. Existing objects are moved to a `public` schema in each database.
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(MySchema::Table)
+                    .col(
+                        ColumnDef::new(MySchema::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(MySchema::DatabaseId).integer().not_null())
+                    .col(ColumnDef::new(MySchema::Name).string().not_null())
+                    .index(
+                        Index::create()
+                            .col(MySchema::DatabaseId)
+                            .col(MySchema::Name)
+                            .unique(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_schema_database_id")
+                            .from(MySchema::Table, MySchema::DatabaseId)
+                            .to(MyDatabase::Table, MyDatabase::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .exec_stmt(
+                Query::insert()
+                    .into_table(MySchema::Table)
+                    .columns([MySchema::DatabaseId, MySchema::Name])
+                    .select_from(
+                        Query::select()
+                            .column(MyDatabase::Id)
+                            .expr(Expr::val("public"))
+                            .from(MyDatabase::Table)
+                            .to_owned(),
+                    )
+                    .unwrap()
+                    .to_owned(),
+            )
+            .await?;
+
+        let public_schema = Query::select()
+            .column(MySchema::Id)
+            .from(MySchema::Table)
+            .and_where(
+                Expr::col((MySchema::Table, MySchema::DatabaseId))
+                    .equals((MyObject::Table, MyObject::DatabaseId)),
+            )
+            .and_where(Expr::col((MySchema::Table, MySchema::Name)).eq("public"))
+            .to_owned();
+
+        match manager.get_database_backend() {
+            // The unique key of `my_object` can't be dropped, so the table is rebuilt.
+            DatabaseBackend::Sqlite => {
+                let mut create = create_object(MyObject::New);
+                create
+                    .col(ColumnDef::new(MyObject::SchemaId).integer().not_null())
+                    .index(
+                        Index::create()
+                            .col(MyObject::SchemaId)
+                            .col(MyObject::Type)
+                            .col(MyObject::Name)
+                            .unique(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_object_schema_id")
+                            .from(MyObject::New, MyObject::SchemaId)
+                            .to(MySchema::Table, MySchema::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    );
+                // A missing schema fails the `NOT NULL` constraint rather than losing the object.
+                let copy = Query::insert()
+                    .into_table(MyObject::New)
+                    .columns(OBJECT_COLUMNS.into_iter().chain([MyObject::SchemaId]))
+                    .select_from(
+                        Query::select()
+                            .columns(OBJECT_COLUMNS.map(|c| (MyObject::Table, c)))
+                            .expr(SimpleExpr::SubQuery(
+                                None,
+                                Box::new(public_schema.into_sub_query_statement()),
+                            ))
+                            .from(MyObject::Table)
+                            .to_owned(),
+                    )
+                    .unwrap()
+                    .to_owned();
+                rebuild_object(manager, create, copy.to_string(SqliteQueryBuilder)).await?;
+            }
+            _ => {
+                manager
+                    .alter_table(
+                        Table::alter()
+                            .table(MyObject::Table)
+                            .add_column(ColumnDef::new(MyObject::SchemaId).integer())
+                            .to_owned(),
+                    )
+                    .await?;
+                manager
+                    .exec_stmt(
+                        Query::update()
+                            .table(MyObject::Table)
+                            .value(
+                                MyObject::SchemaId,
+                                SimpleExpr::SubQuery(
+                                    None,
+                                    Box::new(public_schema.into_sub_query_statement()),
+                                ),
+                            )
+                            .to_owned(),
+                    )
+                    .await?;
+                manager
+                    .alter_table(
+                        Table::alter()
+                            .table(MyObject::Table)
+                            .modify_column(ColumnDef::new(MyObject::SchemaId).integer().not_null())
+                            .add_foreign_key(
+                                TableForeignKey::new()
+                                    .name("fk_object_schema_id")
+                                    .from_tbl(MyObject::Table)
+                                    .from_col(MyObject::SchemaId)
+                                    .to_tbl(MySchema::Table)
+                                    .to_col(MySchema::Id)
+                                    .on_delete(ForeignKeyAction::Cascade)
+                                    .on_update(ForeignKeyAction::Cascade),
+                            )
+                            .to_owned(),
+                    )
+                    .await?;
+                drop_old_unique_key(manager).await?;
+                manager
+                    .create_index(
+                        Index::create()
+                            .name("idx_object_schema_id_type_name")
+                            .table(MyObject::Table)
+                            .col(MyObject::SchemaId)
+                            .col(MyObject::Type)
+                            .col(MyObject::Name)
+                            .unique()
+                            .to_owned(),
+                    )
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Fails if a database has objects of the same type and name in several schemas.
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        match manager.get_database_backend() {
+            DatabaseBackend::Sqlite => {
+                let mut create = create_object(MyObject::New);
+                create.index(
+                    Index::create()
+                        .col(MyObject::DatabaseId)
+                        .col(MyObject::Type)
+                        .col(MyObject::Name)
+                        .unique(),
+                );
+                let copy = Query::insert()
+                    .into_table(MyObject::New)
+                    .columns(OBJECT_COLUMNS)
+                    .select_from(
+                        Query::select()
+                            .columns(OBJECT_COLUMNS)
+                            .from(MyObject::Table)
+                            .to_owned(),
+                    )
+                    .unwrap()
+                    .to_owned();
+                rebuild_object(manager, create, copy.to_string(SqliteQueryBuilder)).await?;
+            }
+            _ => {
+                manager
+                    .drop_index(
+                        Index::drop()
+                            .name("idx_object_schema_id_type_name")
+                            .table(MyObject::Table)
+                            .to_owned(),
+                    )
+                    .await?;
+                manager
+                    .create_index(
+                        Index::create()
+                            .name(old_unique_key(manager))
+                            .table(MyObject::Table)
+                            .col(MyObject::DatabaseId)
+                            .col(MyObject::Type)
+                            .col(MyObject::Name)
+                            .unique()
+                            .to_owned(),
+                    )
+                    .await?;
+                manager
+                    .drop_foreign_key(
+                        ForeignKey::drop()
+                            .name("fk_object_schema_id")
+                            .table(MyObject::Table)
+                            .to_owned(),
+                    )
+                    .await?;
+                manager
+                    .alter_table(
+                        Table::alter()
+                            .table(MyObject::Table)
+                            .drop_column(MyObject::SchemaId)
+                            .to_owned(),
+                    )
+                    .await?;
+            }
+        }
+
+        manager
+            .drop_table(Table::drop().table(MySchema::Table).to_owned())
+            .await
+    }
+}
+
+/// Columns of `my_object` before this migration.
+const OBJECT_COLUMNS: [MyObject; 5] = [
+    MyObject::Id,
+    MyObject::Type,
+    MyObject::Name,
+    MyObject::DatabaseId,
+    MyObject::Version,
+];
+
+/// `my_object` as it was before this migration, without the unique key.
+fn create_object(table: MyObject) -> TableCreateStatement {
+    Table::create()
+        .table(table)
+        .col(
+            ColumnDef::new(MyObject::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(ColumnDef::new(MyObject::Type).string().not_null())
+        .col(ColumnDef::new(MyObject::Name).string().not_null())
+        .col(ColumnDef::new(MyObject::DatabaseId).integer().not_null())
+        .col(
+            ColumnDef::new(MyObject::Version)
+                .integer()
+                .not_null()
+                .default(1),
+        )
+        .to_owned()
+}
+
+/// Replace `my_object` with the table created by `create`, filled by `copy`, as recommended by
+/// <https://www.sqlite.org/lang_altertable.html#otheralter>. Other tables keep referencing it by
+/// name, and nothing is deleted in cascade as foreign keys are off meanwhile.
+///
+/// It's all one script, as the pragma only applies to the connection it's run on.
+async fn rebuild_object(
+    manager: &SchemaManager<'_>,
+    create: TableCreateStatement,
+    copy: String,
+) -> Result<(), DbErr> {
+    let script = [
+        "PRAGMA foreign_keys = OFF".to_owned(),
+        "BEGIN".to_owned(),
+        create.to_string(SqliteQueryBuilder),
+        copy,
+        Table::drop()
+            .table(MyObject::Table)
+            .to_string(SqliteQueryBuilder),
+        Table::rename()
+            .table(MyObject::New, MyObject::Table)
+            .to_string(SqliteQueryBuilder),
+        "COMMIT".to_owned(),
+        "PRAGMA foreign_keys = ON".to_owned(),
+    ];
+
+    manager
+        .get_connection()
+        .execute_unprepared(&script.join(";\n"))
+        .await?;
+    Ok(())
+}
+
+/// Name of the unique key created by `toydb_init`, as chosen by the backend.
+fn old_unique_key(manager: &SchemaManager) -> &'static str {
+    match manager.get_database_backend() {
+        DatabaseBackend::MySql => "database_id",
+        _ => "my_object_database_id_type_name_key",
+    }
+}
+
+async fn drop_old_unique_key(manager: &SchemaManager<'_>) -> Result<(), DbErr> {
+    let name = old_unique_key(manager);
+    match manager.get_database_backend() {
+        // It's a constraint, whose index can't be dropped directly.
+        DatabaseBackend::Postgres => {
+            let sql = format!(r#"ALTER TABLE "my_object" DROP CONSTRAINT "{name}""#);
+            manager.get_connection().execute_unprepared(&sql).await?;
+        }
+        _ => {
+            manager
+                .drop_index(Index::drop().name(name).table(MyObject::Table).to_owned())
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Iden, Clone, Copy)]
+pub enum MyObject {
+    Table,
+    #[iden = "my_object_new"]
+    New,
+    Id,
+    Type,
+    Name,
+    DatabaseId,
+    Version,
+    SchemaId,
+}
+
+#[derive(Iden)]
+pub enum MySchema {
+    Table,
+    Id,
+    DatabaseId,
+    Name,
+}
+
+#[derive(Iden)]
+pub enum MyDatabase {
+    Table,
+    Id,
+}
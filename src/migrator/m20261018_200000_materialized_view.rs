@@ -0,0 +1,69 @@
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "materialized_view"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    // Materialized views are objects of type `materialized_view`, with a definition in `my_view`,
+    // lineage in `my_view_dependency`, storage columns in `my_table` and `my_column`, and how
+    // they are refreshed here.
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(MyMaterializedView::Table)
+                    .col(
+                        ColumnDef::new(MyMaterializedView::ObjectId)
+                            .integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(MyMaterializedView::RefreshMode)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MyMaterializedView::LastRefreshedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_materialized_view_object_id")
+                            .from(MyMaterializedView::Table, MyMaterializedView::ObjectId)
+                            .to(MyObject::Table, MyObject::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MyMaterializedView::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum MyMaterializedView {
+    Table,
+    ObjectId,
+    RefreshMode,
+    LastRefreshedAt,
+}
+
+#[derive(Iden)]
+pub enum MyObject {
+    Table,
+    Id,
+}
@@ -0,0 +1,118 @@
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "sequence"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    // Sequences are objects of type `sequence`. One owned by a column is the source of its values,
+    // i.e., the column is an identity column, and is dropped with it. That's done by the session,
+    // so deleting the column directly only detaches the sequence.
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(MySequence::Table)
+                    .col(
+                        ColumnDef::new(MySequence::ObjectId)
+                            .integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(MySequence::DataType).string().not_null())
+                    .col(
+                        ColumnDef::new(MySequence::StartValue)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MySequence::Increment)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MySequence::MinValue)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MySequence::MaxValue)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(MySequence::Cycle).boolean().not_null())
+                    .col(
+                        ColumnDef::new(MySequence::CurrentValue)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(MySequence::OwnedByColumnId).integer())
+                    .index(Index::create().col(MySequence::OwnedByColumnId).unique())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_sequence_object_id")
+                            .from(MySequence::Table, MySequence::ObjectId)
+                            .to(MyObject::Table, MyObject::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_sequence_owned_by_column_id")
+                            .from(MySequence::Table, MySequence::OwnedByColumnId)
+                            .to(MyColumn::Table, MyColumn::Id)
+                            .on_delete(ForeignKeyAction::SetNull)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Their objects would be left without their `my_sequence` row otherwise.
+        manager
+            .exec_stmt(
+                Query::delete()
+                    .from_table(MyObject::Table)
+                    .and_where(Expr::col(MyObject::Type).eq("sequence"))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().table(MySequence::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum MySequence {
+    Table,
+    ObjectId,
+    DataType,
+    StartValue,
+    Increment,
+    MinValue,
+    MaxValue,
+    Cycle,
+    CurrentValue,
+    OwnedByColumnId,
+}
+
+#[derive(Iden)]
+pub enum MyObject {
+    Table,
+    Id,
+    Type,
+}
+
+#[derive(Iden)]
+pub enum MyColumn {
+    Table,
+    Id,
+}
@@ -0,0 +1,250 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::DatabaseBackend;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "user_type"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    // User-defined types are objects of type `type`, of one of three kinds, like in Postgres: enums
+    // with their labels, composite types with their attributes, and domains over a base type.
+    //
+    // Columns, attributes and domains reference the user-defined type they use by id. Those
+    // foreign keys take no action, which is checked at the end of the statement rather than right
+    // away, so that a type and what uses it can be deleted together, e.g., with their schema.
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(MyType::Table)
+                    .col(
+                        ColumnDef::new(MyType::ObjectId)
+                            .integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(MyType::Kind).string().not_null())
+                    .col(ColumnDef::new(MyType::BaseType).string())
+                    .col(ColumnDef::new(MyType::BaseTypeId).integer())
+                    .col(
+                        ColumnDef::new(MyType::IsNullable)
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .col(ColumnDef::new(MyType::DefaultExpr).string())
+                    .col(ColumnDef::new(MyType::CheckExpr).string())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_type_object_id")
+                            .from(MyType::Table, MyType::ObjectId)
+                            .to(MyObject::Table, MyObject::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_type_base_type_id")
+                            .from(MyType::Table, MyType::BaseTypeId)
+                            .to(MyType::Table, MyType::ObjectId)
+                            .on_delete(ForeignKeyAction::NoAction)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(MyEnumLabel::Table)
+                    .col(ColumnDef::new(MyEnumLabel::TypeId).integer().not_null())
+                    .col(ColumnDef::new(MyEnumLabel::Position).integer().not_null())
+                    .col(ColumnDef::new(MyEnumLabel::Label).string().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(MyEnumLabel::TypeId)
+                            .col(MyEnumLabel::Position),
+                    )
+                    .index(
+                        Index::create()
+                            .col(MyEnumLabel::TypeId)
+                            .col(MyEnumLabel::Label)
+                            .unique(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_enum_label_type_id")
+                            .from(MyEnumLabel::Table, MyEnumLabel::TypeId)
+                            .to(MyType::Table, MyType::ObjectId)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(MyTypeAttribute::Table)
+                    .col(ColumnDef::new(MyTypeAttribute::TypeId).integer().not_null())
+                    .col(
+                        ColumnDef::new(MyTypeAttribute::Position)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(MyTypeAttribute::Name).string().not_null())
+                    .col(
+                        ColumnDef::new(MyTypeAttribute::DataType)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(MyTypeAttribute::UserTypeId).integer())
+                    .primary_key(
+                        Index::create()
+                            .col(MyTypeAttribute::TypeId)
+                            .col(MyTypeAttribute::Position),
+                    )
+                    .index(
+                        Index::create()
+                            .col(MyTypeAttribute::TypeId)
+                            .col(MyTypeAttribute::Name)
+                            .unique(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_type_attribute_type_id")
+                            .from(MyTypeAttribute::Table, MyTypeAttribute::TypeId)
+                            .to(MyType::Table, MyType::ObjectId)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_type_attribute_user_type_id")
+                            .from(MyTypeAttribute::Table, MyTypeAttribute::UserTypeId)
+                            .to(MyType::Table, MyType::ObjectId)
+                            .on_delete(ForeignKeyAction::NoAction)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        match manager.get_database_backend() {
+            // Foreign keys can't be added to existing tables, but a new column may reference
+            // another table.
+            DatabaseBackend::Sqlite => {
+                manager
+                    .alter_table(
+                        Table::alter()
+                            .table(MyColumn::Table)
+                            .add_column(ColumnDef::new(MyColumn::UserTypeId).integer().extra(
+                                r#"REFERENCES "my_type" ("object_id") ON DELETE NO ACTION ON UPDATE CASCADE"#,
+                            ))
+                            .to_owned(),
+                    )
+                    .await
+            }
+            _ => {
+                manager
+                    .alter_table(
+                        Table::alter()
+                            .table(MyColumn::Table)
+                            .add_column(ColumnDef::new(MyColumn::UserTypeId).integer())
+                            .add_foreign_key(
+                                TableForeignKey::new()
+                                    .name("fk_column_user_type_id")
+                                    .from_tbl(MyColumn::Table)
+                                    .from_col(MyColumn::UserTypeId)
+                                    .to_tbl(MyType::Table)
+                                    .to_col(MyType::ObjectId)
+                                    .on_delete(ForeignKeyAction::NoAction)
+                                    .on_update(ForeignKeyAction::Cascade),
+                            )
+                            .to_owned(),
+                    )
+                    .await
+            }
+        }
+    }
+
+    // Columns keep the name of their type in `data_type`.
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let mut alter = Table::alter().table(MyColumn::Table).to_owned();
+        if manager.get_database_backend() != DatabaseBackend::Sqlite {
+            alter.drop_foreign_key(Alias::new("fk_column_user_type_id"));
+        }
+        manager
+            .alter_table(alter.drop_column(MyColumn::UserTypeId).to_owned())
+            .await?;
+
+        // Their objects would be left without their `my_type` row otherwise.
+        manager
+            .exec_stmt(
+                Query::delete()
+                    .from_table(MyObject::Table)
+                    .and_where(Expr::col(MyObject::Type).eq("type"))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().table(MyTypeAttribute::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(MyEnumLabel::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(MyType::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum MyType {
+    Table,
+    ObjectId,
+    Kind,
+    BaseType,
+    BaseTypeId,
+    IsNullable,
+    DefaultExpr,
+    CheckExpr,
+}
+
+#[derive(Iden)]
+pub enum MyEnumLabel {
+    Table,
+    TypeId,
+    Position,
+    Label,
+}
+
+#[derive(Iden)]
+pub enum MyTypeAttribute {
+    Table,
+    TypeId,
+    Position,
+    Name,
+    DataType,
+    UserTypeId,
+}
+
+#[derive(Iden)]
+pub enum MyObject {
+    Table,
+    Id,
+    Type,
+}
+
+#[derive(Iden)]
+pub enum MyColumn {
+    Table,
+    UserTypeId,
+}
@@ -0,0 +1,139 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DatabaseBackend};
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "view_definition_text"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    // `toydb_init` created `my_view.definition` as a `string`, which is `varchar(255)` on MySQL,
+    // too short for a query. Make it `text` on all backends.
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        alter_definition(
+            manager,
+            ColumnDef::new(MyView::Definition)
+                .text()
+                .not_null()
+                .to_owned(),
+        )
+        .await
+    }
+
+    // Fails on MySQL if a definition no longer fits.
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        alter_definition(
+            manager,
+            ColumnDef::new(MyView::Definition)
+                .string()
+                .not_null()
+                .to_owned(),
+        )
+        .await
+    }
+}
+
+async fn alter_definition(
+    manager: &SchemaManager<'_>,
+    mut definition: ColumnDef,
+) -> Result<(), DbErr> {
+    match manager.get_database_backend() {
+        // Column types can't be altered, so the table is rebuilt.
+        DatabaseBackend::Sqlite => {
+            rebuild_view(manager, create_view(MyView::New, definition)).await
+        }
+        _ => {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(MyView::Table)
+                        .modify_column(&mut definition)
+                        .to_owned(),
+                )
+                .await
+        }
+    }
+}
+
+/// `my_view` as created by `toydb_init`, with the given definition column.
+fn create_view(table: MyView, mut definition: ColumnDef) -> TableCreateStatement {
+    Table::create()
+        .table(table)
+        .col(
+            ColumnDef::new(MyView::ObjectId)
+                .integer()
+                .not_null()
+                .primary_key(),
+        )
+        .col(&mut definition)
+        .foreign_key(
+            ForeignKey::create()
+                .name("fk_view_object_id")
+                .from(table, MyView::ObjectId)
+                .to(MyObject::Table, MyObject::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .on_update(ForeignKeyAction::Cascade),
+        )
+        .to_owned()
+}
+
+/// Replace `my_view` with the table created by `create`, as in the `schema` migration. Other
+/// tables keep referencing it by name, and nothing is deleted in cascade as foreign keys are off
+/// meanwhile.
+async fn rebuild_view(
+    manager: &SchemaManager<'_>,
+    create: TableCreateStatement,
+) -> Result<(), DbErr> {
+    let columns = [MyView::ObjectId, MyView::Definition];
+    let copy = Query::insert()
+        .into_table(MyView::New)
+        .columns(columns)
+        .select_from(
+            Query::select()
+                .columns(columns)
+                .from(MyView::Table)
+                .to_owned(),
+        )
+        .unwrap()
+        .to_owned();
+
+    let script = [
+        "PRAGMA foreign_keys = OFF".to_owned(),
+        "BEGIN".to_owned(),
+        create.to_string(SqliteQueryBuilder),
+        copy.to_string(SqliteQueryBuilder),
+        Table::drop()
+            .table(MyView::Table)
+            .to_string(SqliteQueryBuilder),
+        Table::rename()
+            .table(MyView::New, MyView::Table)
+            .to_string(SqliteQueryBuilder),
+        "COMMIT".to_owned(),
+        "PRAGMA foreign_keys = ON".to_owned(),
+    ];
+
+    manager
+        .get_connection()
+        .execute_unprepared(&script.join(";\n"))
+        .await?;
+    Ok(())
+}
+
+#[derive(Iden, Clone, Copy)]
+pub enum MyView {
+    Table,
+    #[iden = "my_view_new"]
+    New,
+    ObjectId,
+    Definition,
+}
+
+#[derive(Iden)]
+pub enum MyObject {
+    Table,
+    Id,
+}
@@ -0,0 +1,242 @@
+//! Results of statements and how to print them.
+
+use std::fmt::Write;
+
+use clap::ValueEnum;
+use unicode_width::UnicodeWidthStr;
+
+/// Rows returned by a statement, e.g., `SHOW TABLES`. All values are already rendered as text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResultSet {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    /// Extra notes shown below the rows, only in human-readable formats.
+    pub footer: Vec<String>,
+}
+
+impl ResultSet {
+    pub fn new(columns: &[&str], rows: Vec<Vec<String>>) -> Self {
+        Self {
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            rows,
+            footer: Vec::new(),
+        }
+    }
+
+    /// Render in the given format. `expanded` prints each row as a record, like `\x` in psql, and
+    /// only applies to [`Format::Table`].
+    pub fn render(&self, format: Format, expanded: bool) -> String {
+        let mut out = match format {
+            Format::Table if expanded => self.render_expanded(),
+            Format::Table => self.render_table(),
+            Format::Csv => return self.render_csv(),
+            Format::Json => return self.render_json(),
+            Format::Markdown if self.footer.is_empty() => self.render_markdown(),
+            // Otherwise the footer would be part of the last row.
+            Format::Markdown => self.render_markdown() + "\n",
+        };
+
+        for line in &self.footer {
+            writeln!(out, "{line}").unwrap();
+        }
+        out
+    }
+
+    fn render_table(&self) -> String {
+        let mut widths = self.columns.iter().map(|c| c.width()).collect::<Vec<_>>();
+        for row in &self.rows {
+            for (width, value) in widths.iter_mut().zip(row) {
+                *width = (*width).max(value.width());
+            }
+        }
+
+        let line = |values: &[String]| {
+            let cells = values
+                .iter()
+                .zip(&widths)
+                .map(|(v, width)| format!(" {v}{} ", pad(v, *width)))
+                .collect::<Vec<_>>();
+            cells.join("|").trim_end().to_owned()
+        };
+
+        let mut out = String::new();
+        writeln!(out, "{}", line(&self.columns)).unwrap();
+        let separator = widths.iter().map(|w| "-".repeat(w + 2)).collect::<Vec<_>>();
+        writeln!(out, "{}", separator.join("+")).unwrap();
+        for row in &self.rows {
+            writeln!(out, "{}", line(row)).unwrap();
+        }
+        match self.rows.len() {
+            1 => writeln!(out, "(1 row)").unwrap(),
+            n => writeln!(out, "({n} rows)").unwrap(),
+        }
+        out
+    }
+
+    fn render_expanded(&self) -> String {
+        let width = self.columns.iter().map(|c| c.width()).max().unwrap_or(0);
+
+        let mut out = String::new();
+        if self.rows.is_empty() {
+            writeln!(out, "(0 rows)").unwrap();
+        }
+        for (i, row) in self.rows.iter().enumerate() {
+            writeln!(out, "-[ RECORD {} ]-", i + 1).unwrap();
+            for (column, value) in self.columns.iter().zip(row) {
+                writeln!(out, "{column}{} | {value}", pad(column, width)).unwrap();
+            }
+        }
+        // Values may be empty, don't leave trailing spaces.
+        out.lines()
+            .map(|l| l.trim_end().to_owned() + "\n")
+            .collect()
+    }
+
+    /// With a header line, quoting values only when needed as in RFC 4180.
+    fn render_csv(&self) -> String {
+        fn quote(value: &str) -> String {
+            if value.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", value.replace('"', "\"\""))
+            } else {
+                value.to_owned()
+            }
+        }
+
+        let mut out = String::new();
+        for row in std::iter::once(&self.columns).chain(&self.rows) {
+            let values = row.iter().map(|v| quote(v)).collect::<Vec<_>>();
+            writeln!(out, "{}", values.join(",")).unwrap();
+        }
+        out
+    }
+
+    /// One object per line, keyed by column names.
+    fn render_json(&self) -> String {
+        let mut out = String::new();
+        for row in &self.rows {
+            let object = self
+                .columns
+                .iter()
+                .zip(row)
+                .map(|(c, v)| (c.clone(), serde_json::Value::String(v.clone())))
+                .collect::<serde_json::Map<_, _>>();
+            writeln!(out, "{}", serde_json::Value::Object(object)).unwrap();
+        }
+        out
+    }
+
+    fn render_markdown(&self) -> String {
+        let line = |values: &[String]| {
+            let cells = values
+                .iter()
+                .map(|v| v.replace('|', "\\|").replace('\n', "<br>"))
+                .collect::<Vec<_>>();
+            format!("| {} |", cells.join(" | "))
+        };
+
+        let mut out = String::new();
+        writeln!(out, "{}", line(&self.columns)).unwrap();
+        let separator = vec!["---".to_owned(); self.columns.len()];
+        writeln!(out, "{}", line(&separator)).unwrap();
+        for row in &self.rows {
+            writeln!(out, "{}", line(row)).unwrap();
+        }
+        out
+    }
+}
+
+/// Spaces after `value` to fill `width` columns of the terminal. Formatting with `{:width$}`
+/// counts chars instead, which misaligns wide ones, e.g., CJK.
+fn pad(value: &str, width: usize) -> String {
+    " ".repeat(width.saturating_sub(value.width()))
+}
+
+/// How result sets are printed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Aligned columns with headers, like psql.
+    #[default]
+    Table,
+    Csv,
+    /// JSON lines, i.e., one object per row.
+    Json,
+    Markdown,
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let mut rs = ResultSet::new(
+            &["Field", "Type", "Key"],
+            vec![
+                vec!["id".to_owned(), "INT".to_owned(), "PRI".to_owned()],
+                vec!["note".to_owned(), "VARCHAR(20)".to_owned(), String::new()],
+                vec!["a|\"b\", c".to_owned(), "TEXT".to_owned(), String::new()],
+                vec!["名前".to_owned(), "TEXT".to_owned(), String::new()],
+            ],
+        );
+        rs.footer.push("Referenced by: v".to_owned());
+
+        let out = [Format::Table, Format::Csv, Format::Json, Format::Markdown]
+            .into_iter()
+            .map(|f| rs.render(f, false))
+            .chain([rs.render(Format::Table, true)])
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        expect![[r#"
+             Field    | Type        | Key
+            ----------+-------------+-----
+             id       | INT         | PRI
+             note     | VARCHAR(20) |
+             a|"b", c | TEXT        |
+             名前     | TEXT        |
+            (4 rows)
+            Referenced by: v
+
+            Field,Type,Key
+            id,INT,PRI
+            note,VARCHAR(20),
+            "a|""b"", c",TEXT,
+            名前,TEXT,
+
+            {"Field":"id","Type":"INT","Key":"PRI"}
+            {"Field":"note","Type":"VARCHAR(20)","Key":""}
+            {"Field":"a|\"b\", c","Type":"TEXT","Key":""}
+            {"Field":"名前","Type":"TEXT","Key":""}
+
+            | Field | Type | Key |
+            | --- | --- | --- |
+            | id | INT | PRI |
+            | note | VARCHAR(20) |  |
+            | a\|"b", c | TEXT |  |
+            | 名前 | TEXT |  |
+
+            Referenced by: v
+
+            -[ RECORD 1 ]-
+            Field | id
+            Type  | INT
+            Key   | PRI
+            -[ RECORD 2 ]-
+            Field | note
+            Type  | VARCHAR(20)
+            Key   |
+            -[ RECORD 3 ]-
+            Field | a|"b", c
+            Type  | TEXT
+            Key   |
+            -[ RECORD 4 ]-
+            Field | 名前
+            Type  | TEXT
+            Key   |
+            Referenced by: v
+        "#]]
+        .assert_eq(&out);
+    }
+}
@@ -0,0 +1,509 @@
+//! Parser for the statements accepted by [`Session`](crate::session::Session).
+//!
+//! Most statements are plain SQL and handled by `sqlparser` directly. Catalog-specific commands
+//! that no SQL dialect knows about (like `DIFF DATABASE`) are recognized here first, and everything
+//! else is delegated to [`Parser::parse_statement`].
+
+use std::fmt;
+
+use sqlparser::ast;
+use sqlparser::dialect::GenericDialect;
+use sqlparser::keywords::Keyword;
+use sqlparser::parser::{Parser, ParserError};
+use sqlparser::tokenizer::Token;
+
+#[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::large_enum_variant)] // most statements are `Sql` anyway
+pub enum Statement {
+    /// A standard SQL statement.
+    Sql(ast::Statement),
+
+    /// `DIFF DATABASE <from> WITH <to>`
+    DiffDatabase { from: ast::Ident, to: ast::Ident },
+
+    /// `GENERATE ENTITY <table> [INTO '<path>']`
+    GenerateEntity {
+        table_name: ast::ObjectName,
+        path: Option<String>,
+    },
+
+    /// `IMPORT SCHEMA FROM '<path>' INTO <database>`
+    ImportSchema { path: String, db_name: ast::Ident },
+
+    /// `SHOW SESSIONS`
+    ShowSessions,
+
+    /// `SHOW CACHE`
+    ShowCache,
+
+    /// `CHECK CATALOG [REPAIR]`
+    CheckCatalog { repair: bool },
+
+    /// `REFRESH MATERIALIZED VIEW <name>`
+    RefreshMaterializedView { name: ast::ObjectName },
+
+    /// `DROP MATERIALIZED VIEW <name>, ...`
+    DropMaterializedView { names: Vec<ast::ObjectName> },
+
+    /// `SHOW MATERIALIZED VIEWS`
+    ShowMaterializedViews,
+
+    /// `ALTER SEQUENCE <name> <option> ...`, with the options of `CREATE SEQUENCE` and
+    /// `RESTART [[WITH] <value>]`.
+    AlterSequence {
+        name: ast::ObjectName,
+        options: Vec<ast::SequenceOptions>,
+        /// The value to restart at, if any, or the start value if `None`.
+        restart: Option<Option<ast::Expr>>,
+    },
+
+    /// `CREATE TYPE <name> AS ENUM ('<label>', ...)` or `CREATE TYPE <name> AS (<attribute>
+    /// <type>, ...)`
+    CreateType {
+        name: ast::ObjectName,
+        representation: TypeRepresentation,
+    },
+
+    /// `CREATE DOMAIN <name> [AS] <type> [DEFAULT <expr>] [[NOT] NULL] [CHECK (<expr>)] ...`
+    CreateDomain {
+        name: ast::ObjectName,
+        data_type: ast::DataType,
+        default: Option<ast::Expr>,
+        not_null: bool,
+        checks: Vec<ast::Expr>,
+    },
+
+    /// `DROP TYPE <name>, ...`, or `DROP DOMAIN <name>, ...` which only drops domains.
+    DropType {
+        names: Vec<ast::ObjectName>,
+        domain: bool,
+    },
+}
+
+/// What a type created by `CREATE TYPE` is made of.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeRepresentation {
+    Enum(Vec<String>),
+    Composite(Vec<ast::UserDefinedTypeCompositeAttributeDef>),
+}
+
+impl Statement {
+    /// Whether the statement reads or writes files on the server, so that network front ends
+    /// must reject it.
+    pub fn is_local_only(&self) -> bool {
+        matches!(
+            self,
+            Statement::GenerateEntity { path: Some(_), .. } | Statement::ImportSchema { .. }
+        )
+    }
+}
+
+impl From<ast::Statement> for Statement {
+    fn from(stmt: ast::Statement) -> Self {
+        Self::Sql(stmt)
+    }
+}
+
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Statement::Sql(stmt) => write!(f, "{stmt}"),
+            Statement::DiffDatabase { from, to } => write!(f, "DIFF DATABASE {from} WITH {to}"),
+            Statement::GenerateEntity { table_name, path } => {
+                write!(f, "GENERATE ENTITY {table_name}")?;
+                if let Some(path) = path {
+                    write!(f, " INTO {}", ast::Value::SingleQuotedString(path.clone()))?;
+                }
+                Ok(())
+            }
+            Statement::ImportSchema { path, db_name } => write!(
+                f,
+                "IMPORT SCHEMA FROM {} INTO {db_name}",
+                ast::Value::SingleQuotedString(path.clone())
+            ),
+            Statement::ShowSessions => write!(f, "SHOW SESSIONS"),
+            Statement::ShowCache => write!(f, "SHOW CACHE"),
+            Statement::CheckCatalog { repair } => {
+                write!(f, "CHECK CATALOG")?;
+                if *repair {
+                    write!(f, " REPAIR")?;
+                }
+                Ok(())
+            }
+            Statement::RefreshMaterializedView { name } => {
+                write!(f, "REFRESH MATERIALIZED VIEW {name}")
+            }
+            Statement::DropMaterializedView { names } => {
+                let names = names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+                write!(f, "DROP MATERIALIZED VIEW {}", names.join(", "))
+            }
+            Statement::ShowMaterializedViews => write!(f, "SHOW MATERIALIZED VIEWS"),
+            Statement::AlterSequence {
+                name,
+                options,
+                restart,
+            } => {
+                write!(f, "ALTER SEQUENCE {name}")?;
+                for option in options {
+                    write!(f, "{option}")?;
+                }
+                match restart {
+                    Some(Some(value)) => write!(f, " RESTART WITH {value}"),
+                    Some(None) => write!(f, " RESTART"),
+                    None => Ok(()),
+                }
+            }
+            Statement::CreateType {
+                name,
+                representation: TypeRepresentation::Enum(labels),
+            } => {
+                let labels = labels
+                    .iter()
+                    .map(|l| ast::Value::SingleQuotedString(l.clone()).to_string())
+                    .collect::<Vec<_>>();
+                write!(f, "CREATE TYPE {name} AS ENUM ({})", labels.join(", "))
+            }
+            Statement::CreateType {
+                name,
+                representation: TypeRepresentation::Composite(attributes),
+            } => {
+                let attributes = attributes.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+                write!(f, "CREATE TYPE {name} AS ({})", attributes.join(", "))
+            }
+            Statement::CreateDomain {
+                name,
+                data_type,
+                default,
+                not_null,
+                checks,
+            } => {
+                write!(f, "CREATE DOMAIN {name} AS {data_type}")?;
+                if let Some(default) = default {
+                    write!(f, " DEFAULT {default}")?;
+                }
+                if *not_null {
+                    write!(f, " NOT NULL")?;
+                }
+                for check in checks {
+                    write!(f, " CHECK ({check})")?;
+                }
+                Ok(())
+            }
+            Statement::DropType { names, domain } => {
+                let names = names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+                let object_type = if *domain { "DOMAIN" } else { "TYPE" };
+                write!(f, "DROP {object_type} {}", names.join(", "))
+            }
+        }
+    }
+}
+
+/// Parse a string with one or more statements separated by `;`.
+pub fn parse(sql: &str) -> Result<Vec<Statement>, ParserError> {
+    let dialect = GenericDialect {};
+    let mut parser = Parser::new(&dialect).try_with_sql(sql)?;
+
+    // Mostly the same as `Parser::parse_statements`.
+    let mut stmts = Vec::new();
+    let mut expecting_statement_delimiter = false;
+    loop {
+        while parser.consume_token(&Token::SemiColon) {
+            expecting_statement_delimiter = false;
+        }
+
+        if parser.peek_token().token == Token::EOF {
+            break;
+        }
+        if expecting_statement_delimiter {
+            return parser.expected("end of statement", parser.peek_token());
+        }
+
+        stmts.push(parse_statement(&mut parser)?);
+        expecting_statement_delimiter = true;
+    }
+
+    Ok(stmts)
+}
+
+fn parse_statement(parser: &mut Parser) -> Result<Statement, ParserError> {
+    if parse_word(parser, "DIFF") {
+        parser.expect_keyword(Keyword::DATABASE)?;
+        let from = parser.parse_identifier()?;
+        parser.expect_keyword(Keyword::WITH)?;
+        let to = parser.parse_identifier()?;
+        return Ok(Statement::DiffDatabase { from, to });
+    }
+
+    if parse_word(parser, "GENERATE") {
+        if !parse_word(parser, "ENTITY") {
+            return parser.expected("ENTITY", parser.peek_token());
+        }
+        let table_name = parser.parse_object_name()?;
+        let path = if parser.parse_keyword(Keyword::INTO) {
+            Some(parser.parse_literal_string()?)
+        } else {
+            None
+        };
+        return Ok(Statement::GenerateEntity { table_name, path });
+    }
+
+    if parse_word(parser, "IMPORT") {
+        parser.expect_keyword(Keyword::SCHEMA)?;
+        parser.expect_keyword(Keyword::FROM)?;
+        let path = parser.parse_literal_string()?;
+        parser.expect_keyword(Keyword::INTO)?;
+        let db_name = parser.parse_identifier()?;
+        return Ok(Statement::ImportSchema { path, db_name });
+    }
+
+    if parse_word(parser, "CHECK") {
+        if !parse_word(parser, "CATALOG") {
+            return parser.expected("CATALOG", parser.peek_token());
+        }
+        let repair = parse_word(parser, "REPAIR");
+        return Ok(Statement::CheckCatalog { repair });
+    }
+
+    if parse_word(parser, "REFRESH") {
+        parser.expect_keywords(&[Keyword::MATERIALIZED, Keyword::VIEW])?;
+        let name = parser.parse_object_name()?;
+        return Ok(Statement::RefreshMaterializedView { name });
+    }
+
+    // `sqlparser` knows none of these, or parses them differently.
+    if let Token::Word(w) = parser.peek_token().token {
+        let next = parser.peek_nth_token(1).token;
+        match w.keyword {
+            Keyword::CREATE if is_word(&next, "DOMAIN") => {
+                parser.next_token();
+                parser.next_token();
+                return parse_create_domain(parser);
+            }
+            Keyword::CREATE if is_word(&next, "SEQUENCE") => {
+                parser.next_token();
+                parser.next_token();
+                return parse_create_sequence(parser);
+            }
+            Keyword::CREATE if is_word(&next, "TYPE") => {
+                parser.next_token();
+                parser.next_token();
+                return parse_create_type(parser);
+            }
+            Keyword::ALTER if is_word(&next, "SEQUENCE") => {
+                parser.next_token();
+                parser.next_token();
+                let name = parser.parse_object_name()?;
+                let (options, restart) = parse_sequence_options(parser, true)?;
+                return Ok(Statement::AlterSequence {
+                    name,
+                    options,
+                    restart,
+                });
+            }
+            Keyword::DROP if is_word(&next, "TYPE") || is_word(&next, "DOMAIN") => {
+                parser.next_token();
+                let domain = is_word(&parser.next_token().token, "DOMAIN");
+                let names = parser.parse_comma_separated(Parser::parse_object_name)?;
+                return Ok(Statement::DropType { names, domain });
+            }
+            Keyword::DROP if is_word(&next, "MATERIALIZED") => {
+                parser.next_token();
+                parser.next_token();
+                parser.expect_keyword(Keyword::VIEW)?;
+                let names = parser.parse_comma_separated(Parser::parse_object_name)?;
+                return Ok(Statement::DropMaterializedView { names });
+            }
+            // Otherwise parsed as `SHOW <variable>`, like `SHOW SESSIONS` and `SHOW CACHE`.
+            Keyword::SHOW if is_word(&next, "MATERIALIZED") => {
+                parser.next_token();
+                parser.next_token();
+                if !parse_word(parser, "VIEWS") {
+                    return parser.expected("VIEWS", parser.peek_token());
+                }
+                return Ok(Statement::ShowMaterializedViews);
+            }
+            Keyword::SHOW if is_word(&next, "SESSIONS") => {
+                parser.next_token();
+                parser.next_token();
+                return Ok(Statement::ShowSessions);
+            }
+            Keyword::SHOW if is_word(&next, "CACHE") => {
+                parser.next_token();
+                parser.next_token();
+                return Ok(Statement::ShowCache);
+            }
+            _ => {}
+        }
+    }
+
+    parser.parse_statement().map(Statement::Sql)
+}
+
+/// Parse what follows `CREATE SEQUENCE`. `sqlparser` only accepts the options in a fixed order,
+/// and no negative values.
+fn parse_create_sequence(parser: &mut Parser) -> Result<Statement, ParserError> {
+    let if_not_exists = parser.parse_keywords(&[Keyword::IF, Keyword::NOT, Keyword::EXISTS]);
+    let name = parser.parse_object_name()?;
+    let data_type = if parser.parse_keyword(Keyword::AS) {
+        Some(parser.parse_data_type()?)
+    } else {
+        None
+    };
+    let (sequence_options, _) = parse_sequence_options(parser, false)?;
+    let owned_by = if parser.parse_keywords(&[Keyword::OWNED, Keyword::BY]) {
+        Some(parser.parse_object_name()?)
+    } else {
+        None
+    };
+    Ok(Statement::Sql(ast::Statement::CreateSequence {
+        temporary: false,
+        if_not_exists,
+        name,
+        data_type,
+        sequence_options,
+        owned_by,
+    }))
+}
+
+/// Parse what follows `CREATE TYPE`. `sqlparser` only knows composite types.
+fn parse_create_type(parser: &mut Parser) -> Result<Statement, ParserError> {
+    let name = parser.parse_object_name()?;
+    parser.expect_keyword(Keyword::AS)?;
+    let representation = if parse_word(parser, "ENUM") {
+        parser.expect_token(&Token::LParen)?;
+        TypeRepresentation::Enum(parse_list(parser, Parser::parse_literal_string)?)
+    } else {
+        parser.expect_token(&Token::LParen)?;
+        TypeRepresentation::Composite(parse_list(parser, |parser| {
+            Ok(ast::UserDefinedTypeCompositeAttributeDef {
+                name: parser.parse_identifier()?,
+                data_type: parser.parse_data_type()?,
+                collation: None,
+            })
+        })?)
+    };
+    Ok(Statement::CreateType {
+        name,
+        representation,
+    })
+}
+
+/// Parse what follows `CREATE DOMAIN`.
+fn parse_create_domain(parser: &mut Parser) -> Result<Statement, ParserError> {
+    let name = parser.parse_object_name()?;
+    let _ = parser.parse_keyword(Keyword::AS);
+    let data_type = parser.parse_data_type()?;
+
+    let (mut default, mut not_null, mut checks) = (None, false, Vec::new());
+    loop {
+        if parser.parse_keyword(Keyword::DEFAULT) {
+            default = Some(parser.parse_expr()?);
+        } else if parser.parse_keywords(&[Keyword::NOT, Keyword::NULL]) {
+            not_null = true;
+        } else if parser.parse_keyword(Keyword::NULL) {
+            not_null = false;
+        } else if parser.parse_keyword(Keyword::CHECK) {
+            parser.expect_token(&Token::LParen)?;
+            checks.push(parser.parse_expr()?);
+            parser.expect_token(&Token::RParen)?;
+        } else {
+            return Ok(Statement::CreateDomain {
+                name,
+                data_type,
+                default,
+                not_null,
+                checks,
+            });
+        }
+    }
+}
+
+/// Parse a comma-separated list, which may be empty, up to the closing parenthesis.
+fn parse_list<'a, T>(
+    parser: &mut Parser<'a>,
+    f: impl FnMut(&mut Parser<'a>) -> Result<T, ParserError>,
+) -> Result<Vec<T>, ParserError> {
+    if parser.consume_token(&Token::RParen) {
+        return Ok(Vec::new());
+    }
+    let items = parser.parse_comma_separated(f)?;
+    parser.expect_token(&Token::RParen)?;
+    Ok(items)
+}
+
+/// Parse the options of `CREATE SEQUENCE`, in any order, and `RESTART` if `alter` is set.
+fn parse_sequence_options(
+    parser: &mut Parser,
+    alter: bool,
+) -> Result<(Vec<ast::SequenceOptions>, Option<Option<ast::Expr>>), ParserError> {
+    use ast::{MinMaxValue, SequenceOptions};
+
+    let mut options = Vec::new();
+    let mut restart = None;
+    loop {
+        if parser.parse_keyword(Keyword::INCREMENT) {
+            let by = parser.parse_keyword(Keyword::BY);
+            options.push(SequenceOptions::IncrementBy(parse_signed(parser)?, by));
+        } else if parser.parse_keyword(Keyword::MINVALUE) {
+            let value = MinMaxValue::Some(parse_signed(parser)?);
+            options.push(SequenceOptions::MinValue(value));
+        } else if parser.parse_keyword(Keyword::MAXVALUE) {
+            let value = MinMaxValue::Some(parse_signed(parser)?);
+            options.push(SequenceOptions::MaxValue(value));
+        } else if parser.parse_keywords(&[Keyword::NO, Keyword::MINVALUE]) {
+            options.push(SequenceOptions::MinValue(MinMaxValue::None));
+        } else if parser.parse_keywords(&[Keyword::NO, Keyword::MAXVALUE]) {
+            options.push(SequenceOptions::MaxValue(MinMaxValue::None));
+        } else if parser.parse_keyword(Keyword::START) {
+            let with = parser.parse_keyword(Keyword::WITH);
+            options.push(SequenceOptions::StartWith(parse_signed(parser)?, with));
+        } else if parser.parse_keyword(Keyword::CACHE) {
+            options.push(SequenceOptions::Cache(parse_signed(parser)?));
+        } else if parser.parse_keyword(Keyword::CYCLE) {
+            options.push(SequenceOptions::Cycle(false));
+        } else if parser.parse_keywords(&[Keyword::NO, Keyword::CYCLE]) {
+            options.push(SequenceOptions::Cycle(true));
+        } else if alter && parse_word(parser, "RESTART") {
+            restart = Some(match parser.peek_token().token {
+                Token::Word(w) if w.keyword == Keyword::WITH => {
+                    parser.next_token();
+                    Some(parse_signed(parser)?)
+                }
+                Token::Number(..) | Token::Minus => Some(parse_signed(parser)?),
+                _ => None,
+            });
+        } else {
+            return Ok((options, restart));
+        }
+    }
+}
+
+/// An integer literal, possibly negative.
+fn parse_signed(parser: &mut Parser) -> Result<ast::Expr, ParserError> {
+    let negative = parser.consume_token(&Token::Minus);
+    let value = ast::Expr::Value(parser.parse_number_value()?);
+    Ok(match negative {
+        true => ast::Expr::UnaryOp {
+            op: ast::UnaryOperator::Minus,
+            expr: Box::new(value),
+        },
+        false => value,
+    })
+}
+
+/// Consume the next token if it's an unquoted word equal to `expected`, ignoring case.
+///
+/// Unlike [`Parser::parse_keyword`], this also works for words that are not keywords of
+/// `sqlparser`.
+fn parse_word(parser: &mut Parser, expected: &str) -> bool {
+    if is_word(&parser.peek_token().token, expected) {
+        parser.next_token();
+        true
+    } else {
+        false
+    }
+}
+
+fn is_word(token: &Token, expected: &str) -> bool {
+    matches!(token, Token::Word(w) if w.quote_style.is_none() && w.value.eq_ignore_ascii_case(expected))
+}
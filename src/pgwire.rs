@@ -0,0 +1,451 @@
+//! A server speaking the Postgres wire protocol, so that the catalog can be used with psql and
+//! other Postgres clients.
+//!
+//! Only the simple query protocol is supported, with all values sent as text. See
+//! <https://www.postgresql.org/docs/current/protocol-flow.html>.
+
+use std::net::SocketAddr;
+
+use anyhow::{bail, Context, Result};
+use sqlparser::ast;
+use sqlparser::parser::ParserError;
+use tokio::io::{AsyncReadExt, AsyncWriteExt, BufReader, BufWriter};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::catalog::Catalog;
+use crate::error::{AlreadyExists, ConcurrentModification, InUse, LocalOnly, NotFound};
+use crate::output::ResultSet;
+use crate::parser::{self, Statement};
+use crate::session::Session;
+
+const PROTOCOL_VERSION: i32 = 196608; // 3.0
+const SSL_REQUEST: i32 = 80877103;
+const GSSENC_REQUEST: i32 = 80877104;
+const CANCEL_REQUEST: i32 = 80877102;
+
+/// Largest startup message accepted, as its parameters are just a few names.
+const MAX_STARTUP_MESSAGE_LEN: usize = 1 << 20;
+/// Largest message accepted after startup, e.g., a query.
+const MAX_MESSAGE_LEN: usize = 64 << 20;
+
+/// Type OID of `text`, the type of all columns we return.
+const TEXT_OID: i32 = 25;
+
+/// Accept connections on `listener` until Ctrl-C, with one session for each.
+pub async fn serve(catalog: Catalog, listener: TcpListener) -> Result<()> {
+    loop {
+        let (stream, addr) = tokio::select! {
+            accepted = listener.accept() => accepted?,
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        };
+
+        let session = catalog.session(addr.to_string());
+        tokio::spawn(async move {
+            if let Err(e) = Connection::new(stream, session).run().await {
+                tracing::error!("connection from {addr}: {e:#}");
+            }
+        });
+    }
+}
+
+/// Bind to `addr` and serve until Ctrl-C.
+pub async fn listen(catalog: Catalog, addr: SocketAddr) -> Result<()> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("failed to listen on {addr}"))?;
+    println!("listening on {}", listener.local_addr()?);
+
+    serve(catalog, listener).await
+}
+
+struct Connection {
+    reader: BufReader<OwnedReadHalf>,
+    writer: BufWriter<OwnedWriteHalf>,
+    session: Session,
+}
+
+impl Connection {
+    fn new(stream: TcpStream, session: Session) -> Self {
+        let (reader, writer) = stream.into_split();
+        Self {
+            reader: BufReader::new(reader),
+            writer: BufWriter::new(writer),
+            session,
+        }
+    }
+
+    async fn run(mut self) -> Result<()> {
+        let Some(params) = self.startup().await? else {
+            return Ok(());
+        };
+
+        // Clients usually default the database to the user name, which may not exist in the
+        // catalog. Start without one then.
+        if let Some((_, database)) = params.iter().find(|(k, _)| k == "database") {
+            let stmt = ast::Statement::Use {
+                db_name: ast::Ident::new(database),
+            };
+            let _ = self.session.handle(stmt.into()).await;
+        }
+
+        self.send(b'R', &0i32.to_be_bytes()).await?; // AuthenticationOk
+        for (name, value) in [
+            ("server_version", "14.0"),
+            ("server_encoding", "UTF8"),
+            ("client_encoding", "UTF8"),
+            ("DateStyle", "ISO, MDY"),
+            ("integer_datetimes", "on"),
+            ("standard_conforming_strings", "on"),
+        ] {
+            self.send(b'S', &[cstr(name), cstr(value)].concat()).await?;
+        }
+        self.ready_for_query().await?;
+
+        // Errors in the extended query protocol are reported once, then messages are skipped up to
+        // the next `Sync`.
+        let mut skipping = false;
+
+        loop {
+            let Some((tag, body)) = self.read_message().await? else {
+                return Ok(()); // closed without `Terminate`
+            };
+
+            match tag {
+                b'Q' => {
+                    let sql = std::str::from_utf8(body.strip_suffix(b"\0").unwrap_or(&body))
+                        .context("query is not valid UTF-8")?;
+                    self.simple_query(sql).await?;
+                    self.ready_for_query().await?;
+                }
+                b'X' => return Ok(()),
+                b'S' => {
+                    skipping = false;
+                    self.ready_for_query().await?;
+                }
+                b'P' | b'B' | b'D' | b'E' | b'C' | b'H' | b'F' => {
+                    if !skipping {
+                        skipping = true;
+                        self.error("0A000", "extended query protocol is not supported")
+                            .await?;
+                        self.writer.flush().await?;
+                    }
+                }
+                _ => bail!("unexpected message `{}`", tag as char),
+            }
+        }
+    }
+
+    /// Handle the startup phase, returning the parameters sent by the client, or `None` if it's
+    /// not a regular connection.
+    async fn startup(&mut self) -> Result<Option<Vec<(String, String)>>> {
+        loop {
+            let len = self.reader.read_i32().await?;
+            let code = self.reader.read_i32().await?;
+            let mut body = vec![0; body_len(len, 8, MAX_STARTUP_MESSAGE_LEN)?];
+            self.reader.read_exact(&mut body).await?;
+
+            match code {
+                // Encryption is not supported, and clients fall back to plain connections.
+                SSL_REQUEST | GSSENC_REQUEST => {
+                    self.writer.write_u8(b'N').await?;
+                    self.writer.flush().await?;
+                }
+                CANCEL_REQUEST => return Ok(None),
+                PROTOCOL_VERSION => {
+                    let strings = body
+                        .split(|b| *b == 0)
+                        .map(|s| String::from_utf8_lossy(s).into_owned())
+                        .collect::<Vec<_>>();
+                    let params = strings
+                        .chunks_exact(2)
+                        .take_while(|kv| !kv[0].is_empty())
+                        .map(|kv| (kv[0].clone(), kv[1].clone()))
+                        .collect();
+                    return Ok(Some(params));
+                }
+                _ => {
+                    self.error("08P01", &format!("unsupported protocol version {code}"))
+                        .await?;
+                    self.writer.flush().await?;
+                    bail!("unsupported protocol version {code}");
+                }
+            }
+        }
+    }
+
+    /// Execute all statements in `sql`, stopping at the first error, which is sent to the client.
+    /// Statements before it stay committed.
+    ///
+    /// Statements accessing files on the server are rejected, as the client may be anyone.
+    async fn simple_query(&mut self, sql: &str) -> Result<()> {
+        let stmts = match parser::parse(sql) {
+            Ok(stmts) => stmts,
+            Err(e) => return self.error("42601", &e.to_string()).await,
+        };
+        if stmts.is_empty() {
+            return self.send(b'I', &[]).await; // EmptyQueryResponse
+        }
+
+        for stmt in stmts {
+            let tag = command_tag(&stmt);
+            let result = match stmt.is_local_only() {
+                true => Err(LocalOnly::new(&stmt).into()),
+                false => self.session.handle(stmt).await,
+            };
+            match result {
+                Ok(Some(result)) => self.result_set(&result).await?,
+                Ok(None) => self.send(b'C', &cstr(&tag)).await?,
+                Err(e) => {
+                    let code = if e.downcast_ref::<ParserError>().is_some() {
+                        "42601" // syntax_error
+                    } else if e.downcast_ref::<NotFound>().is_some() {
+                        "42704" // undefined_object
+                    } else if e.downcast_ref::<AlreadyExists>().is_some() {
+                        "42710" // duplicate_object
+                    } else if e.downcast_ref::<InUse>().is_some() {
+                        "2BP01" // dependent_objects_still_exist
+                    } else if e.downcast_ref::<ConcurrentModification>().is_some() {
+                        "40001" // serialization_failure
+                    } else if e.downcast_ref::<LocalOnly>().is_some() {
+                        "42501" // insufficient_privilege
+                    } else {
+                        "XX000" // internal_error
+                    };
+                    return self.error(code, &format!("{e:#}")).await;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn result_set(&mut self, result: &ResultSet) -> Result<()> {
+        // RowDescription
+        let mut body = (result.columns.len() as i16).to_be_bytes().to_vec();
+        for column in &result.columns {
+            body.extend(cstr(column));
+            body.extend(0i32.to_be_bytes()); // table OID
+            body.extend(0i16.to_be_bytes()); // column number
+            body.extend(TEXT_OID.to_be_bytes());
+            body.extend((-1i16).to_be_bytes()); // variable length
+            body.extend((-1i32).to_be_bytes()); // no type modifier
+            body.extend(0i16.to_be_bytes()); // text format
+        }
+        self.send(b'T', &body).await?;
+
+        for row in &result.rows {
+            let mut body = (row.len() as i16).to_be_bytes().to_vec();
+            for value in row {
+                body.extend((value.len() as i32).to_be_bytes());
+                body.extend(value.as_bytes());
+            }
+            self.send(b'D', &body).await?;
+        }
+
+        self.send(b'C', &cstr(&format!("SELECT {}", result.rows.len())))
+            .await
+    }
+
+    async fn error(&mut self, code: &str, message: &str) -> Result<()> {
+        let body = [
+            &b"S"[..],
+            &cstr("ERROR"),
+            b"V",
+            &cstr("ERROR"),
+            b"C",
+            &cstr(code),
+            b"M",
+            &cstr(message),
+            b"\0",
+        ]
+        .concat();
+        self.send(b'E', &body).await
+    }
+
+    async fn ready_for_query(&mut self) -> Result<()> {
+        self.send(b'Z', b"I").await?; // idle, no transaction
+        self.writer.flush().await?;
+        Ok(())
+    }
+
+    async fn read_message(&mut self) -> Result<Option<(u8, Vec<u8>)>> {
+        let tag = match self.reader.read_u8().await {
+            Ok(tag) => tag,
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let len = self.reader.read_i32().await?;
+        let mut body = vec![0; body_len(len, 4, MAX_MESSAGE_LEN)?];
+        self.reader.read_exact(&mut body).await?;
+
+        Ok(Some((tag, body)))
+    }
+
+    async fn send(&mut self, tag: u8, body: &[u8]) -> Result<()> {
+        self.writer.write_u8(tag).await?;
+        self.writer.write_i32(body.len() as i32 + 4).await?;
+        self.writer.write_all(body).await?;
+        Ok(())
+    }
+}
+
+/// Length of the body of a message whose length field is `len`, including the `header` bytes
+/// already read.
+fn body_len(len: i32, header: usize, max: usize) -> Result<usize> {
+    usize::try_from(len)
+        .ok()
+        .filter(|len| *len <= max)
+        .and_then(|len| len.checked_sub(header))
+        .with_context(|| format!("invalid message length {len}"))
+}
+
+fn cstr(s: &str) -> Vec<u8> {
+    [s.as_bytes(), b"\0"].concat()
+}
+
+/// The tag of `CommandComplete` for statements returning no rows, e.g., `CREATE TABLE`.
+fn command_tag(stmt: &Statement) -> String {
+    use ast::Statement::*;
+
+    match stmt {
+        Statement::Sql(stmt) => match stmt {
+            CreateDatabase { .. } => "CREATE DATABASE".to_owned(),
+            CreateSchema { .. } => "CREATE SCHEMA".to_owned(),
+            Use { .. } => "USE".to_owned(),
+            CreateTable { .. } => "CREATE TABLE".to_owned(),
+            CreateView {
+                materialized: true, ..
+            } => "CREATE MATERIALIZED VIEW".to_owned(),
+            CreateView { .. } => "CREATE VIEW".to_owned(),
+            AlterTable { .. } => "ALTER TABLE".to_owned(),
+            CreateSequence { .. } => "CREATE SEQUENCE".to_owned(),
+            Drop { object_type, .. } => format!("DROP {object_type}"),
+            SetVariable { .. } => "SET".to_owned(),
+            _ => "OK".to_owned(),
+        },
+        Statement::DiffDatabase { .. } => "DIFF".to_owned(),
+        Statement::GenerateEntity { .. } => "GENERATE ENTITY".to_owned(),
+        Statement::ImportSchema { .. } => "IMPORT SCHEMA".to_owned(),
+        Statement::ShowSessions | Statement::ShowCache | Statement::ShowMaterializedViews => {
+            "SHOW".to_owned()
+        }
+        Statement::CheckCatalog { .. } => "CHECK CATALOG".to_owned(),
+        Statement::RefreshMaterializedView { .. } => "REFRESH MATERIALIZED VIEW".to_owned(),
+        Statement::DropMaterializedView { .. } => "DROP MATERIALIZED VIEW".to_owned(),
+        Statement::AlterSequence { .. } => "ALTER SEQUENCE".to_owned(),
+        Statement::CreateType { .. } => "CREATE TYPE".to_owned(),
+        Statement::CreateDomain { .. } => "CREATE DOMAIN".to_owned(),
+        Statement::DropType { domain: true, .. } => "DROP DOMAIN".to_owned(),
+        Statement::DropType { .. } => "DROP TYPE".to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+    use tokio_postgres::{NoTls, SimpleQueryMessage};
+
+    use super::*;
+    use crate::metastore;
+
+    #[tokio::test]
+    async fn test_pgwire() {
+        let meta = metastore::connect(metastore::MEMORY_URL).await.unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(serve(Catalog::new(meta), listener));
+
+        let (client, connection) =
+            tokio_postgres::connect(&format!("host=127.0.0.1 port={port} user=test"), NoTls)
+                .await
+                .unwrap();
+        tokio::spawn(connection);
+
+        fn render(messages: Vec<SimpleQueryMessage>) -> String {
+            let mut out = String::new();
+            for message in messages {
+                match message {
+                    SimpleQueryMessage::Row(row) => {
+                        let values = (0..row.len()).map(|i| row.get(i).unwrap());
+                        out += &format!("{}\n", values.collect::<Vec<_>>().join("|"));
+                    }
+                    SimpleQueryMessage::CommandComplete(n) => out += &format!("-- {n} rows\n"),
+                    _ => {}
+                }
+            }
+            out
+        }
+
+        let mut out = String::new();
+        out += &render(
+            client
+                .simple_query(
+                    "CREATE DATABASE shop; USE shop;
+                 CREATE TABLE users (id INT PRIMARY KEY, name TEXT);
+                 SHOW TABLES; EXPLAIN users;",
+                )
+                .await
+                .unwrap(),
+        );
+
+        let e = client.simple_query("DROP TABLE nope").await.unwrap_err();
+        out += &format!(
+            "{:?}: {}\n",
+            e.code().unwrap(),
+            e.as_db_error().unwrap().message()
+        );
+        let e = client.simple_query("SELECT FROM FROM").await.unwrap_err();
+        out += &format!("{:?}\n", e.code().unwrap());
+        for sql in [
+            "IMPORT SCHEMA FROM '/etc/passwd' INTO other",
+            "GENERATE ENTITY users INTO '/tmp/users.rs'",
+        ] {
+            let e = client.simple_query(sql).await.unwrap_err();
+            out += &format!(
+                "{:?}: {}\n",
+                e.code().unwrap(),
+                e.as_db_error().unwrap().message()
+            );
+        }
+
+        // The connection is still usable after errors.
+        out += &render(client.simple_query("SHOW TABLES").await.unwrap());
+
+        expect![[r#"
+            -- 0 rows
+            -- 0 rows
+            -- 0 rows
+            users
+            -- 1 rows
+            id|INT|PRI
+            name|TEXT|
+            -- 2 rows
+            SqlState(E42704): table `nope` not found
+            SqlState(E42601)
+            SqlState(E42501): `IMPORT SCHEMA FROM '/etc/passwd' INTO other` accesses files on the server and is only allowed from the command line
+            SqlState(E42501): `GENERATE ENTITY users INTO '/tmp/users.rs'` accesses files on the server and is only allowed from the command line
+            users
+            -- 1 rows
+        "#]]
+        .assert_eq(&out);
+    }
+
+    /// Lengths are checked before allocating the body, and the connection is closed.
+    #[tokio::test]
+    async fn test_invalid_length() {
+        let meta = metastore::connect(metastore::MEMORY_URL).await.unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(Catalog::new(meta), listener));
+
+        for len in [i32::MIN, -1, 4, i32::MAX] {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            stream.write_i32(len).await.unwrap();
+            stream.write_i32(PROTOCOL_VERSION).await.unwrap();
+            let mut buf = Vec::new();
+            stream.read_to_end(&mut buf).await.unwrap();
+            assert!(buf.is_empty(), "{len}: {buf:?}");
+        }
+    }
+}
@@ -1,28 +1,426 @@
-use anyhow::Result;
+mod helper;
 
+use std::path::PathBuf;
+use std::time::Instant;
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::ValueEnum;
+use rustyline::error::ReadlineError;
+use rustyline::history::DefaultHistory;
+use rustyline::Editor;
+use sqlparser::ast;
+use sqlparser::dialect::GenericDialect;
+use sqlparser::parser::ParserError;
+use sqlparser::tokenizer::{Token, Tokenizer};
+
+use self::helper::CatalogHelper;
+use crate::output::Format;
 use crate::session::Session;
 
+/// Settings changed by meta-commands, on top of those kept by the session.
+#[derive(Default)]
+struct Settings {
+    /// Print how long each statement takes, like `\timing` in psql.
+    timing: bool,
+    /// How many scripts are being run by `\i`, within each other.
+    include_depth: usize,
+}
+
+/// Scripts including each other deeper than this are assumed to recurse forever. Each level takes
+/// a lot of stack in debug builds, enough to overflow at 12 on a 2 MiB thread.
+const MAX_INCLUDE_DEPTH: usize = 8;
+
 pub async fn repl(session: &mut Session) -> Result<()> {
-    async fn handle_line(session: &mut Session, line: String) -> Result<()> {
-        let stmts =
-            sqlparser::parser::Parser::parse_sql(&sqlparser::dialect::GenericDialect {}, &line)?;
-        for stmt in stmts {
-            session.handle(stmt).await?;
-        }
-        Ok(())
+    let mut rl = Editor::<CatalogHelper, DefaultHistory>::new()?;
+    rl.set_helper(Some(CatalogHelper::new(session.meta().clone())));
+
+    let history = history_path();
+    if let Some(path) = &history {
+        // Missing on the first run.
+        let _ = rl.load_history(path);
     }
 
-    let mut rl = rustyline::DefaultEditor::new()?;
+    let mut settings = Settings::default();
+
+    // Lines of the statement being typed, until it's terminated with `;`.
+    let mut buffer = String::new();
 
     loop {
-        let line = rl.readline(&if let Some(db_name) = session.current_db_name() {
+        let db_name = session.current_db_name().unwrap_or_default();
+        let prompt = if buffer.is_empty() {
             format!("{}> ", db_name)
         } else {
-            "> ".to_string()
-        })?;
+            format!("{}-> ", db_name)
+        };
+
+        let helper = rl.helper_mut().expect("helper is set");
+        helper.current_db_id = session.current_db_id().ok();
+        helper.pending.clone_from(&buffer);
+
+        let line = match rl.readline(&prompt) {
+            Ok(line) => line,
+            // Ctrl-C: discard the current statement only.
+            Err(ReadlineError::Interrupted) => {
+                buffer.clear();
+                continue;
+            }
+            // Ctrl-D: exit, so that the caller can close the connection.
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        };
+
+        // Meta-commands take the whole line and need no `;`.
+        if buffer.is_empty() && line.trim_start().starts_with('\\') {
+            rl.add_history_entry(&line)?;
+            if let Err(e) = run_meta_command(session, &mut settings, &line).await {
+                tracing::error!("{:#}", e);
+            }
+            continue;
+        }
 
-        if let Err(e) = handle_line(session, line).await {
-            tracing::error!("{:#}", e)
+        if buffer.is_empty() && line.trim().is_empty() {
+            continue;
         }
+        if !buffer.is_empty() {
+            buffer.push('\n');
+        }
+        buffer.push_str(&line);
+
+        if !is_complete(&buffer) {
+            continue;
+        }
+        let sql = std::mem::take(&mut buffer);
+        rl.add_history_entry(&sql)?;
+
+        if let Err(e) = run_sql(session, &settings, &sql).await {
+            match e.downcast_ref::<ParserError>() {
+                Some(e) => tracing::error!("{}", render_parse_error(&sql, e)),
+                None => tracing::error!("{:#}", e),
+            }
+        }
+    }
+
+    if let Some(path) = &history {
+        if let Err(e) = rl.save_history(path) {
+            tracing::error!("failed to save history to `{}`: {}", path.display(), e);
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_sql(session: &mut Session, settings: &Settings, sql: &str) -> Result<()> {
+    let start = Instant::now();
+    let result = session.execute(sql).await;
+    if settings.timing {
+        println!("Time: {:.3} ms", start.elapsed().as_secs_f64() * 1000.0);
+    }
+    result
+}
+
+/// Run a script as if it were typed into the REPL, stopping at the first error.
+async fn run_script(session: &mut Session, settings: &mut Settings, script: &str) -> Result<()> {
+    let mut buffer = String::new();
+
+    for line in script.lines() {
+        if buffer.is_empty() && line.trim_start().starts_with('\\') {
+            // `\i` may include another script.
+            Box::pin(run_meta_command(session, settings, line)).await?;
+            continue;
+        }
+
+        if !buffer.is_empty() {
+            buffer.push('\n');
+        }
+        buffer.push_str(line);
+
+        if is_complete(&buffer) {
+            run_sql(session, settings, &std::mem::take(&mut buffer)).await?;
+        }
+    }
+
+    // Like `exec`, the last statement doesn't need a `;`.
+    if !buffer.trim().is_empty() {
+        run_sql(session, settings, &buffer).await?;
+    }
+
+    Ok(())
+}
+
+const HELP: &str = "\
+General
+  \\?                     show this help
+  \\i FILE                execute statements and meta-commands from FILE
+  \\timing [on|off]       toggle printing the time taken by each statement
+  \\x [on|off]            toggle expanded output
+  \\pset format [NAME]    set the output format: table, csv, json or markdown
+
+Informational
+  \\l                     list databases
+  \\c DATABASE            connect to (use) another database
+  \\d [NAME]              describe a relation, sequence or type, or list relations and sequences
+  \\dm                    list materialized views
+  \\ds                    list sequences
+  \\dt                    list tables
+  \\dT                    list user-defined types
+  \\dv                    list views";
+
+/// Run a psql-style meta-command, e.g., `\dt`.
+async fn run_meta_command(
+    session: &mut Session,
+    settings: &mut Settings,
+    line: &str,
+) -> Result<()> {
+    let mut args = line.trim().trim_start_matches('\\').split_whitespace();
+    let command = args.next().unwrap_or_default();
+    let arg = args.next();
+
+    // The only one with two arguments.
+    if let ("pset", Some("format")) = (command, arg) {
+        if let Some(name) = args.next() {
+            session.format = Format::from_str(name, true).map_err(|_| {
+                anyhow!("\\pset: unknown format `{name}`, expected table, csv, json or markdown")
+            })?;
+        }
+        let name = session
+            .format
+            .to_possible_value()
+            .expect("no skipped variants");
+        println!("Output format is {}.", name.get_name());
+        return Ok(());
+    }
+
+    if let Some(extra) = args.next() {
+        bail!("\\{command}: extra argument `{extra}`");
+    }
+
+    match (command, arg) {
+        ("?", None) => println!("{HELP}"),
+        ("l", None) => session.print(&session.list_databases().await?),
+        ("c", Some(db_name)) => {
+            let stmt = ast::Statement::Use {
+                db_name: ast::Ident::new(db_name),
+            };
+            session.handle(stmt.into()).await?;
+            println!("now using database `{db_name}`");
+        }
+        ("d", None) => {
+            let object_types = ["table", "view", "materialized_view", "sequence"];
+            session.print(&session.list_objects(&object_types).await?)
+        }
+        ("d", Some(name)) => session.print(&session.describe(name).await?),
+        ("dm", None) => session.print(&session.list_objects(&["materialized_view"]).await?),
+        ("ds", None) => session.print(&session.list_objects(&["sequence"]).await?),
+        ("dt", None) => session.print(&session.list_objects(&["table"]).await?),
+        ("dv", None) => session.print(&session.list_objects(&["view"]).await?),
+        ("dT", None) => session.print(&session.list_objects(&["type"]).await?),
+        ("i", Some(path)) => {
+            if settings.include_depth >= MAX_INCLUDE_DEPTH {
+                bail!("\\i: scripts nested more than {MAX_INCLUDE_DEPTH} deep, at `{path}`");
+            }
+            let script = std::fs::read_to_string(path)
+                .with_context(|| format!("failed to read `{path}`"))?;
+            settings.include_depth += 1;
+            let result = run_script(session, settings, &script).await;
+            settings.include_depth -= 1;
+            result.with_context(|| format!("failed to run `{path}`"))?;
+        }
+        ("timing", arg) => {
+            settings.timing = toggle(settings.timing, arg)?;
+            println!("Timing is {}.", if settings.timing { "on" } else { "off" });
+        }
+        ("pset", _) => bail!("\\pset: expected `format [NAME]`"),
+        ("x", arg) => {
+            session.expanded = toggle(session.expanded, arg)?;
+            println!(
+                "Expanded display is {}.",
+                if session.expanded { "on" } else { "off" }
+            );
+        }
+        ("c" | "i", None) => bail!("\\{command}: missing required argument"),
+        ("?" | "l" | "dm" | "ds" | "dt" | "dT" | "dv", Some(arg)) => {
+            bail!("\\{command}: extra argument `{arg}`")
+        }
+        _ => bail!("invalid command \\{command}, try \\? for help"),
+    }
+
+    Ok(())
+}
+
+fn toggle(value: bool, arg: Option<&str>) -> Result<bool> {
+    match arg {
+        None => Ok(!value),
+        Some("on") => Ok(true),
+        Some("off") => Ok(false),
+        Some(arg) => bail!("unrecognized value `{arg}`, expected `on` or `off`"),
+    }
+}
+
+/// History is kept in `~/.catalog_history`, if the home directory is known.
+fn history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".catalog_history"))
+}
+
+/// Whether `sql` ends with a `;` outside of any string literal or comment.
+fn is_complete(sql: &str) -> bool {
+    match Tokenizer::new(&GenericDialect {}, sql).tokenize() {
+        Ok(tokens) => tokens
+            .iter()
+            .rev()
+            .find(|t| !matches!(t, Token::Whitespace(_)))
+            .is_some_and(|t| *t == Token::SemiColon),
+
+        // Wait for the rest of the literal or comment.
+        Err(e)
+            if e.message.starts_with("Unterminated")
+                || e.message.starts_with("Unexpected EOF")
+                || e.message.starts_with("Expected close delimiter") =>
+        {
+            false
+        }
+        // Let the parser report the error.
+        Err(_) => true,
+    }
+}
+
+/// Show the line of `sql` where the error occurs, with a caret pointing at the column.
+fn render_parse_error(sql: &str, e: &ParserError) -> String {
+    let message = e.to_string();
+
+    // `sqlparser` only embeds the location in the message, like `... at Line: 1, Column 5`.
+    let location = message.rsplit_once(" at Line: ").and_then(|(_, location)| {
+        let (line, column) = location.split_once(", Column ")?;
+        Some((line.parse::<usize>().ok()?, column.parse::<usize>().ok()?))
+    });
+    let Some((line, column)) = location else {
+        return message;
+    };
+    let Some(text) = sql.lines().nth(line.saturating_sub(1)) else {
+        return message;
+    };
+
+    let gutter = line.to_string().len();
+    format!(
+        "{message}\n{line} | {text}\n{:gutter$} | {:>column$}",
+        "", "^"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+
+    use super::*;
+    use crate::catalog::Catalog;
+    use crate::{metastore, parser};
+
+    #[test]
+    fn test_is_complete() {
+        assert!(is_complete("SHOW TABLES;"));
+        assert!(is_complete("CREATE TABLE t (\n  a INT\n); -- done"));
+        assert!(!is_complete("CREATE TABLE t (\n  a INT"));
+        assert!(!is_complete("SELECT 'a;"));
+        assert!(!is_complete("SELECT 1; /* comment;"));
+    }
+
+    #[test]
+    fn test_render_parse_error() {
+        let sql = "CREATE TABLE t (\n  a INT,,\n  b INT\n);";
+        let e = parser::parse(sql).unwrap_err();
+
+        expect![[r#"
+            sql parser error: Expected column name or constraint definition, found: , at Line: 2, Column 9
+            2 |   a INT,,
+              |         ^"#]].assert_eq(&render_parse_error(sql, &e));
+    }
+
+    #[tokio::test]
+    async fn test_meta_commands() {
+        let catalog = Catalog::new(metastore::connect(metastore::MEMORY_URL).await.unwrap());
+        let mut session = catalog.session("test");
+        let mut settings = Settings::default();
+        session
+            .execute(
+                "CREATE DATABASE other; CREATE DATABASE shop; USE shop;
+                 CREATE TABLE users (id INT PRIMARY KEY)",
+            )
+            .await
+            .unwrap();
+
+        for line in ["\\x", "\\timing on", "\\d users", "\\c other"] {
+            run_meta_command(&mut session, &mut settings, line)
+                .await
+                .unwrap();
+        }
+        assert!(session.expanded);
+        assert!(settings.timing);
+        assert_eq!(session.current_db_name().unwrap(), "other");
+
+        // `users` is in `shop`.
+        let e = run_meta_command(&mut session, &mut settings, "\\d users")
+            .await
+            .unwrap_err();
+        expect!["object `users` not found"].assert_eq(&e.to_string());
+        let e = run_meta_command(&mut session, &mut settings, "\\timing maybe")
+            .await
+            .unwrap_err();
+        expect!["unrecognized value `maybe`, expected `on` or `off`"].assert_eq(&e.to_string());
+
+        for command in ["dm", "ds", "dT"] {
+            let line = format!("\\{command} foo");
+            let e = run_meta_command(&mut session, &mut settings, &line)
+                .await
+                .unwrap_err();
+            assert_eq!(e.to_string(), format!("\\{command}: extra argument `foo`"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_include() {
+        let catalog = Catalog::new(metastore::connect(metastore::MEMORY_URL).await.unwrap());
+        let mut session = catalog.session("test");
+        let mut settings = Settings::default();
+
+        let dir = std::env::temp_dir().join(format!("include-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let tables = dir.join("tables.sql");
+        let setup = dir.join("setup.sql");
+        let looping = dir.join("loop.sql");
+        std::fs::write(
+            &tables,
+            "CREATE TABLE users (\n  id INT PRIMARY KEY\n);\n\\dt\n",
+        )
+        .unwrap();
+        std::fs::write(
+            &setup,
+            format!(
+                "CREATE DATABASE shop;\nUSE shop;\n\\i {}\n",
+                tables.display()
+            ),
+        )
+        .unwrap();
+        std::fs::write(&looping, format!("\\i {}\n", looping.display())).unwrap();
+
+        let line = format!("\\i {}", setup.display());
+        run_meta_command(&mut session, &mut settings, &line)
+            .await
+            .unwrap();
+        session.describe("users").await.unwrap();
+
+        // Including itself fails instead of overflowing the stack.
+        let line = format!("\\i {}", looping.display());
+        let e = run_meta_command(&mut session, &mut settings, &line)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            e.root_cause().to_string(),
+            format!(
+                "\\i: scripts nested more than 8 deep, at `{}`",
+                looping.display()
+            )
+        );
+        assert_eq!(settings.include_depth, 0);
+
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 }
@@ -0,0 +1,340 @@
+//! Tab completion and syntax highlighting for the REPL.
+
+use std::borrow::Cow;
+
+use rustyline::completion::{Completer, Pair};
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Helper};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+    RelationTrait,
+};
+use sqlparser::keywords::ALL_KEYWORDS;
+
+use crate::entities::prelude::*;
+use crate::entities::*;
+
+/// Words of our own statements that are not keywords of `sqlparser`.
+const EXTRA_KEYWORDS: &[&str] = &["DIFF", "ENTITY", "IMPORT", "SESSIONS"];
+
+/// What kind of name is expected at the cursor.
+#[derive(Debug, PartialEq)]
+enum Expected {
+    Nothing,
+    Keyword,
+    Database,
+    /// Objects of the given types in the current database.
+    Object(&'static [&'static str]),
+    /// Columns of the given table in the current database.
+    Column(String),
+}
+
+pub struct CatalogHelper {
+    meta: DatabaseConnection,
+    /// Database to look up objects in. Updated before each prompt.
+    pub current_db_id: Option<i32>,
+    /// Previous lines of a multi-line statement. Updated before each prompt.
+    pub pending: String,
+}
+
+impl CatalogHelper {
+    pub fn new(meta: DatabaseConnection) -> Self {
+        Self {
+            meta,
+            current_db_id: None,
+            pending: String::new(),
+        }
+    }
+
+    /// Look up candidates in the catalog. Errors are ignored, as there's nothing to complete then.
+    fn names(&self, expected: &Expected) -> Vec<String> {
+        let db_id = self.current_db_id;
+        let meta = &self.meta;
+
+        let query = async move {
+            let names = match (expected, db_id) {
+                (Expected::Database, _) => MyDatabase::find()
+                    .order_by_asc(my_database::Column::Name)
+                    .all(meta)
+                    .await?
+                    .into_iter()
+                    .map(|db| db.name)
+                    .collect(),
+                (Expected::Object(types), Some(db_id)) => MyObject::find()
+                    .filter(
+                        (my_object::Column::DatabaseId.eq(db_id))
+                            .and(my_object::Column::Type.is_in(types.iter().copied())),
+                    )
+                    .order_by_asc(my_object::Column::Name)
+                    .all(meta)
+                    .await?
+                    .into_iter()
+                    .map(|o| o.name)
+                    .collect(),
+                (Expected::Column(table_name), Some(db_id)) => MyColumn::find()
+                    .inner_join(MyTable)
+                    .join(
+                        sea_orm::JoinType::InnerJoin,
+                        my_table::Relation::MyObject.def(),
+                    )
+                    .filter(
+                        (my_object::Column::DatabaseId.eq(db_id))
+                            .and(my_object::Column::Type.eq("table"))
+                            .and(my_object::Column::Name.eq(table_name)),
+                    )
+                    .order_by_asc(my_column::Column::Id)
+                    .all(meta)
+                    .await?
+                    .into_iter()
+                    .map(|c| c.name)
+                    .collect(),
+                _ => Vec::new(),
+            };
+            Ok::<_, sea_orm::DbErr>(names)
+        };
+
+        // The editor is synchronous, while the REPL runs on the multi-threaded runtime.
+        tokio::task::block_in_place(|| tokio::runtime::Handle::current().block_on(query))
+            .unwrap_or_default()
+    }
+}
+
+/// Decide what to complete from the words before the one under the cursor.
+fn expected(words: &[&str]) -> Expected {
+    let upper = words
+        .iter()
+        .map(|w| w.to_ascii_uppercase())
+        .collect::<Vec<_>>();
+    let upper = upper.iter().map(|w| w.as_str()).collect::<Vec<_>>();
+
+    match upper.as_slice() {
+        [.., "ALTER", "TABLE", _, "DROP", "COLUMN"]
+        | [.., "ALTER", "TABLE", _, "ALTER", "COLUMN"] => {
+            Expected::Column(words[words.len() - 3].to_owned())
+        }
+        [.., "USE"] | [.., "DIFF", "DATABASE"] | [.., "DIFF", "DATABASE", _, "WITH"] => {
+            Expected::Database
+        }
+        [.., "DROP", "TABLE"] | [.., "ALTER", "TABLE"] | [.., "EXPLAIN"] | [.., "ENTITY"] => {
+            Expected::Object(&["table"])
+        }
+        [.., "DROP", "VIEW"] => Expected::Object(&["view"]),
+        [.., "FROM"] | [.., "JOIN"] => Expected::Object(&["table", "view"]),
+        _ => Expected::Keyword,
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+impl Completer for CatalogHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let start = line[..pos]
+            .char_indices()
+            .rev()
+            .find(|(_, c)| !is_word_char(*c))
+            .map_or(0, |(i, c)| i + c.len_utf8());
+        let prefix = &line[start..pos];
+
+        let before = format!("{}\n{}", self.pending, &line[..start]);
+        let words = before
+            .split(|c: char| !is_word_char(c))
+            .filter(|w| !w.is_empty())
+            .collect::<Vec<_>>();
+
+        // Arguments of meta-commands, e.g., `\\d users`.
+        let expected = match line.trim_start().strip_prefix('\\') {
+            Some(meta) if self.pending.is_empty() => match meta.split_whitespace().next() {
+                Some("c") if start > 2 => Expected::Database,
+                Some("d") if start > 2 => Expected::Object(&["table", "view"]),
+                _ => Expected::Nothing,
+            },
+            _ => expected(&words),
+        };
+
+        let candidates = match expected {
+            Expected::Keyword => {
+                // Follow the case of what's typed so far.
+                let lowercase = prefix.chars().any(|c| c.is_ascii_lowercase());
+                let upper_prefix = prefix.to_ascii_uppercase();
+                ALL_KEYWORDS
+                    .iter()
+                    .chain(EXTRA_KEYWORDS)
+                    .filter(|k| k.starts_with(&upper_prefix))
+                    .map(|k| {
+                        if lowercase {
+                            k.to_ascii_lowercase()
+                        } else {
+                            k.to_string()
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            }
+            expected => self
+                .names(&expected)
+                .into_iter()
+                .filter(|n| n.starts_with(prefix))
+                .collect(),
+        };
+
+        let pairs = candidates
+            .into_iter()
+            .map(|c| Pair {
+                display: c.clone(),
+                replacement: c,
+            })
+            .collect();
+
+        Ok((start, pairs))
+    }
+}
+
+impl Highlighter for CatalogHelper {
+    /// Keywords in bold blue, string literals in green.
+    fn highlight<'l>(&self, line: &'l str, _pos: usize) -> Cow<'l, str> {
+        const KEYWORD: &str = "\x1b[1;34m";
+        const STRING: &str = "\x1b[32m";
+        const RESET: &str = "\x1b[0m";
+
+        let mut out = String::with_capacity(line.len());
+        let mut chars = line.char_indices().peekable();
+
+        while let Some((i, c)) = chars.next() {
+            if c == '\'' {
+                // Until the closing quote, or the end of line if it's not closed yet.
+                let mut end = line.len();
+                while let Some((j, c)) = chars.next() {
+                    if c == '\'' {
+                        if chars.peek().is_some_and(|(_, c)| *c == '\'') {
+                            chars.next(); // escaped quote
+                        } else {
+                            end = j + 1;
+                            break;
+                        }
+                    }
+                }
+                out.push_str(STRING);
+                out.push_str(&line[i..end]);
+                out.push_str(RESET);
+            } else if is_word_char(c) {
+                let mut end = line.len();
+                while let Some(&(j, c)) = chars.peek() {
+                    if !is_word_char(c) {
+                        end = j;
+                        break;
+                    }
+                    chars.next();
+                }
+                let word = &line[i..end];
+                let upper = word.to_ascii_uppercase();
+                if ALL_KEYWORDS.binary_search(&upper.as_str()).is_ok()
+                    || EXTRA_KEYWORDS.contains(&upper.as_str())
+                {
+                    out.push_str(KEYWORD);
+                    out.push_str(word);
+                    out.push_str(RESET);
+                } else {
+                    out.push_str(word);
+                }
+            } else {
+                out.push(c);
+            }
+        }
+
+        Cow::Owned(out)
+    }
+
+    fn highlight_char(&self, _line: &str, _pos: usize) -> bool {
+        true
+    }
+}
+
+impl Hinter for CatalogHelper {
+    type Hint = String;
+}
+
+impl Validator for CatalogHelper {}
+
+impl Helper for CatalogHelper {}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+    use rustyline::history::DefaultHistory;
+
+    use super::*;
+    use crate::catalog::Catalog;
+    use crate::metastore;
+
+    #[test]
+    fn test_expected() {
+        let expected = |sql: &str| {
+            let words = sql.split_whitespace().collect::<Vec<_>>();
+            super::expected(&words)
+        };
+
+        assert_eq!(expected("use"), Expected::Database);
+        assert_eq!(
+            expected("SELECT * FROM"),
+            Expected::Object(&["table", "view"])
+        );
+        assert_eq!(expected("DROP TABLE"), Expected::Object(&["table"]));
+        assert_eq!(expected("drop view"), Expected::Object(&["view"]));
+        assert_eq!(expected("EXPLAIN"), Expected::Object(&["table"]));
+        assert_eq!(
+            expected("ALTER TABLE Foo DROP COLUMN"),
+            Expected::Column("Foo".to_owned())
+        );
+        assert_eq!(expected("CREATE"), Expected::Keyword);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_complete() {
+        let meta = metastore::connect(metastore::MEMORY_URL).await.unwrap();
+        let mut session = Catalog::new(meta.clone()).session("test");
+        session
+            .execute(
+                "CREATE DATABASE shop; USE shop;
+                 CREATE TABLE users (id INT PRIMARY KEY, name TEXT);
+                 CREATE TABLE orders (id INT PRIMARY KEY, user_id INT);
+                 CREATE VIEW user_orders AS SELECT * FROM users JOIN orders ON users.id = orders.user_id;",
+            )
+            .await
+            .unwrap();
+
+        let mut helper = CatalogHelper::new(meta);
+        helper.current_db_id = session.current_db_id().ok();
+
+        let history = DefaultHistory::new();
+        let mut complete = |pending: &str, line: &str| {
+            helper.pending = pending.to_owned();
+            let (start, pairs) = helper
+                .complete(line, line.len(), &Context::new(&history))
+                .unwrap();
+            let candidates = pairs.into_iter().map(|p| p.replacement).collect::<Vec<_>>();
+            format!("{start} {candidates:?}")
+        };
+
+        expect![[r#"4 ["shop"]"#]].assert_eq(&complete("", "use "));
+        expect![[r#"14 ["orders", "user_orders", "users"]"#]]
+            .assert_eq(&complete("", "SELECT * FROM "));
+        expect![[r#"5 ["users"]"#]].assert_eq(&complete("SELECT *", "FROM users"));
+        expect![[r#"11 ["orders", "users"]"#]].assert_eq(&complete("", "DROP TABLE "));
+        expect![[r#"30 ["id", "name"]"#]]
+            .assert_eq(&complete("", "ALTER TABLE users DROP COLUMN "));
+        expect![[r#"0 ["create", "createdb", "createrole"]"#]].assert_eq(&complete("", "creat"));
+        expect![[r#"3 ["user_orders", "users"]"#]].assert_eq(&complete("", "\\d us"));
+        // After a multibyte separator.
+        expect![[r#"20 ["users"]"#]].assert_eq(&complete("", "SELECT '→' FROM\u{3000}users"));
+    }
+}
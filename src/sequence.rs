@@ -0,0 +1,349 @@
+//! Sequences: counters handing out the values of identity columns, or used on their own. The
+//! catalog hands out no values, but records where each sequence is at, as set by `START` and
+//! `RESTART`.
+//!
+//! An identity column, declared with `GENERATED ... AS IDENTITY`, `AUTO_INCREMENT` or a `SERIAL`
+//! type, owns a sequence created with it, and its default is `nextval` of that sequence. The
+//! sequence is dropped with the column, or its table.
+
+use anyhow::{bail, Context, Result};
+use sea_orm::ActiveValue::*;
+use sea_orm::{ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter};
+use sqlparser::ast::{self, MinMaxValue, SequenceOptions};
+use sqlparser::tokenizer::Token;
+
+use crate::entities::prelude::*;
+use crate::entities::*;
+use crate::error::already_exists;
+
+/// Types a sequence may have, with their range.
+const TYPES: [(&str, i64, i64); 3] = [
+    ("SMALLINT", i16::MIN as i64, i16::MAX as i64),
+    ("INT", i32::MIN as i64, i32::MAX as i64),
+    ("BIGINT", i64::MIN, i64::MAX),
+];
+
+/// The type of sequences created without `AS`.
+pub const DEFAULT_TYPE: &str = "BIGINT";
+
+/// The name of the type in [`TYPES`], if it's one of them.
+pub fn type_name(data_type: &ast::DataType) -> Option<&'static str> {
+    match data_type {
+        ast::DataType::SmallInt(None) => Some("SMALLINT"),
+        ast::DataType::Int(None) | ast::DataType::Integer(None) => Some("INT"),
+        ast::DataType::BigInt(None) => Some("BIGINT"),
+        _ => None,
+    }
+}
+
+/// The integer type behind a `SERIAL` type, like in Postgres.
+pub fn serial_type(data_type: &ast::DataType) -> Option<ast::DataType> {
+    let ast::DataType::Custom(name, modifiers) = data_type else {
+        return None;
+    };
+    match &name.0[..] {
+        [name] if modifiers.is_empty() => match name.value.to_uppercase().as_str() {
+            "SMALLSERIAL" | "SERIAL2" => Some(ast::DataType::SmallInt(None)),
+            "SERIAL" | "SERIAL4" => Some(ast::DataType::Int(None)),
+            "BIGSERIAL" | "SERIAL8" => Some(ast::DataType::BigInt(None)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The options of the sequence of an identity column, or `None` if it's not one.
+pub fn identity(column: &ast::ColumnDef) -> Option<Vec<SequenceOptions>> {
+    if serial_type(&column.data_type).is_some() {
+        return Some(Vec::new());
+    }
+    column
+        .options
+        .iter()
+        .find_map(|option| match &option.option {
+            ast::ColumnOption::Generated {
+                generation_expr: None,
+                sequence_options,
+                ..
+            } => Some(sequence_options.clone().unwrap_or_default()),
+            // `AUTO_INCREMENT` of MySQL, `AUTOINCREMENT` of SQLite.
+            ast::ColumnOption::DialectSpecific(tokens) => match &tokens[..] {
+                [Token::Word(w)] if w.value.eq_ignore_ascii_case("AUTO_INCREMENT") => {
+                    Some(Vec::new())
+                }
+                [Token::Word(w)] if w.value.eq_ignore_ascii_case("AUTOINCREMENT") => {
+                    Some(Vec::new())
+                }
+                _ => None,
+            },
+            _ => None,
+        })
+}
+
+/// Create a sequence of type `data_type`, one of [`TYPES`].
+pub async fn create(
+    conn: &impl ConnectionTrait,
+    schema: &my_schema::Model,
+    name: &str,
+    data_type: &str,
+    options: &[SequenceOptions],
+    owned_by_column_id: Option<i32>,
+) -> Result<my_object::Model> {
+    let mut sequence = my_sequence::Model {
+        object_id: 0,
+        data_type: data_type.to_owned(),
+        start_value: 0,
+        increment: 1,
+        min_value: 0,
+        max_value: 0,
+        cycle: false,
+        current_value: 0,
+        owned_by_column_id,
+    };
+    configure(&mut sequence, options, None, true)?;
+
+    let object = my_object::ActiveModel {
+        id: NotSet,
+        name: Set(name.to_owned()),
+        r#type: Set("sequence".to_owned()),
+        database_id: Set(schema.database_id),
+        version: NotSet,
+        schema_id: Set(schema.id),
+    }
+    .insert(conn)
+    .await
+    .map_err(already_exists("sequence", name))?;
+
+    sequence.object_id = object.id;
+    my_sequence::ActiveModel::from(sequence)
+        .reset_all()
+        .insert(conn)
+        .await?;
+
+    Ok(object)
+}
+
+/// Create the sequence of an identity column, in the schema of its table. It's named like in
+/// Postgres, i.e., `<table>_<column>_seq`, with a number appended if that's taken.
+pub async fn create_owned(
+    conn: &impl ConnectionTrait,
+    schema: &my_schema::Model,
+    table: &str,
+    column: &my_column::Model,
+    options: &[SequenceOptions],
+) -> Result<my_object::Model> {
+    let data_type = column.ast_data_type()?;
+    let data_type = type_name(&data_type).with_context(|| {
+        format!(
+            "identity column `{}` must be SMALLINT, INT or BIGINT, not {data_type}",
+            column.name
+        )
+    })?;
+
+    let base = format!("{table}_{}_seq", column.name);
+    let mut name = base.clone();
+    for n in 1.. {
+        let taken = MyObject::find()
+            .filter(
+                (my_object::Column::SchemaId.eq(schema.id))
+                    .and(my_object::Column::Type.eq("sequence"))
+                    .and(my_object::Column::Name.eq(&name)),
+            )
+            .one(conn)
+            .await?
+            .is_some();
+        if !taken {
+            break;
+        }
+        name = format!("{base}{n}");
+    }
+
+    create(conn, schema, &name, data_type, options, Some(column.id)).await
+}
+
+/// Change the options of the sequence, and restart it at `restart`, or its start value if that's
+/// `Some(None)`.
+pub async fn alter(
+    conn: &impl ConnectionTrait,
+    object: &my_object::Model,
+    options: &[SequenceOptions],
+    restart: Option<Option<&ast::Expr>>,
+) -> Result<()> {
+    let mut sequence = MySequence::find_by_id(object.id)
+        .one(conn)
+        .await?
+        .context("sequence settings not found")?;
+    configure(&mut sequence, options, restart, false)?;
+
+    let mut sequence = my_sequence::ActiveModel::from(sequence).reset_all();
+    sequence.object_id = Unchanged(object.id);
+    sequence.update(conn).await?;
+    Ok(())
+}
+
+/// The column owning the sequence, as `<table>.<column>`, if any.
+pub async fn owner(conn: &impl ConnectionTrait, object_id: i32) -> Result<Option<String>> {
+    let Some(column_id) = MySequence::find_by_id(object_id)
+        .one(conn)
+        .await?
+        .and_then(|s| s.owned_by_column_id)
+    else {
+        return Ok(None);
+    };
+    let Some(column) = MyColumn::find_by_id(column_id).one(conn).await? else {
+        return Ok(None);
+    };
+    let table = MyObject::find_by_id(column.table_id)
+        .one(conn)
+        .await?
+        .context("table not found")?;
+    Ok(Some(format!("{}.{}", table.name, column.name)))
+}
+
+/// Drop the sequences owned by the columns, which must be done before deleting the columns as it
+/// would only detach the sequences. Returns the dropped sequences.
+pub async fn drop_owned(
+    conn: &impl ConnectionTrait,
+    column_ids: impl IntoIterator<Item = i32>,
+) -> Result<Vec<my_object::Model>> {
+    let objects = MySequence::find()
+        .filter(my_sequence::Column::OwnedByColumnId.is_in(column_ids))
+        .find_also_related(MyObject)
+        .all(conn)
+        .await?
+        .into_iter()
+        .filter_map(|(_, object)| object)
+        .collect::<Vec<_>>();
+    MyObject::delete_many()
+        .filter(my_object::Column::Id.is_in(objects.iter().map(|o| o.id)))
+        .exec(conn)
+        .await?;
+    Ok(objects)
+}
+
+/// Apply the options to the sequence, with the defaults of Postgres for a `new` one, and check
+/// that its values are within its bounds.
+fn configure(
+    sequence: &mut my_sequence::Model,
+    options: &[SequenceOptions],
+    restart: Option<Option<&ast::Expr>>,
+    new: bool,
+) -> Result<()> {
+    let data_type = sequence.data_type.clone();
+    let (type_min, type_max) = TYPES
+        .iter()
+        .find(|(name, ..)| *name == data_type)
+        .map(|&(_, min, max)| (min, max))
+        .with_context(|| format!("unknown sequence type {data_type}"))?;
+
+    // `Some(None)` for `NO MINVALUE`, etc., which means the default.
+    let (mut min, mut max, mut start) = (None, None, None);
+    for option in options {
+        match option {
+            SequenceOptions::IncrementBy(value, _) => sequence.increment = integer(value)?,
+            SequenceOptions::MinValue(MinMaxValue::Some(value)) => {
+                min = Some(Some(integer(value)?))
+            }
+            SequenceOptions::MinValue(MinMaxValue::None) => min = Some(None),
+            SequenceOptions::MaxValue(MinMaxValue::Some(value)) => {
+                max = Some(Some(integer(value)?))
+            }
+            SequenceOptions::MaxValue(MinMaxValue::None) => max = Some(None),
+            SequenceOptions::MinValue(MinMaxValue::Empty)
+            | SequenceOptions::MaxValue(MinMaxValue::Empty) => {}
+            SequenceOptions::StartWith(value, _) => start = Some(integer(value)?),
+            SequenceOptions::Cache(_) => bail!("CACHE is not supported"),
+            SequenceOptions::Cycle(no) => sequence.cycle = !no,
+        }
+    }
+
+    if sequence.increment == 0 {
+        bail!("INCREMENT must not be zero");
+    }
+    let ascending = sequence.increment > 0;
+    match min {
+        Some(Some(value)) => sequence.min_value = value,
+        Some(None) => sequence.min_value = if ascending { 1 } else { type_min },
+        None if new => sequence.min_value = if ascending { 1 } else { type_min },
+        None => {}
+    }
+    match max {
+        Some(Some(value)) => sequence.max_value = value,
+        Some(None) => sequence.max_value = if ascending { type_max } else { -1 },
+        None if new => sequence.max_value = if ascending { type_max } else { -1 },
+        None => {}
+    }
+    for (option, value) in [
+        ("MINVALUE", sequence.min_value),
+        ("MAXVALUE", sequence.max_value),
+    ] {
+        if !(type_min..=type_max).contains(&value) {
+            bail!("{option} ({value}) is out of range for sequence data type {data_type}");
+        }
+    }
+    if sequence.min_value >= sequence.max_value {
+        bail!(
+            "MINVALUE ({}) must be less than MAXVALUE ({})",
+            sequence.min_value,
+            sequence.max_value
+        );
+    }
+
+    match start {
+        Some(value) => sequence.start_value = value,
+        None if new => {
+            sequence.start_value = if ascending {
+                sequence.min_value
+            } else {
+                sequence.max_value
+            }
+        }
+        None => {}
+    }
+    check_bounds(sequence, "START", sequence.start_value)?;
+
+    if new {
+        sequence.current_value = sequence.start_value;
+    }
+    match restart {
+        Some(Some(value)) => sequence.current_value = integer(value)?,
+        Some(None) => sequence.current_value = sequence.start_value,
+        None => {}
+    }
+    // Also checked without `RESTART`, as the bounds may have changed.
+    check_bounds(sequence, "RESTART", sequence.current_value)
+}
+
+fn check_bounds(sequence: &my_sequence::Model, option: &str, value: i64) -> Result<()> {
+    if value < sequence.min_value {
+        bail!(
+            "{option} value ({value}) cannot be less than MINVALUE ({})",
+            sequence.min_value
+        );
+    }
+    if value > sequence.max_value {
+        bail!(
+            "{option} value ({value}) cannot be greater than MAXVALUE ({})",
+            sequence.max_value
+        );
+    }
+    Ok(())
+}
+
+/// The value of an integer literal, possibly negative.
+fn integer(expr: &ast::Expr) -> Result<i64> {
+    let literal = match expr {
+        ast::Expr::Value(ast::Value::Number(n, _)) => n.clone(),
+        ast::Expr::UnaryOp {
+            op: ast::UnaryOperator::Minus,
+            expr: operand,
+        } => match &**operand {
+            ast::Expr::Value(ast::Value::Number(n, _)) => format!("-{n}"),
+            _ => bail!("expected an integer, not `{expr}`"),
+        },
+        _ => bail!("expected an integer, not `{expr}`"),
+    };
+    literal
+        .parse()
+        .with_context(|| format!("value {literal} is out of range for type BIGINT"))
+}
@@ -1,36 +1,202 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::iter;
 use std::ops::ControlFlow;
+use std::time::Duration;
 
 use anyhow::{bail, Context, Result};
+use sea_orm::sea_query::Expr;
 use sea_orm::ActiveValue::*;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, ModelTrait,
-    QueryFilter, TransactionTrait,
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DatabaseTransaction,
+    EntityTrait, IntoActiveModel, QueryFilter, QueryOrder, TransactionTrait,
 };
-use sqlparser::ast::{self, visit_relations};
+use sqlparser::ast::{self, visit_relations, visit_relations_mut};
 
+use crate::cache::catalog_version;
+use crate::catalog::{
+    display_name, is_busy, object_kind, Catalog, QualifiedName, Scope, DEFAULT_SCHEMA,
+};
+use crate::diff::Schema;
 use crate::entities::prelude::*;
 use crate::entities::*;
+use crate::error::{already_exists, ConcurrentModification, InUse, NotFound};
+use crate::event::CatalogEvent;
+use crate::import::{self, SchemaObject};
+use crate::output::{Format, ResultSet};
+use crate::parser::{self, Statement, TypeRepresentation};
+use crate::{check, codegen, information_schema, materialized_view, sequence, user_type};
 
 pub struct Session {
+    catalog: Catalog,
+    /// Id in the registry of `catalog`.
+    id: u32,
+    /// Same as `catalog.meta()`, for brevity.
     meta: DatabaseConnection,
     current_db: Option<my_database::Model>,
+    /// Schemas where names without one are looked up, in order. Objects are created in the first
+    /// one that exists.
+    search_path: Vec<String>,
+    /// How [`Self::execute`] prints result sets.
+    pub format: Format,
+    /// Print each row as a record of `header | value` lines, like `\x` in psql.
+    pub expanded: bool,
 }
 
+/// Types of the objects queries can read.
+const RELATION_TYPES: &[&str] = &["table", "view", "materialized_view"];
+
+/// How times are shown, like `timestamptz` in psql.
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S%:z";
+
+/// Times a statement failing with `SQLITE_BUSY` is retried.
+const MAX_BUSY_RETRIES: u32 = 5;
+
 impl Session {
-    pub fn new(meta: DatabaseConnection) -> Self {
+    /// Use [`Catalog::session`] instead, so that the session is registered.
+    pub(crate) fn new(catalog: Catalog, id: u32) -> Self {
         Self {
-            meta,
+            meta: catalog.meta().clone(),
+            catalog,
+            id,
             current_db: None,
+            search_path: vec![DEFAULT_SCHEMA.to_owned()],
+            format: Format::default(),
+            expanded: false,
+        }
+    }
+
+    /// Parse and handle all statements in `sql`, stopping at the first error. Result sets are
+    /// printed as they come.
+    pub async fn execute(&mut self, sql: &str) -> Result<()> {
+        for stmt in parser::parse(sql)? {
+            let display = stmt.to_string();
+            let result = self
+                .handle(stmt)
+                .await
+                .with_context(|| format!("failed to execute `{display}`"))?;
+
+            if let Some(result) = result {
+                self.print(&result);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Print a result set in the format of this session.
+    pub fn print(&self, result: &ResultSet) {
+        print!("{}", result.render(self.format, self.expanded));
+    }
+
+    /// Handle a statement, returning the rows if it's a query.
+    ///
+    /// Statements modifying the catalog hold the write lock of the catalog, and are retried if
+    /// the metastore is busy. See [`crate::catalog`].
+    pub async fn handle(&mut self, stmt: Statement) -> Result<Option<ResultSet>> {
+        let catalog = self.catalog.clone();
+        let _guard = match is_read_only(&stmt) {
+            true => None,
+            false => Some(catalog.write_lock().await),
+        };
+        // Imports are not atomic, so a retry could fail on the objects already imported.
+        let retries = match stmt {
+            Statement::ImportSchema { .. } => 0,
+            _ => MAX_BUSY_RETRIES,
+        };
+
+        // Entries may have been changed by other processes since the last statement.
+        if let Some(cache) = catalog.cache() {
+            cache.check_version(&self.meta).await?;
+        }
+
+        let mut attempt = 0;
+        loop {
+            match self.handle_once(stmt.clone()).await {
+                Err(e) if attempt < retries && is_busy(&e) => {
+                    attempt += 1;
+                    tracing::warn!("metastore is busy, retrying ({attempt}/{retries})");
+                    tokio::time::sleep(Duration::from_millis(50 * 2u64.pow(attempt))).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn handle_once(&mut self, stmt: Statement) -> Result<Option<ResultSet>> {
+        match stmt {
+            Statement::Sql(stmt) => return self.handle_sql(stmt).await,
+            Statement::DiffDatabase { from, to } => {
+                return self.diff_database(from, to).await.map(Some)
+            }
+            Statement::GenerateEntity { table_name, path } => {
+                return self.generate_entity(table_name, path).await
+            }
+            Statement::ImportSchema { path, db_name } => {
+                return self.import_schema(path, db_name).await.map(Some)
+            }
+            Statement::ShowSessions => return Ok(Some(self.show_sessions())),
+            Statement::ShowCache => return self.show_cache().map(Some),
+            Statement::CheckCatalog { repair } => {
+                return self.check_catalog(repair).await.map(Some)
+            }
+            Statement::RefreshMaterializedView { name } => {
+                self.refresh_materialized_view(name).await?
+            }
+            Statement::DropMaterializedView { names } => {
+                self.drop_object("materialized_view", names).await?
+            }
+            Statement::ShowMaterializedViews => {
+                return self.show_materialized_views().await.map(Some)
+            }
+            Statement::AlterSequence {
+                name,
+                options,
+                restart,
+            } => self.alter_sequence(name, options, restart).await?,
+            Statement::CreateType {
+                name,
+                representation,
+            } => self.create_type(name, representation).await?,
+            Statement::CreateDomain {
+                name,
+                data_type,
+                default,
+                not_null,
+                checks,
+            } => {
+                self.create_domain(name, data_type, default, not_null, checks)
+                    .await?
+            }
+            Statement::DropType { names, domain } => self.drop_type(names, domain).await?,
         }
+
+        Ok(None)
     }
 
-    pub async fn handle(&mut self, stmt: ast::Statement) -> Result<()> {
+    async fn handle_sql(&mut self, stmt: ast::Statement) -> Result<Option<ResultSet>> {
         use ast::Statement::*;
 
         match stmt {
             CreateDatabase { db_name, .. } => self.create_database(db_name).await?,
             Use { db_name } => self.use_database(db_name).await?,
 
+            CreateSchema {
+                schema_name: ast::SchemaName::Simple(name),
+                ..
+            } => self.create_schema(name).await?,
+            Drop {
+                object_type: ast::ObjectType::Schema,
+                names,
+                cascade,
+                ..
+            } => self.drop_schema(names, cascade).await?,
+            SetVariable {
+                variable, value, ..
+            } if is_search_path(&variable.0) => self.set_search_path(value)?,
+            ShowVariable { variable } if is_search_path(&variable) => {
+                return Ok(Some(self.show_search_path()))
+            }
+
             CreateTable { name, columns, .. } => self.create_table(name, columns).await?,
             Drop {
                 object_type: ast::ObjectType::Table,
@@ -42,65 +208,217 @@ impl Session {
                 name, operations, ..
             } => self.alter_table(name, operations).await?,
 
-            ShowTables { .. } => self.show_tables().await?,
-            ExplainTable { table_name, .. } => self.explain_table(table_name).await?,
+            CreateSequence {
+                name,
+                data_type,
+                sequence_options,
+                owned_by,
+                ..
+            } => {
+                self.create_sequence(name, data_type, sequence_options, owned_by)
+                    .await?
+            }
+            Drop {
+                object_type: ast::ObjectType::Sequence,
+                names,
+                ..
+            } => self.drop_object("sequence", names).await?,
+
+            ShowTables { db_name, .. } => return self.show_tables(db_name).await.map(Some),
+            ExplainTable { table_name, .. } => {
+                return self.explain_table(table_name).await.map(Some)
+            }
 
-            CreateView { name, query, .. } => self.create_view(name, query).await?,
+            CreateView {
+                name,
+                query,
+                or_replace,
+                materialized,
+                with_options,
+                ..
+            } => {
+                self.create_view(name, query, or_replace, materialized, with_options)
+                    .await?
+            }
             Drop {
                 object_type: ast::ObjectType::View,
                 names,
                 ..
             } => self.drop_object("view", names).await?,
 
+            Query(query) => return self.query(&query).await.map(Some),
+
             _ => bail!("unimplemented statement"),
         }
 
+        Ok(None)
+    }
+
+    /// Connection to the metastore this session works on.
+    pub fn meta(&self) -> &DatabaseConnection {
+        &self.meta
+    }
+
+    pub fn current_db_id(&self) -> Result<i32> {
+        self.current_db().map(|db| db.id)
+    }
+
+    fn current_db(&self) -> Result<&my_database::Model> {
+        self.current_db.as_ref().context("no database selected")
+    }
+
+    /// Where names are resolved.
+    fn scope(&self) -> Scope<'_> {
+        Scope {
+            db: self.current_db.as_ref(),
+            search_path: &self.search_path,
+        }
+    }
+
+    fn set_search_path(&mut self, value: Vec<ast::Expr>) -> Result<()> {
+        let mut search_path = Vec::new();
+        for expr in value {
+            match expr {
+                ast::Expr::Identifier(ident) => search_path.push(ident.value),
+                ast::Expr::Value(ast::Value::SingleQuotedString(s)) => {
+                    search_path.extend(s.split(',').map(|s| s.trim().to_owned()))
+                }
+                _ => bail!("invalid schema name `{expr}`"),
+            }
+        }
+
+        self.search_path = search_path;
         Ok(())
     }
 
-    fn current_db_id(&self) -> Result<i32> {
-        self.current_db
-            .as_ref()
-            .map(|db| db.id)
-            .context("no database selected")
-            .map_err(Into::into)
+    fn show_search_path(&self) -> ResultSet {
+        let row = vec![self.search_path.join(", ")];
+        ResultSet::new(&["search_path"], vec![row])
     }
 
     pub fn current_db_name(&self) -> Option<&str> {
         self.current_db.as_ref().map(|db| db.name.as_str())
     }
 
+    fn set_current_db(&mut self, db: Option<my_database::Model>) {
+        let name = db.as_ref().map(|db| db.name.clone());
+        self.catalog.set_session_database(self.id, name);
+        self.current_db = db;
+    }
+
+    fn show_sessions(&self) -> ResultSet {
+        let rows = self
+            .catalog
+            .sessions()
+            .into_iter()
+            .map(|s| {
+                vec![
+                    s.id.to_string(),
+                    s.client,
+                    s.database.unwrap_or_default(),
+                    if s.id == self.id { "*" } else { "" }.to_owned(),
+                ]
+            })
+            .collect();
+        ResultSet::new(&["Id", "Client", "Database", "Current"], rows)
+    }
+
+    fn show_cache(&self) -> Result<ResultSet> {
+        let stats = self.catalog.cache().context("cache is disabled")?.stats();
+        let row = vec![stats.hits.to_string(), stats.misses.to_string()];
+        Ok(ResultSet::new(&["Hits", "Misses"], vec![row]))
+    }
+
+    /// Report the violations of the invariants of the catalog, repairing the safe ones if asked
+    /// to. Repairs are done in one transaction.
+    async fn check_catalog(&self, repair: bool) -> Result<ResultSet> {
+        let txn = self.meta.begin().await?;
+
+        let mut rows = Vec::new();
+        let mut repaired = false;
+        for violation in check::check_catalog(&txn).await? {
+            let status = match (&violation.repair, repair) {
+                (Some(fix), true) => {
+                    fix.apply(&txn).await?;
+                    repaired = true;
+                    "repaired"
+                }
+                (Some(_), false) => "safe",
+                (None, _) => "manual",
+            };
+            rows.push(vec![violation.object, violation.problem, status.to_owned()]);
+        }
+
+        if repaired {
+            bump_catalog_version(&txn).await?;
+        }
+        txn.commit().await?;
+        // Repairs may span databases, so drop all cached entries.
+        if let (true, Some(cache)) = (repaired, self.catalog.cache()) {
+            cache.check_version(&self.meta).await?;
+        }
+
+        Ok(ResultSet::new(&["Object", "Problem", "Repair"], rows))
+    }
+
+    /// A `SELECT` over the `information_schema` relations, read in one transaction.
+    async fn query(&self, query: &ast::Query) -> Result<ResultSet> {
+        let txn = self.meta.begin().await?;
+        let result = information_schema::query(&txn, query).await?;
+        txn.commit().await?;
+        Ok(result)
+    }
+
     async fn create_database(&mut self, db_name: ast::ObjectName) -> Result<()> {
-        let db_name = &db_name.0[0].value;
+        let db = self.insert_database(&db_name.0[0].value).await?;
+
+        if self.current_db.is_none() {
+            self.set_current_db(Some(db));
+        }
+
+        Ok(())
+    }
+
+    async fn insert_database(&self, db_name: &str) -> Result<my_database::Model> {
+        let txn = self.meta.begin().await?;
+
         let my_db = my_database::ActiveModel {
             id: NotSet,
             name: Set(db_name.to_owned()),
         };
+        let db = my_db
+            .insert(&txn)
+            .await
+            .map_err(already_exists("database", db_name))?;
 
-        let db = my_db.insert(&self.meta).await?;
-
-        if self.current_db.is_none() {
-            self.current_db = Some(db);
-        }
+        let my_schema = my_schema::ActiveModel {
+            id: NotSet,
+            database_id: Set(db.id),
+            name: Set(DEFAULT_SCHEMA.to_owned()),
+        };
+        my_schema.insert(&txn).await?;
 
-        Ok(())
+        let version = bump_catalog_version(&txn).await?;
+        txn.commit().await?;
+        self.catalog.committed(
+            db.id,
+            version,
+            [CatalogEvent::DatabaseCreated {
+                db: db.name.clone(),
+            }],
+        );
+
+        Ok(db)
     }
 
     async fn use_database(&mut self, db_name: ast::Ident) -> Result<()> {
-        let db = MyDatabase::find()
-            // Really confusing here, especially if there's no `my_` prefix.
-            // Given a SQL term, you cannot tell if it's talking about our database or the meta
-            // store.
-            //
-            // `my_database`: entity (table) name
-            // `Column`: generated enum for all columns
-            // `Name`: column named `name`
-            .filter(my_database::Column::Name.eq(&db_name.value))
-            .one(&self.meta)
+        let db = self
+            .catalog
+            .find_database(&self.meta, &db_name.value)
             .await?
-            .context("database not found")?;
+            .with_context(|| NotFound::new("database", &db_name.value))?;
 
-        self.current_db = Some(db);
+        self.set_current_db(Some(db));
 
         Ok(())
     }
@@ -113,102 +431,550 @@ impl Session {
             .await?;
 
         if res.rows_affected == 0 {
-            bail!("database `{}` not found", db_name.value);
+            return Err(NotFound::new("database", &db_name.value).into());
         }
 
         if let Some(current_db) = &self.current_db {
             if current_db.name == db_name.value {
-                self.current_db = None;
+                self.set_current_db(None);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Create a schema in the current database, or in the one it's qualified with.
+    async fn create_schema(&mut self, schema_name: ast::ObjectName) -> Result<()> {
+        let (db, name) = self.resolve_schema_name(&schema_name).await?;
+        if name == information_schema::SCHEMA {
+            bail!("schema name `{name}` is reserved");
+        }
+
+        let txn = self.meta.begin().await?;
+
+        let my_schema = my_schema::ActiveModel {
+            id: NotSet,
+            database_id: Set(db.id),
+            name: Set(name.clone()),
+        };
+        my_schema
+            .insert(&txn)
+            .await
+            .map_err(already_exists("schema", &name))?;
+
+        let version = bump_catalog_version(&txn).await?;
+        txn.commit().await?;
+        self.catalog.committed(
+            db.id,
+            version,
+            [CatalogEvent::SchemaCreated { db: db.name, name }],
+        );
+
+        Ok(())
+    }
+
+    /// Drop schemas, which must be empty unless `cascade` is set. Then the objects in them are
+    /// dropped too, as well as the views depending on those, wherever they are.
+    async fn drop_schema(&mut self, names: Vec<ast::ObjectName>, cascade: bool) -> Result<()> {
+        let txn = self.meta.begin().await?;
+
+        let mut events = BTreeMap::<i32, Vec<_>>::new();
+        for schema_name in names {
+            let (db, name) = self.resolve_schema_name(&schema_name).await?;
+            let schema = self
+                .catalog
+                .find_schema(&txn, db.id, &name)
+                .await?
+                .with_context(|| NotFound::new("schema", schema_name.to_string()))?;
+
+            let mut objects = MyObject::find()
+                .filter(my_object::Column::SchemaId.eq(schema.id))
+                .all(&txn)
+                .await?;
+            if !cascade && !objects.is_empty() {
+                let ids = objects.iter().map(|o| o.id);
+                let names = object_names(&txn, ids).await?;
+                return Err(InUse::new("schema", schema_name.to_string(), names).into());
+            }
+
+            // Views depending on the objects, directly or not.
+            let mut ids = objects.iter().map(|o| o.id).collect::<BTreeSet<_>>();
+            loop {
+                let dependents = MyViewDependency::find()
+                    .filter(
+                        (my_view_dependency::Column::DependentObjectId.is_in(ids.clone()))
+                            .and(my_view_dependency::Column::ViewId.is_not_in(ids.clone())),
+                    )
+                    .all(&txn)
+                    .await?;
+                if dependents.is_empty() {
+                    break;
+                }
+                let dependents = dependents.into_iter().map(|d| d.view_id);
+                let dependents = MyObject::find()
+                    .filter(my_object::Column::Id.is_in(dependents))
+                    .all(&txn)
+                    .await?;
+                ids.extend(dependents.iter().map(|o| o.id));
+                objects.extend(dependents);
+            }
+
+            // Unlike views, columns and types elsewhere using the types are not dropped.
+            let types = objects.iter().filter(|o| o.r#type == "type").map(|o| o.id);
+            let dependents = user_type::dependents(&txn, types)
+                .await?
+                .into_iter()
+                .filter(|(id, _)| !ids.contains(id))
+                .map(|(_, name)| name)
+                .collect::<Vec<_>>();
+            if !dependents.is_empty() {
+                return Err(InUse::new("schema", schema_name.to_string(), dependents).into());
             }
+
+            // Otherwise the dependencies between the objects would restrict their deletion.
+            MyViewDependency::delete_many()
+                .filter(my_view_dependency::Column::ViewId.is_in(ids.clone()))
+                .exec(&txn)
+                .await?;
+            MyObject::delete_many()
+                .filter(my_object::Column::Id.is_in(ids))
+                .exec(&txn)
+                .await?;
+            MySchema::delete_by_id(schema.id).exec(&txn).await?;
+
+            let schemas = MySchema::find()
+                .filter(my_schema::Column::Id.is_in(objects.iter().map(|o| o.schema_id)))
+                .all(&txn)
+                .await?
+                .into_iter()
+                .map(|s| (s.id, s.name))
+                .chain([(schema.id, schema.name.clone())])
+                .collect::<BTreeMap<_, _>>();
+            let events = events.entry(db.id).or_default();
+            for object in objects {
+                let (db, schema) = (db.name.clone(), schemas[&object.schema_id].clone());
+                let name = object.name;
+                events.push(match object.r#type.as_str() {
+                    "view" => CatalogEvent::ViewDropped { db, schema, name },
+                    "materialized_view" => {
+                        CatalogEvent::MaterializedViewDropped { db, schema, name }
+                    }
+                    "sequence" => CatalogEvent::SequenceDropped { db, schema, name },
+                    "type" => CatalogEvent::TypeDropped { db, schema, name },
+                    _ => CatalogEvent::TableDropped { db, schema, name },
+                });
+            }
+            events.push(CatalogEvent::SchemaDropped {
+                db: db.name,
+                name: schema.name,
+            });
+        }
+
+        let version = bump_catalog_version(&txn).await?;
+        txn.commit().await?;
+        for (db_id, events) in events {
+            self.catalog.committed(db_id, version, events);
         }
 
         Ok(())
     }
 
+    /// The database of a schema name, which is the current one unless it's qualified.
+    async fn resolve_schema_name(
+        &self,
+        schema_name: &ast::ObjectName,
+    ) -> Result<(my_database::Model, String)> {
+        match &schema_name.0[..] {
+            [name] => Ok((self.current_db()?.clone(), name.value.clone())),
+            [db_name, name] => {
+                let db = self
+                    .catalog
+                    .find_database(&self.meta, &db_name.value)
+                    .await?
+                    .with_context(|| NotFound::new("database", &db_name.value))?;
+                Ok((db, name.value.clone()))
+            }
+            _ => bail!("invalid schema name `{schema_name}`"),
+        }
+    }
+
     async fn create_table(
         &mut self,
         table_name: ast::ObjectName,
         columns: Vec<ast::ColumnDef>,
     ) -> Result<()> {
-        let db_id = self.current_db_id()?;
+        let target = self
+            .catalog
+            .resolve_new(&self.meta, self.scope(), &table_name)
+            .await?;
+
+        self.create_table_in(&target, columns).await
+    }
 
-        let table_name = &table_name.0[0].value;
+    async fn create_table_in(
+        &self,
+        target: &QualifiedName,
+        columns: Vec<ast::ColumnDef>,
+    ) -> Result<()> {
+        let mut names = BTreeSet::new();
+        if let Some(column) = columns.iter().find(|c| !names.insert(&c.name.value)) {
+            bail!("column `{}` specified more than once", column.name.value);
+        }
 
         let txn = self.meta.begin().await?;
 
+        let table_name = &target.name;
         let my_object = my_object::ActiveModel {
             id: NotSet,
-            name: Set(table_name.to_owned()),
+            name: Set(table_name.clone()),
             r#type: Set("table".to_owned()),
-            database_id: Set(db_id),
+            database_id: Set(target.db.id),
+            version: NotSet,
+            schema_id: Set(target.schema.id),
         };
-        let object_id = my_object.insert(&txn).await?.id;
+        let object_id = my_object
+            .insert(&txn)
+            .await
+            .map_err(already_exists("table", table_name))?
+            .id;
 
         let my_table = my_table::ActiveModel {
             object_id: Set(object_id),
         };
         my_table.insert(&txn).await?;
 
-        let my_columns = columns
-            .into_iter()
-            .map(|col| my_column::ActiveModel::from_ast(col, object_id))
-            .collect::<Vec<_>>();
+        let mut my_columns = Vec::new();
+        for col in &columns {
+            let mut column = my_column::ActiveModel::from_ast(col.clone(), object_id);
+            self.set_user_type(&txn, &target.db, &mut column, &col.data_type)
+                .await?;
+            my_columns.push(column);
+        }
 
         MyColumn::insert_many(my_columns).exec(&txn).await?;
 
+        let (db, schema) = (&target.db.name, &target.schema.name);
+        let mut sequences = Vec::new();
+        for column_def in &columns {
+            let Some(options) = sequence::identity(column_def) else {
+                continue;
+            };
+            let column = MyColumn::find()
+                .filter(
+                    (my_column::Column::TableId.eq(object_id))
+                        .and(my_column::Column::Name.eq(&column_def.name.value)),
+                )
+                .one(&txn)
+                .await?
+                .context("column not found")?;
+            let sequence =
+                sequence::create_owned(&txn, &target.schema, table_name, &column, &options).await?;
+            sequences.push(CatalogEvent::SequenceCreated {
+                db: db.clone(),
+                schema: schema.clone(),
+                name: sequence.name,
+            });
+        }
+
+        let version = bump_catalog_version(&txn).await?;
         txn.commit().await?;
+        let table_created = CatalogEvent::TableCreated {
+            db: db.clone(),
+            schema: schema.clone(),
+            name: table_name.clone(),
+            columns,
+        };
+        self.catalog.committed(
+            target.db.id,
+            version,
+            iter::once(table_created).chain(sequences),
+        );
 
         Ok(())
     }
 
-    async fn show_tables(&mut self) -> Result<()> {
-        let db_id = self.current_db_id()?;
+    /// Tables in the search path, or in the given schema of the current database.
+    async fn show_tables(&mut self, schema_name: Option<ast::Ident>) -> Result<ResultSet> {
+        match schema_name {
+            Some(name) => {
+                let db_id = self.current_db_id()?;
+                let schema = self
+                    .catalog
+                    .find_schema(&self.meta, db_id, &name.value)
+                    .await?
+                    .with_context(|| NotFound::new("schema", &name.value))?;
+                self.list_objects_in(&[schema], &["table"]).await
+            }
+            None => self.list_objects(&["table"]).await,
+        }
+    }
 
-        let tables = MyObject::find()
-            .filter(
-                (my_object::Column::DatabaseId.eq(db_id)).and(my_object::Column::Type.eq("table")),
-            )
+    pub async fn list_databases(&self) -> Result<ResultSet> {
+        let dbs = MyDatabase::find()
+            .order_by_asc(my_database::Column::Name)
             .all(&self.meta)
             .await?;
 
-        for table in tables {
-            println!("{}", table.name);
+        let rows = dbs.into_iter().map(|db| vec![db.name]).collect();
+        Ok(ResultSet::new(&["Name"], rows))
+    }
+
+    /// List objects of the given types in the search path, leaving out those hidden by objects of
+    /// the same type and name in a schema before theirs.
+    pub async fn list_objects(&self, object_types: &[&str]) -> Result<ResultSet> {
+        let schemas = self.search_path_schemas().await?;
+        self.list_objects_in(&schemas, object_types).await
+    }
+
+    /// Schemas of the search path that exist in the current database, in order.
+    async fn search_path_schemas(&self) -> Result<Vec<my_schema::Model>> {
+        let db_id = self.current_db_id()?;
+
+        let mut schemas = Vec::new();
+        for name in &self.search_path {
+            if let Some(schema) = self.catalog.find_schema(&self.meta, db_id, name).await? {
+                schemas.push(schema);
+            }
         }
+        Ok(schemas)
+    }
 
-        Ok(())
+    async fn list_objects_in(
+        &self,
+        schemas: &[my_schema::Model],
+        object_types: &[&str],
+    ) -> Result<ResultSet> {
+        let objects = self.visible_objects(schemas, object_types).await?;
+
+        // The type is only worth a column if there are several.
+        if let [_] = object_types {
+            let rows = objects.into_iter().map(|o| vec![o.name]).collect();
+            Ok(ResultSet::new(&["Name"], rows))
+        } else {
+            let rows = objects
+                .into_iter()
+                .map(|o| vec![o.name, object_kind(&o.r#type).to_owned()])
+                .collect();
+            Ok(ResultSet::new(&["Name", "Type"], rows))
+        }
     }
 
-    async fn explain_table(&mut self, table_name: ast::ObjectName) -> Result<()> {
+    /// Objects of the given types in the schemas, ordered by name.
+    async fn visible_objects(
+        &self,
+        schemas: &[my_schema::Model],
+        object_types: &[&str],
+    ) -> Result<Vec<my_object::Model>> {
         let db_id = self.current_db_id()?;
 
-        let table_name = &table_name.0[0].value;
+        let mut objects = self.catalog.list_objects(&self.meta, db_id).await?;
+        objects.retain(|o| object_types.contains(&o.r#type.as_str()));
+        let position = |o: &my_object::Model| schemas.iter().position(|s| s.id == o.schema_id);
+        objects.retain(|o| position(o).is_some());
+        // Stable, so that objects remain ordered by name.
+        objects.sort_by_key(|o| position(o));
+        let mut visible = BTreeSet::new();
+        objects.retain(|o| visible.insert((o.r#type.clone(), o.name.clone())));
+        objects.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(objects)
+    }
+
+    /// Materialized views in the search path, with how and when they were last refreshed.
+    async fn show_materialized_views(&self) -> Result<ResultSet> {
+        let schemas = self.search_path_schemas().await?;
+        let objects = self
+            .visible_objects(&schemas, &["materialized_view"])
+            .await?;
+
+        let mut views = MyMaterializedView::find()
+            .filter(my_materialized_view::Column::ObjectId.is_in(objects.iter().map(|o| o.id)))
+            .all(&self.meta)
+            .await?
+            .into_iter()
+            .map(|v| (v.object_id, v))
+            .collect::<BTreeMap<_, _>>();
+        let rows = objects
+            .into_iter()
+            .map(|o| match views.remove(&o.id) {
+                Some(view) => vec![
+                    o.name,
+                    view.refresh_mode,
+                    view.last_refreshed_at.format(TIMESTAMP_FORMAT).to_string(),
+                ],
+                None => vec![o.name, String::new(), String::new()],
+            })
+            .collect();
+        Ok(ResultSet::new(
+            &["Name", "Refresh mode", "Last refresh"],
+            rows,
+        ))
+    }
+
+    /// Describe a table, view, sequence or type in the current database, including the views
+    /// depending on it and, for a view, the objects it depends on.
+    pub async fn describe(&self, name: &str) -> Result<ResultSet> {
+        let object_name = ast::ObjectName(name.split('.').map(ast::Ident::new).collect());
 
         let txn = self.meta.begin().await?;
 
-        let table = MyTable::find()
-            .inner_join(MyObject) // so that we can filter by columns from `my_object`
-            .filter(
-                (my_object::Column::DatabaseId.eq(db_id))
-                    .and(my_object::Column::Type.eq("table"))
-                    .and(my_object::Column::Name.eq(table_name)),
+        let (_, object) = self
+            .catalog
+            .resolve_object(
+                &txn,
+                self.scope(),
+                &object_name,
+                &["table", "view", "materialized_view", "sequence", "type"],
             )
-            .one(&txn)
             .await?
-            .context("table not found")?;
-
-        // Why not eager loading like `alter_table`? Just for demo purpose.
-        let columns = table.find_related(MyColumn).all(&txn).await?;
+            .with_context(|| NotFound::new("object", name))?;
+
+        let mut result = match object.r#type.as_str() {
+            "table" | "materialized_view" => {
+                let columns = self.catalog.columns(&txn, &object).await?;
+                let columns = with_type_names(&txn, columns).await?;
+
+                // Identity columns take their values from the sequence they own.
+                let mut sequences = MySequence::find()
+                    .filter(
+                        my_sequence::Column::OwnedByColumnId.is_in(columns.iter().map(|c| c.id)),
+                    )
+                    .find_also_related(MyObject)
+                    .all(&txn)
+                    .await?
+                    .into_iter()
+                    .filter_map(|(s, o)| Some((s.owned_by_column_id?, o?.name)))
+                    .collect::<BTreeMap<_, _>>();
+
+                let rows = columns
+                    .into_iter()
+                    .map(|c| {
+                        let default = match sequences.remove(&c.id) {
+                            Some(sequence) => format!("nextval('{sequence}')"),
+                            None => String::new(),
+                        };
+                        vec![
+                            c.name,
+                            c.data_type,
+                            if c.is_primary_key { "PRI" } else { "" }.to_owned(),
+                            if c.is_nullable { "" } else { "NOT NULL" }.to_owned(),
+                            default,
+                        ]
+                    })
+                    .collect();
+                let mut result =
+                    ResultSet::new(&["Column", "Type", "Key", "Nullable", "Default"], rows);
+                if let Some(view) = MyMaterializedView::find_by_id(object.id).one(&txn).await? {
+                    let definition = MyView::find_by_id(object.id)
+                        .one(&txn)
+                        .await?
+                        .context("view definition not found")?
+                        .definition;
+                    let refreshed_at = view.last_refreshed_at.format(TIMESTAMP_FORMAT);
+                    result.footer.push(format!("Definition: {definition}"));
+                    result.footer.push(format!(
+                        "Refresh mode: {}, last refreshed at {refreshed_at}",
+                        view.refresh_mode
+                    ));
+                }
+                result
+            }
+            "type" => user_type::describe(&txn, &object).await?,
+            "sequence" => {
+                let sequence = MySequence::find_by_id(object.id)
+                    .one(&txn)
+                    .await?
+                    .context("sequence settings not found")?;
+                let row = vec![
+                    sequence.data_type,
+                    sequence.start_value.to_string(),
+                    sequence.min_value.to_string(),
+                    sequence.max_value.to_string(),
+                    sequence.increment.to_string(),
+                    if sequence.cycle { "yes" } else { "no" }.to_owned(),
+                    sequence.current_value.to_string(),
+                ];
+                let mut result = ResultSet::new(
+                    &[
+                        "Type",
+                        "Start",
+                        "Minimum",
+                        "Maximum",
+                        "Increment",
+                        "Cycles?",
+                        "Current",
+                    ],
+                    vec![row],
+                );
+                if let Some(owner) = sequence::owner(&txn, object.id).await? {
+                    result.footer.push(format!("Owned by: {owner}"));
+                }
+                result
+            }
+            _ => {
+                let view = MyView::find_by_id(object.id)
+                    .one(&txn)
+                    .await?
+                    .context("view definition not found")?;
+                let dependencies = MyViewDependency::find()
+                    .filter(my_view_dependency::Column::ViewId.eq(object.id))
+                    .all(&txn)
+                    .await?
+                    .into_iter()
+                    .map(|d| d.dependent_object_id);
+                let dependencies = object_names(&txn, dependencies).await?;
+
+                ResultSet::new(
+                    &["Definition", "Depends on"],
+                    vec![vec![view.definition, dependencies.join(", ")]],
+                )
+            }
+        };
 
-        for column in columns {
-            println!(
-                "{}\t{}\t{}",
-                column.name,
-                column.data_type,
-                if column.is_primary_key { "PRI" } else { "" }
-            );
+        let dependents = MyViewDependency::find()
+            .filter(my_view_dependency::Column::DependentObjectId.eq(object.id))
+            .all(&txn)
+            .await?
+            .into_iter()
+            .map(|d| d.view_id);
+        let dependents = object_names(&txn, dependents).await?;
+        if !dependents.is_empty() {
+            result
+                .footer
+                .push(format!("Referenced by: {}", dependents.join(", ")));
         }
 
-        Ok(())
+        Ok(result)
+    }
+
+    async fn explain_table(&mut self, table_name: ast::ObjectName) -> Result<ResultSet> {
+        let txn = self.meta.begin().await?;
+
+        let (_, table) = self
+            .catalog
+            .resolve_object(
+                &txn,
+                self.scope(),
+                &table_name,
+                &["table", "materialized_view"],
+            )
+            .await?
+            .with_context(|| NotFound::new("table", table_name.to_string()))?;
+
+        let columns = self.catalog.columns(&txn, &table).await?;
+        let columns = with_type_names(&txn, columns).await?;
+
+        let rows = columns
+            .into_iter()
+            .map(|column| {
+                vec![
+                    column.name,
+                    column.data_type,
+                    if column.is_primary_key { "PRI" } else { "" }.to_owned(),
+                ]
+            })
+            .collect();
+
+        Ok(ResultSet::new(&["Field", "Type", "Key"], rows))
     }
 
     async fn alter_table(
@@ -216,138 +982,1356 @@ impl Session {
         table_name: ast::ObjectName,
         operations: Vec<ast::AlterTableOperation>,
     ) -> Result<()> {
-        let db_id = self.current_db_id()?;
-
-        let table_name = &table_name.0[0].value;
-
         let txn = self.meta.begin().await?;
 
-        let (table, columns) = MyTable::find()
-            .inner_join(MyObject)
-            .filter(
-                (my_object::Column::DatabaseId.eq(db_id))
-                    .and(my_object::Column::Type.eq("table"))
-                    .and(my_object::Column::Name.eq(table_name)),
-            )
+        // Read the version first, so that any change to the columns read below bumps it. Objects
+        // may be cached, hence not read from the metastore.
+        let candidates = self
+            .catalog
+            .candidates(&txn, self.scope(), &table_name)
+            .await?;
+        let mut found = None;
+        for schema in candidates.schemas {
+            let object = MyObject::find()
+                .filter(
+                    (my_object::Column::SchemaId.eq(schema.id))
+                        .and(my_object::Column::Type.eq("table"))
+                        .and(my_object::Column::Name.eq(&candidates.name)),
+                )
+                .one(&txn)
+                .await?;
+            if let Some(object) = object {
+                found = Some((schema, object));
+                break;
+            }
+        }
+        let (schema, object) =
+            found.with_context(|| NotFound::new("table", table_name.to_string()))?;
+        let db = candidates.db;
+        let table_name = &candidates.name;
+
+        let (table, mut columns) = MyTable::find_by_id(object.id)
             .find_with_related(MyColumn) // eager loading, compared to `find_related` in `explain_table`
             .all(&txn)
             .await?
             .into_iter()
             .next()
-            .context("table not found")?;
+            .with_context(|| NotFound::new("table", table_name))?;
+
+        // Published once committed.
+        let mut events = Vec::new();
+        let (db_name, schema_name, table_name) = (&db.name, &schema.name, table_name.to_owned());
 
+        // `columns` is kept current, as operations may refer to columns added or dropped by the
+        // previous ones.
         for op in operations {
             use ast::AlterTableOperation::*;
 
             // TODO: inefficient to manipulate columns one by one
             match op {
                 AddColumn { column_def, .. } => {
-                    let column = my_column::ActiveModel::from_ast(column_def, table.object_id);
-                    MyColumn::insert(column).exec(&txn).await?;
+                    let identity = sequence::identity(&column_def);
+                    let mut column =
+                        my_column::ActiveModel::from_ast(column_def.clone(), table.object_id);
+                    self.set_user_type(&txn, &db, &mut column, &column_def.data_type)
+                        .await?;
+                    let column = MyColumn::insert(column)
+                        .exec_with_returning(&txn)
+                        .await
+                        .map_err(already_exists("column", &column_def.name.value))?;
+                    events.push(CatalogEvent::ColumnAdded {
+                        db: db_name.clone(),
+                        schema: schema_name.clone(),
+                        table: table_name.clone(),
+                        column: column_def,
+                    });
+
+                    if let Some(options) = identity {
+                        let sequence =
+                            sequence::create_owned(&txn, &schema, &table_name, &column, &options)
+                                .await?;
+                        events.push(CatalogEvent::SequenceCreated {
+                            db: db_name.clone(),
+                            schema: schema_name.clone(),
+                            name: sequence.name,
+                        });
+                    }
+                    columns.push(column);
                 }
                 DropColumn { column_name, .. } => {
-                    let column = columns
+                    let index = columns
                         .iter()
+                        .position(|c| c.name == column_name.value)
+                        .with_context(|| NotFound::new("column", &column_name.value))?;
+                    let column = columns.remove(index);
+
+                    for sequence in sequence::drop_owned(&txn, [column.id]).await? {
+                        events.push(CatalogEvent::SequenceDropped {
+                            db: db_name.clone(),
+                            schema: schema_name.clone(),
+                            name: sequence.name,
+                        });
+                    }
+                    let deleted = MyColumn::delete(column.into_active_model())
+                        .exec(&txn)
+                        .await?;
+                    if deleted.rows_affected == 0 {
+                        return Err(NotFound::new("column", &column_name.value).into());
+                    }
+                    events.push(CatalogEvent::ColumnDropped {
+                        db: db_name.clone(),
+                        schema: schema_name.clone(),
+                        table: table_name.clone(),
+                        column: column_name.value,
+                    });
+                }
+                AlterColumn { column_name, op } => {
+                    let current = columns
+                        .iter_mut()
                         .find(|c| c.name == column_name.value)
-                        .context("column not found")?
-                        .clone()
-                        .into_active_model();
-
-                    MyColumn::delete(column).exec(&txn).await?;
+                        .with_context(|| NotFound::new("column", &column_name.value))?;
+                    let is_primary_key = current.is_primary_key;
+                    let mut column = current.clone().into_active_model();
+
+                    match op {
+                        ast::AlterColumnOperation::SetNotNull => column.is_nullable = Set(false),
+                        ast::AlterColumnOperation::DropNotNull => {
+                            if is_primary_key {
+                                bail!(
+                                    "primary key column `{}` cannot be nullable",
+                                    column_name.value
+                                );
+                            }
+                            column.is_nullable = Set(true);
+                        }
+                        ast::AlterColumnOperation::SetDataType { data_type, .. } => {
+                            column.data_type = Set(data_type.to_string());
+                            self.set_user_type(&txn, &db, &mut column, &data_type)
+                                .await?;
+                        }
+                        _ => bail!("unimplemented alter column operation"),
+                    }
+
+                    let updated = column.update(&txn).await?;
+                    events.push(CatalogEvent::ColumnAltered {
+                        db: db_name.clone(),
+                        schema: schema_name.clone(),
+                        table: table_name.clone(),
+                        column: updated.to_ast()?,
+                    });
+                    *current = updated;
                 }
                 _ => bail!("unimplemented alter table operation"),
             }
         }
 
+        events.extend(refresh_dependents(&txn, &db, object.id).await?);
+        bump_version(&txn, &object).await?;
+
+        let version = bump_catalog_version(&txn).await?;
         txn.commit().await?;
+        self.catalog.committed(db.id, version, events);
 
         Ok(())
     }
 
+    /// `options` only apply to materialized views.
     async fn create_view(
         &mut self,
         view_name: ast::ObjectName,
         query: Box<ast::Query>,
+        or_replace: bool,
+        materialized: bool,
+        options: Vec<ast::SqlOption>,
     ) -> Result<()> {
-        let db_id = self.current_db_id()?;
-
-        let view_name = &view_name.0[0].value;
+        let refresh_mode = match materialized {
+            true if or_replace => bail!("materialized views cannot be replaced"),
+            true => Some(materialized_view::refresh_mode(&options)?),
+            false => None,
+        };
+        self.create_view_in(self.scope(), &view_name, query, or_replace, refresh_mode)
+            .await
+    }
 
-        let mut references = Vec::new();
-        visit_relations(&query, |r| {
-            references.push(r.0[0].value.to_owned());
+    /// Create the view, or replace its definition if it exists and `or_replace` is set. Names are
+    /// resolved in `scope`, and the references of the query are stored qualified by their schema,
+    /// so that the view doesn't depend on the search path.
+    ///
+    /// With a `refresh_mode`, it's a new materialized view instead.
+    async fn create_view_in(
+        &self,
+        scope: Scope<'_>,
+        view_name: &ast::ObjectName,
+        mut query: Box<ast::Query>,
+        or_replace: bool,
+        refresh_mode: Option<&str>,
+    ) -> Result<()> {
+        // The same object may be referenced several times.
+        let mut references = BTreeSet::new();
+        let _ = visit_relations(&query, |r| {
+            references.insert(r.clone());
             ControlFlow::<()>::Continue(())
         });
+        let references = references.into_iter().collect::<Vec<_>>();
 
         let txn = self.meta.begin().await?;
 
-        let mut reference_ids = Vec::new();
-        for reference in references {
-            let id = MyObject::find()
-                .filter(my_object::Column::Name.eq(reference))
-                .one(&txn)
-                .await?
-                .context("referenced object not found")?
-                .id;
+        let target = self.catalog.resolve_new(&txn, scope, view_name).await?;
+        let (db, view_name) = (&target.db, &target.name);
+        // References are relative to the database of the view.
+        let scope = Scope {
+            db: Some(db),
+            search_path: scope.search_path,
+        };
 
-            reference_ids.push(id);
+        let resolved = self
+            .catalog
+            .resolve_objects(&txn, scope, &references, RELATION_TYPES)
+            .await?;
+        let missing = references
+            .iter()
+            .zip(&resolved)
+            .filter(|(_, r)| r.is_none())
+            .map(|(name, _)| name.to_string())
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            return Err(NotFound::many("referenced object", missing).into());
+        }
+        let resolved = references
+            .into_iter()
+            .zip(resolved.into_iter().flatten())
+            .collect::<BTreeMap<_, _>>();
+        if let Some((name, _)) = resolved.iter().find(|(_, (_, o))| o.database_id != db.id) {
+            bail!("cross-database references are not supported: `{name}`");
         }
+        let referenced = resolved
+            .values()
+            .map(|(_, o)| (o.id, o.clone()))
+            .collect::<BTreeMap<_, _>>();
+
+        let _ = visit_relations_mut(&mut query, |r| {
+            let (schema, _) = &resolved[r];
+            let name = r.0.pop().unwrap();
+            let schema = match r.0.pop() {
+                Some(ident) => ident,
+                None => ast::Ident::new(&schema.name),
+            };
+            *r = ast::ObjectName(vec![schema, name]);
+            ControlFlow::<()>::Continue(())
+        });
 
-        let my_object = my_object::ActiveModel {
-            id: NotSet,
-            name: Set(view_name.to_owned()),
-            r#type: Set("view".to_owned()),
-            database_id: Set(db_id),
+        let existing = if or_replace {
+            MyObject::find()
+                .filter(
+                    (my_object::Column::SchemaId.eq(target.schema.id))
+                        .and(my_object::Column::Type.is_in(["view", "materialized_view"]))
+                        .and(my_object::Column::Name.eq(view_name)),
+                )
+                .one(&txn)
+                .await?
+        } else {
+            None
         };
-        let object_id = my_object.insert(&txn).await?.id;
 
-        let my_view = my_view::ActiveModel {
-            object_id: Set(object_id),
-            definition: Set(query.to_string()),
+        let definition = query.to_string();
+        // Published once committed.
+        let mut events = Vec::new();
+        let object_id = match &existing {
+            Some(object) => {
+                if object.r#type != "view" {
+                    bail!(
+                        "{} `{view_name}` is not a view",
+                        object_kind(&object.r#type)
+                    );
+                }
+
+                // Views depending on this one, directly or not, can't become its dependencies.
+                let mut dependents = vec![object.id];
+                let mut visited = BTreeSet::new();
+                while let Some(id) = dependents.pop() {
+                    if referenced.contains_key(&id) {
+                        bail!("view `{view_name}` cannot depend on itself");
+                    }
+                    if visited.insert(id) {
+                        dependents.extend(
+                            MyViewDependency::find()
+                                .filter(my_view_dependency::Column::DependentObjectId.eq(id))
+                                .all(&txn)
+                                .await?
+                                .into_iter()
+                                .map(|d| d.view_id),
+                        );
+                    }
+                }
+
+                bump_version(&txn, object).await?;
+                my_view::ActiveModel {
+                    object_id: Set(object.id),
+                    definition: Set(definition.clone()),
+                }
+                .update(&txn)
+                .await?;
+                MyViewDependency::delete_many()
+                    .filter(my_view_dependency::Column::ViewId.eq(object.id))
+                    .exec(&txn)
+                    .await?;
+
+                object.id
+            }
+            None => {
+                let object_type = match refresh_mode {
+                    Some(_) => "materialized_view",
+                    None => "view",
+                };
+                let my_object = my_object::ActiveModel {
+                    id: NotSet,
+                    name: Set(view_name.to_owned()),
+                    r#type: Set(object_type.to_owned()),
+                    database_id: Set(db.id),
+                    version: NotSet,
+                    schema_id: Set(target.schema.id),
+                };
+                let object = my_object
+                    .insert(&txn)
+                    .await
+                    .map_err(already_exists(object_kind(object_type), view_name))?;
+
+                let my_view = my_view::ActiveModel {
+                    object_id: Set(object.id),
+                    definition: Set(definition.clone()),
+                };
+                my_view.insert(&txn).await?;
+
+                if let Some(refresh_mode) = refresh_mode {
+                    materialized_view::create(&txn, &object, &query, refresh_mode).await?;
+                }
+
+                object.id
+            }
         };
-        my_view.insert(&txn).await?;
 
-        let my_view_references = reference_ids
-            .into_iter()
-            .map(|table_id| my_view_dependency::ActiveModel {
+        let my_view_references = referenced
+            .keys()
+            .map(|&table_id| my_view_dependency::ActiveModel {
                 view_id: Set(object_id),
                 dependent_object_id: Set(table_id),
             })
             .collect::<Vec<_>>();
-        MyViewDependency::insert_many(my_view_references)
-            .exec(&txn)
+        if !my_view_references.is_empty() {
+            MyViewDependency::insert_many(my_view_references)
+                .exec(&txn)
+                .await?;
+        }
+
+        // The view is based on what the referenced objects are now.
+        for object in referenced.values() {
+            bump_version(&txn, object).await?;
+        }
+
+        let (db_name, name) = (db.name.clone(), view_name.to_owned());
+        let schema = target.schema.name.clone();
+        events.push(match (&existing, refresh_mode) {
+            (Some(_), _) => CatalogEvent::ViewReplaced {
+                db: db_name,
+                schema,
+                name,
+                definition,
+            },
+            (None, Some(_)) => CatalogEvent::MaterializedViewCreated {
+                db: db_name,
+                schema,
+                name,
+                definition,
+            },
+            (None, None) => CatalogEvent::ViewCreated {
+                db: db_name,
+                schema,
+                name,
+                definition,
+            },
+        });
+        // Materialized views reading the replaced view may have other columns now.
+        if existing.is_some() {
+            events.extend(refresh_dependents(&txn, db, object_id).await?);
+        }
+
+        let version = bump_catalog_version(&txn).await?;
+        txn.commit().await?;
+        self.catalog.committed(db.id, version, events);
+
+        Ok(())
+    }
+
+    /// Re-infer the storage columns of the materialized view from its definition, then refresh the
+    /// ones in `auto` mode that read it.
+    async fn refresh_materialized_view(&mut self, name: ast::ObjectName) -> Result<()> {
+        let txn = self.meta.begin().await?;
+
+        let (qualified, object) = self
+            .catalog
+            .resolve_object(&txn, self.scope(), &name, &["materialized_view"])
+            .await?
+            .with_context(|| NotFound::new("materialized view", name.to_string()))?;
+
+        materialized_view::refresh(&txn, &object).await?;
+        bump_version(&txn, &object).await?;
+
+        let mut events = vec![CatalogEvent::MaterializedViewRefreshed {
+            db: qualified.db.name.clone(),
+            schema: qualified.schema.name,
+            name: object.name,
+        }];
+        events.extend(refresh_dependents(&txn, &qualified.db, object.id).await?);
+
+        let version = bump_catalog_version(&txn).await?;
+        txn.commit().await?;
+        self.catalog.committed(qualified.db.id, version, events);
+
+        Ok(())
+    }
+
+    /// One row per change, then one per statement applying them.
+    async fn diff_database(&mut self, from: ast::Ident, to: ast::Ident) -> Result<ResultSet> {
+        let txn = self.meta.begin().await?;
+
+        let mut schemas = Vec::new();
+        for db_name in [from, to] {
+            let db = MyDatabase::find()
+                .filter(my_database::Column::Name.eq(&db_name.value))
+                .one(&txn)
+                .await?
+                .with_context(|| NotFound::new("database", &db_name.value))?;
+
+            schemas.push(Schema::load(&txn, db.id).await?);
+        }
+
+        let diff = schemas[0].diff(&schemas[1])?;
+
+        let changes = diff
+            .changes
+            .iter()
+            .map(|change| vec!["change".to_owned(), change.to_string()]);
+        let statements = diff
+            .statements
+            .iter()
+            .map(|stmt| vec!["statement".to_owned(), format!("{stmt};")]);
+        Ok(ResultSet::new(
+            &["Kind", "Detail"],
+            changes.chain(statements).collect(),
+        ))
+    }
+
+    /// The source is returned as a single value, unless it's written to `path`.
+    async fn generate_entity(
+        &mut self,
+        table_name: ast::ObjectName,
+        path: Option<String>,
+    ) -> Result<Option<ResultSet>> {
+        let (name, table) = self
+            .catalog
+            .resolve_object(&self.meta, self.scope(), &table_name, &["table"])
+            .await?
+            .with_context(|| NotFound::new("table", table_name.to_string()))?;
+
+        let columns = self.catalog.columns(&self.meta, &table).await?;
+
+        let source = codegen::generate_entity(&name.name, &columns)?;
+
+        match path {
+            Some(path) => {
+                std::fs::write(&path, source)
+                    .with_context(|| format!("failed to write entity to `{path}`"))?;
+                Ok(None)
+            }
+            None => Ok(Some(ResultSet::new(&["Entity"], vec![vec![source]]))),
+        }
+    }
+
+    /// One row per object, imported or skipped, and per part of a table that's ignored.
+    async fn import_schema(&mut self, path: String, db_name: ast::Ident) -> Result<ResultSet> {
+        let objects = import::read_sqlite_schema(&path).await?;
+
+        let db = match self
+            .catalog
+            .find_database(&self.meta, &db_name.value)
+            .await?
+        {
+            Some(db) => db,
+            None => self.insert_database(&db_name.value).await?,
+        };
+
+        // Objects are created in the search path of the session, within the database.
+        let scope = Scope {
+            db: Some(&db),
+            search_path: &self.search_path,
+        };
+        let mut rows = Vec::new();
+        let mut report = |object: String, status: &str, detail: String| {
+            rows.push(vec![object, status.to_owned(), detail]);
+        };
+        let mut views = Vec::new();
+
+        for object in objects {
+            match object {
+                SchemaObject::Table {
+                    name,
+                    columns,
+                    ignored,
+                } => {
+                    let object_name = ast::ObjectName(vec![ast::Ident::new(&name)]);
+                    let created = match self
+                        .catalog
+                        .resolve_new(&self.meta, scope, &object_name)
+                        .await
+                    {
+                        Ok(target) => self.create_table_in(&target, columns).await,
+                        Err(e) => Err(e),
+                    };
+                    match created {
+                        Ok(()) => {
+                            report(format!("table `{name}`"), "imported", String::new());
+                            for ignored in ignored {
+                                report(format!("table `{name}`"), "ignored", ignored);
+                            }
+                        }
+                        Err(e) => report(format!("table `{name}`"), "skipped", format!("{e:#}")),
+                    }
+                }
+                // Views may depend on each other, import them after all tables.
+                SchemaObject::View { name, query } => views.push((name, query)),
+                SchemaObject::Unsupported {
+                    r#type,
+                    name,
+                    reason,
+                } => report(format!("{type} `{name}`"), "skipped", reason),
+            }
+        }
+
+        // Views are stored in the order of creation, but a view may have been recreated after the
+        // ones depending on it. Retry until no more progress can be made.
+        loop {
+            let pending = views.len();
+            let mut failed = Vec::new();
+            for (name, query) in views {
+                let object_name = ast::ObjectName(vec![ast::Ident::new(&name)]);
+                match self
+                    .create_view_in(scope, &object_name, query.clone(), false, None)
+                    .await
+                {
+                    Ok(()) => report(format!("view `{name}`"), "imported", String::new()),
+                    Err(e) => failed.push((name, query, e)),
+                }
+            }
+
+            if failed.len() == pending {
+                for (name, _, e) in failed {
+                    report(format!("view `{name}`"), "skipped", format!("{e:#}"));
+                }
+                break;
+            }
+            views = failed
+                .into_iter()
+                .map(|(name, query, _)| (name, query))
+                .collect();
+        }
+
+        let imported = rows.iter().filter(|r| r[1] == "imported").count();
+        let skipped = rows.iter().filter(|r| r[1] == "skipped").count();
+        let mut result = ResultSet::new(&["Object", "Status", "Detail"], rows);
+        result.footer.push(format!(
+            "imported {imported} objects into `{}`, skipped {skipped}",
+            db.name
+        ));
+        Ok(result)
+    }
+
+    /// `OWNED BY` is not supported, as a sequence is owned by the identity column it's created
+    /// with.
+    async fn create_sequence(
+        &mut self,
+        name: ast::ObjectName,
+        data_type: Option<ast::DataType>,
+        options: Vec<ast::SequenceOptions>,
+        owned_by: Option<ast::ObjectName>,
+    ) -> Result<()> {
+        if owned_by.is_some() {
+            bail!("OWNED BY is not supported, declare an identity column instead");
+        }
+        let data_type = match &data_type {
+            Some(data_type) => sequence::type_name(data_type).with_context(|| {
+                format!("sequence type must be SMALLINT, INT or BIGINT, not {data_type}")
+            })?,
+            None => sequence::DEFAULT_TYPE,
+        };
+
+        let target = self
+            .catalog
+            .resolve_new(&self.meta, self.scope(), &name)
             .await?;
 
+        let txn = self.meta.begin().await?;
+        sequence::create(
+            &txn,
+            &target.schema,
+            &target.name,
+            data_type,
+            &options,
+            None,
+        )
+        .await?;
+        let version = bump_catalog_version(&txn).await?;
         txn.commit().await?;
+        self.catalog.committed(
+            target.db.id,
+            version,
+            [CatalogEvent::SequenceCreated {
+                db: target.db.name,
+                schema: target.schema.name,
+                name: target.name,
+            }],
+        );
 
         Ok(())
     }
 
-    async fn drop_object(&mut self, object_type: &str, names: Vec<ast::ObjectName>) -> Result<()> {
-        let db_id = self.current_db_id()?;
+    async fn alter_sequence(
+        &mut self,
+        name: ast::ObjectName,
+        options: Vec<ast::SequenceOptions>,
+        restart: Option<Option<ast::Expr>>,
+    ) -> Result<()> {
+        let txn = self.meta.begin().await?;
+
+        let (qualified, object) = self
+            .catalog
+            .resolve_object(&txn, self.scope(), &name, &["sequence"])
+            .await?
+            .with_context(|| NotFound::new("sequence", name.to_string()))?;
+
+        sequence::alter(
+            &txn,
+            &object,
+            &options,
+            restart.as_ref().map(Option::as_ref),
+        )
+        .await?;
+        bump_version(&txn, &object).await?;
+
+        let version = bump_catalog_version(&txn).await?;
+        txn.commit().await?;
+        self.catalog.committed(
+            qualified.db.id,
+            version,
+            [CatalogEvent::SequenceAltered {
+                db: qualified.db.name,
+                schema: qualified.schema.name,
+                name: object.name,
+            }],
+        );
+
+        Ok(())
+    }
+
+    /// The data type as stored in the catalog: a user-defined type it names is referenced by id,
+    /// and named as seen from anywhere in the database. Other names are kept as they are.
+    ///
+    /// Types are looked up in `db`, the database of the object using them, which must also be the
+    /// one of the type.
+    async fn resolve_type(
+        &self,
+        conn: &impl ConnectionTrait,
+        db: &my_database::Model,
+        data_type: &ast::DataType,
+    ) -> Result<user_type::DataType> {
+        let unresolved = || user_type::DataType {
+            name: data_type.to_string(),
+            user_type_id: None,
+        };
+        let ast::DataType::Custom(name, modifiers) = data_type else {
+            return Ok(unresolved());
+        };
+        if !modifiers.is_empty() {
+            return Ok(unresolved());
+        }
+        let scope = Scope {
+            db: Some(db),
+            search_path: &self.search_path,
+        };
+        let Some((qualified, object)) = self
+            .catalog
+            .resolve_object(conn, scope, name, &["type"])
+            .await?
+        else {
+            return Ok(unresolved());
+        };
+        if qualified.db.id != db.id {
+            bail!("type `{name}` is in another database");
+        }
+
+        Ok(user_type::DataType {
+            name: display_name(&qualified.schema.name, &object.name),
+            user_type_id: Some(object.id),
+        })
+    }
+
+    async fn set_user_type(
+        &self,
+        conn: &impl ConnectionTrait,
+        db: &my_database::Model,
+        column: &mut my_column::ActiveModel,
+        data_type: &ast::DataType,
+    ) -> Result<()> {
+        let data_type = self.resolve_type(conn, db, data_type).await?;
+        if data_type.user_type_id.is_some() {
+            column.data_type = Set(data_type.name);
+        }
+        column.user_type_id = Set(data_type.user_type_id);
+        Ok(())
+    }
+
+    async fn create_type(
+        &mut self,
+        name: ast::ObjectName,
+        representation: TypeRepresentation,
+    ) -> Result<()> {
+        let target = self
+            .catalog
+            .resolve_new(&self.meta, self.scope(), &name)
+            .await?;
+
+        let txn = self.meta.begin().await?;
+        match representation {
+            TypeRepresentation::Enum(labels) => {
+                user_type::create_enum(&txn, &target.schema, &target.name, &labels).await?;
+            }
+            TypeRepresentation::Composite(attributes) => {
+                let mut resolved = Vec::new();
+                for attribute in attributes {
+                    let data_type = self
+                        .resolve_type(&txn, &target.db, &attribute.data_type)
+                        .await?;
+                    resolved.push((attribute.name.value, data_type));
+                }
+                user_type::create_composite(&txn, &target.schema, &target.name, resolved).await?;
+            }
+        }
+        self.type_created(txn, target).await
+    }
+
+    async fn create_domain(
+        &mut self,
+        name: ast::ObjectName,
+        data_type: ast::DataType,
+        default: Option<ast::Expr>,
+        not_null: bool,
+        checks: Vec<ast::Expr>,
+    ) -> Result<()> {
+        let target = self
+            .catalog
+            .resolve_new(&self.meta, self.scope(), &name)
+            .await?;
 
         let txn = self.meta.begin().await?;
+        let base_type = self.resolve_type(&txn, &target.db, &data_type).await?;
+        user_type::create_domain(
+            &txn,
+            &target.schema,
+            &target.name,
+            base_type,
+            not_null,
+            default.as_ref(),
+            &checks,
+        )
+        .await?;
+        self.type_created(txn, target).await
+    }
+
+    async fn type_created(&self, txn: DatabaseTransaction, target: QualifiedName) -> Result<()> {
+        let version = bump_catalog_version(&txn).await?;
+        txn.commit().await?;
+        self.catalog.committed(
+            target.db.id,
+            version,
+            [CatalogEvent::TypeCreated {
+                db: target.db.name,
+                schema: target.schema.name,
+                name: target.name,
+            }],
+        );
+        Ok(())
+    }
 
+    /// `DROP DOMAIN` only drops domains, while `DROP TYPE` drops any type, like in Postgres.
+    async fn drop_type(&mut self, names: Vec<ast::ObjectName>, domain: bool) -> Result<()> {
+        self.drop_objects("type", names, domain).await
+    }
+
+    async fn drop_object(
+        &mut self,
+        object_type: &'static str,
+        names: Vec<ast::ObjectName>,
+    ) -> Result<()> {
+        self.drop_objects(object_type, names, false).await
+    }
+
+    /// `only_domains` restricts dropping types to domains.
+    async fn drop_objects(
+        &mut self,
+        object_type: &'static str,
+        names: Vec<ast::ObjectName>,
+        only_domains: bool,
+    ) -> Result<()> {
+        let txn = self.meta.begin().await?;
+
+        // Names may be qualified by other databases.
+        let mut events = BTreeMap::<i32, Vec<_>>::new();
         for name in names {
-            let name = &name.0[0].value;
+            let kind = match only_domains {
+                true => user_type::DOMAIN,
+                false => object_kind(object_type),
+            };
+            let (qualified, object) = self
+                .catalog
+                .resolve_object(&txn, self.scope(), &name, &[object_type])
+                .await?
+                .with_context(|| NotFound::new(kind, name.to_string()))?;
+            if only_domains && user_type::find(&txn, object.id).await?.kind != user_type::DOMAIN {
+                bail!("`{name}` is not a domain");
+            }
+
+            // Constraints would restrict the deletion anyway, but without telling why.
+            let dependents = MyViewDependency::find()
+                .filter(my_view_dependency::Column::DependentObjectId.eq(object.id))
+                .all(&txn)
+                .await?
+                .into_iter()
+                .map(|d| d.view_id);
+            let dependents = object_names(&txn, dependents).await?;
+            if !dependents.is_empty() {
+                let kind = object_kind(object_type);
+                return Err(InUse::new(kind, name.to_string(), dependents).into());
+            }
 
-            // Constraints will help us restrict or cascade the deletion.
+            // The sequence of an identity column goes with it, and those of a table with it.
+            let mut owned = Vec::new();
+            match object_type {
+                "type" => {
+                    let dependents = user_type::dependents(&txn, [object.id]).await?;
+                    if !dependents.is_empty() {
+                        let dependents = dependents.into_iter().map(|(_, n)| n).collect();
+                        return Err(InUse::new("type", name.to_string(), dependents).into());
+                    }
+                }
+                "sequence" => {
+                    if let Some(owner) = sequence::owner(&txn, object.id).await? {
+                        return Err(InUse::new("sequence", name.to_string(), vec![owner]).into());
+                    }
+                }
+                "table" => {
+                    let columns = MyColumn::find()
+                        .filter(my_column::Column::TableId.eq(object.id))
+                        .all(&txn)
+                        .await?;
+                    owned = sequence::drop_owned(&txn, columns.iter().map(|c| c.id)).await?;
+                }
+                _ => {}
+            }
+
+            // Columns, view definitions, etc. are deleted in cascade.
             let res = MyObject::delete_many()
                 .filter(
-                    (my_object::Column::Name.eq(name.as_str()))
-                        .and(my_object::Column::Type.eq(object_type))
-                        .and(my_object::Column::DatabaseId.eq(db_id)),
+                    (my_object::Column::Id.eq(object.id))
+                        .and(my_object::Column::Version.eq(object.version)),
                 )
                 .exec(&txn)
                 .await?;
-
             if res.rows_affected == 0 {
-                bail!("{object_type} `{name}` not found");
+                return Err(concurrent_modification(&object));
             }
+
+            let (db, schema, name) = (qualified.db.name, qualified.schema.name, object.name);
+            let events = events.entry(qualified.db.id).or_default();
+            // Owned sequences are in the schema of their table.
+            for sequence in owned {
+                events.push(CatalogEvent::SequenceDropped {
+                    db: db.clone(),
+                    schema: schema.clone(),
+                    name: sequence.name,
+                });
+            }
+            events.push(match object_type {
+                "view" => CatalogEvent::ViewDropped { db, schema, name },
+                "materialized_view" => CatalogEvent::MaterializedViewDropped { db, schema, name },
+                "sequence" => CatalogEvent::SequenceDropped { db, schema, name },
+                "type" => CatalogEvent::TypeDropped { db, schema, name },
+                _ => CatalogEvent::TableDropped { db, schema, name },
+            });
         }
 
+        let version = bump_catalog_version(&txn).await?;
         txn.commit().await?;
+        for (db_id, events) in events {
+            self.catalog.committed(db_id, version, events);
+        }
 
         Ok(())
     }
 }
+
+/// Names of the objects with the given ids as shown to users, sorted.
+pub async fn object_names(
+    conn: &impl ConnectionTrait,
+    ids: impl IntoIterator<Item = i32>,
+) -> Result<Vec<String>> {
+    let objects = MyObject::find()
+        .filter(my_object::Column::Id.is_in(ids))
+        .find_also_related(MySchema)
+        .all(conn)
+        .await?;
+
+    let mut names = objects
+        .into_iter()
+        .map(|(o, s)| match s {
+            Some(s) => display_name(&s.name, &o.name),
+            None => o.name,
+        })
+        .collect::<Vec<_>>();
+    names.sort();
+    Ok(names)
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.catalog.unregister_session(self.id);
+    }
+}
+
+/// The columns, typed with the names of the user-defined types they reference by id.
+async fn with_type_names(
+    conn: &impl ConnectionTrait,
+    mut columns: Vec<my_column::Model>,
+) -> Result<Vec<my_column::Model>> {
+    let names = user_type::names(conn, columns.iter().filter_map(|c| c.user_type_id)).await?;
+    for column in &mut columns {
+        if let Some(name) = column.user_type_id.and_then(|id| names.get(&id)) {
+            column.data_type = name.clone();
+        }
+    }
+    Ok(columns)
+}
+
+/// Whether the statement only reads the catalog.
+fn is_read_only(stmt: &Statement) -> bool {
+    use ast::Statement::*;
+
+    match stmt {
+        Statement::Sql(stmt) => matches!(
+            stmt,
+            Use { .. }
+                | SetVariable { .. }
+                | ShowVariable { .. }
+                | ShowTables { .. }
+                | ExplainTable { .. }
+                | Query(_)
+        ),
+        Statement::DiffDatabase { .. }
+        | Statement::GenerateEntity { .. }
+        | Statement::ShowSessions
+        | Statement::ShowCache
+        | Statement::ShowMaterializedViews => true,
+        Statement::ImportSchema { .. }
+        | Statement::RefreshMaterializedView { .. }
+        | Statement::DropMaterializedView { .. }
+        | Statement::AlterSequence { .. }
+        | Statement::CreateType { .. }
+        | Statement::CreateDomain { .. }
+        | Statement::DropType { .. } => false,
+        Statement::CheckCatalog { repair } => !repair,
+    }
+}
+
+fn is_search_path(variable: &[ast::Ident]) -> bool {
+    matches!(variable, [name] if name.value.eq_ignore_ascii_case("search_path"))
+}
+
+/// Refresh the materialized views in `auto` mode that read the object, directly or through other
+/// views, after it changed. Returns the events to publish.
+///
+/// Each view is refreshed once, after all the views it reads. Fails if views depend on each other
+/// in a cycle, which `CHECK CATALOG` reports.
+async fn refresh_dependents(
+    conn: &impl ConnectionTrait,
+    db: &my_database::Model,
+    object_id: i32,
+) -> Result<Vec<CatalogEvent>> {
+    // The views reading the object, through views other than materialized ones in `manual` mode,
+    // whose contents don't change.
+    let mut views = BTreeMap::new();
+    let mut readers = BTreeMap::<i32, Vec<i32>>::new();
+    let mut changed = VecDeque::from([object_id]);
+    while let Some(id) = changed.pop_front() {
+        let dependents = MyViewDependency::find()
+            .filter(my_view_dependency::Column::DependentObjectId.eq(id))
+            .all(conn)
+            .await?
+            .into_iter()
+            .map(|d| d.view_id);
+        let dependents = MyObject::find()
+            .filter(my_object::Column::Id.is_in(dependents))
+            .find_also_related(MyMaterializedView)
+            .all(conn)
+            .await?;
+        for (object, view) in dependents {
+            let auto = match view {
+                Some(view) if view.refresh_mode == materialized_view::AUTO => true,
+                Some(_) => continue,
+                None => false,
+            };
+            readers.entry(id).or_default().push(object.id);
+            if object.id != object_id && !views.contains_key(&object.id) {
+                changed.push_back(object.id);
+                views.insert(object.id, (object, auto));
+            }
+        }
+    }
+
+    // Kahn's algorithm, from the object.
+    let mut pending = BTreeMap::<i32, usize>::new();
+    for id in readers.values().flatten() {
+        *pending.entry(*id).or_default() += 1;
+    }
+    let mut order = Vec::new();
+    let mut ready = VecDeque::from([object_id]);
+    while let Some(id) = ready.pop_front() {
+        for reader in readers.get(&id).into_iter().flatten() {
+            let count = pending.get_mut(reader).unwrap();
+            *count -= 1;
+            if *count == 0 && *reader != object_id {
+                ready.push_back(*reader);
+                order.push(*reader);
+            }
+        }
+    }
+    if order.len() < views.len() || pending.contains_key(&object_id) {
+        bail!("views depend on each other in a cycle, run `CHECK CATALOG`");
+    }
+
+    let mut refreshed = Vec::new();
+    for id in order {
+        let (object, auto) = views.remove(&id).unwrap();
+        if auto {
+            materialized_view::refresh(conn, &object)
+                .await
+                .with_context(|| format!("cannot refresh materialized view `{}`", object.name))?;
+            refreshed.push(object);
+        }
+    }
+
+    let mut events = Vec::new();
+    for object in refreshed {
+        bump_version(conn, &object).await?;
+        let schema = MySchema::find_by_id(object.schema_id)
+            .one(conn)
+            .await?
+            .context("schema not found")?;
+        events.push(CatalogEvent::MaterializedViewRefreshed {
+            db: db.name.clone(),
+            schema: schema.name,
+            name: object.name,
+        });
+    }
+    Ok(events)
+}
+
+/// Increment the version of an object, failing if it's not the one read by the session, i.e., the
+/// object has been modified concurrently.
+async fn bump_version(conn: &impl ConnectionTrait, object: &my_object::Model) -> Result<()> {
+    let res = MyObject::update_many()
+        .col_expr(
+            my_object::Column::Version,
+            Expr::col(my_object::Column::Version).add(1),
+        )
+        .filter(
+            (my_object::Column::Id.eq(object.id))
+                .and(my_object::Column::Version.eq(object.version)),
+        )
+        .exec(conn)
+        .await?;
+
+    if res.rows_affected == 0 {
+        return Err(concurrent_modification(object));
+    }
+    Ok(())
+}
+
+/// Increment the version of the whole catalog, returning the new one. See [`crate::cache`].
+async fn bump_catalog_version(conn: &impl ConnectionTrait) -> Result<i32> {
+    MyCatalog::update_many()
+        .col_expr(
+            my_catalog::Column::Version,
+            Expr::col(my_catalog::Column::Version).add(1),
+        )
+        .exec(conn)
+        .await?;
+
+    catalog_version(conn).await
+}
+
+fn concurrent_modification(object: &my_object::Model) -> anyhow::Error {
+    ConcurrentModification {
+        kind: object.r#type.clone(),
+        name: object.name.clone(),
+    }
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+    use futures_util::{FutureExt, Stream, StreamExt};
+    use sea_orm::prelude::DateTimeWithTimeZone;
+
+    use super::*;
+    use crate::metastore;
+
+    #[tokio::test]
+    async fn test_object_version() {
+        let catalog = Catalog::new(metastore::connect(metastore::MEMORY_URL).await.unwrap());
+        let mut session = catalog.session("test");
+        session
+            .execute("CREATE DATABASE shop; USE shop; CREATE TABLE users (id INT PRIMARY KEY)")
+            .await
+            .unwrap();
+
+        let find = |name: &'static str| {
+            MyObject::find()
+                .filter(my_object::Column::Name.eq(name))
+                .one(catalog.meta())
+        };
+        let stale = find("users").await.unwrap().unwrap();
+
+        session
+            .execute(
+                "ALTER TABLE users ADD COLUMN name TEXT;
+                 CREATE VIEW names AS SELECT name FROM users JOIN users AS u2 ON users.id = u2.id",
+            )
+            .await
+            .unwrap();
+        let users = find("users").await.unwrap().unwrap();
+        let names = find("names").await.unwrap().unwrap();
+        assert_eq!((stale.version, users.version, names.version), (1, 3, 1));
+
+        // As if another session altered the table after this one read it.
+        let e = bump_version(catalog.meta(), &stale).await.unwrap_err();
+        expect!["concurrent modification of table `users`, please retry"].assert_eq(&e.to_string());
+        bump_version(catalog.meta(), &users).await.unwrap();
+    }
+
+    /// The source is returned rather than printed, so that it reaches network clients.
+    #[tokio::test]
+    async fn test_generate_entity() {
+        let catalog = Catalog::new(metastore::connect(metastore::MEMORY_URL).await.unwrap());
+        let mut session = catalog.session("test");
+        session
+            .execute("CREATE DATABASE shop; USE shop; CREATE TABLE users (id INT PRIMARY KEY)")
+            .await
+            .unwrap();
+
+        let stmt = parser::parse("GENERATE ENTITY users").unwrap().remove(0);
+        let result = session.handle(stmt).await.unwrap().unwrap();
+        assert_eq!(result.columns, ["Entity"]);
+        let [row] = result.rows.as_slice() else {
+            panic!("expected one row: {result:?}");
+        };
+        assert!(
+            row[0].contains(r#"#[sea_orm(table_name = "users")]"#),
+            "{}",
+            row[0]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_view_references() {
+        let catalog = Catalog::new(metastore::connect(metastore::MEMORY_URL).await.unwrap());
+        let mut session = catalog.session("test");
+        session
+            .execute(
+                "CREATE DATABASE other; USE other; CREATE TABLE orders (id INT PRIMARY KEY);
+                 CREATE DATABASE shop; USE shop; CREATE TABLE users (id INT PRIMARY KEY)",
+            )
+            .await
+            .unwrap();
+
+        // `orders` is in another database.
+        let e = session
+            .execute(
+                "CREATE VIEW v AS SELECT * FROM users, orders, items WHERE orders.user_id IN \
+                 (SELECT id FROM users)",
+            )
+            .await
+            .unwrap_err();
+        expect!["referenced objects `items`, `orders` not found"]
+            .assert_eq(&e.root_cause().to_string());
+        assert!(e.root_cause().downcast_ref::<NotFound>().is_some());
+
+        let e = session
+            .execute("CREATE VIEW v AS SELECT * FROM items")
+            .await
+            .unwrap_err();
+        expect!["referenced object `items` not found"].assert_eq(&e.root_cause().to_string());
+    }
+
+    #[tokio::test]
+    async fn test_show_materialized_views() {
+        let catalog = Catalog::new(metastore::connect(metastore::MEMORY_URL).await.unwrap());
+        let mut session = catalog.session("test");
+        session
+            .execute(
+                "CREATE DATABASE shop; USE shop; CREATE TABLE users (id INT PRIMARY KEY);
+                 CREATE MATERIALIZED VIEW ids AS SELECT id FROM users;
+                 CREATE MATERIALIZED VIEW auto_ids WITH (refresh_mode = 'auto') AS SELECT * FROM ids",
+            )
+            .await
+            .unwrap();
+
+        // The rows without the times, and the times.
+        async fn show(session: &mut Session) -> (Vec<Vec<String>>, Vec<DateTimeWithTimeZone>) {
+            let stmt = parser::parse("SHOW MATERIALIZED VIEWS").unwrap().remove(0);
+            let mut result = session.handle(stmt).await.unwrap().unwrap();
+            let refreshed_at = result
+                .rows
+                .iter_mut()
+                .map(|row| {
+                    let time = row.pop().unwrap();
+                    DateTimeWithTimeZone::parse_from_str(&time, TIMESTAMP_FORMAT).unwrap()
+                })
+                .collect();
+            (result.rows, refreshed_at)
+        }
+        let (rows, _) = show(&mut session).await;
+        expect![[r#"[["auto_ids", "auto"], ["ids", "manual"]]"#]].assert_eq(&format!("{rows:?}"));
+
+        // Refreshing `ids` refreshes `auto_ids`, which reads it.
+        let mut events = std::pin::pin!(catalog.subscribe());
+        let stmt = parser::parse("REFRESH MATERIALIZED VIEW ids")
+            .unwrap()
+            .remove(0);
+        session.handle(stmt).await.unwrap();
+        expect![[r#"
+            materialized view `shop.public.ids` refreshed
+            materialized view `shop.public.auto_ids` refreshed
+        "#]]
+        .assert_eq(&drain(&mut events));
+    }
+
+    fn drain(events: &mut (impl Stream<Item = CatalogEvent> + Unpin)) -> String {
+        let mut out = String::new();
+        while let Some(Some(event)) = events.next().now_or_never() {
+            out += &format!("{event}\n");
+        }
+        out
+    }
+
+    /// A view reading the changed table in several ways is refreshed once, after the views it
+    /// reads.
+    #[tokio::test]
+    async fn test_refresh_dependents_diamond() {
+        let catalog = Catalog::new(metastore::connect(metastore::MEMORY_URL).await.unwrap());
+        let mut session = catalog.session("test");
+        session
+            .execute(
+                "CREATE DATABASE shop; USE shop; CREATE TABLE users (id INT PRIMARY KEY);
+                 CREATE MATERIALIZED VIEW c WITH (refresh_mode = 'auto') AS
+                     SELECT u1.id FROM users AS u1 JOIN users AS u2 ON u1.id = u2.id;
+                 CREATE VIEW a AS SELECT id FROM users;
+                 CREATE MATERIALIZED VIEW b WITH (refresh_mode = 'auto') AS SELECT id FROM users;
+                 CREATE MATERIALIZED VIEW d WITH (refresh_mode = 'auto') AS
+                     SELECT a.id AS a_id, b.id AS b_id, c.id AS c_id FROM a, b, c",
+            )
+            .await
+            .unwrap();
+
+        let mut events = std::pin::pin!(catalog.subscribe());
+        session
+            .execute("ALTER TABLE users ADD COLUMN name TEXT")
+            .await
+            .unwrap();
+        expect![[r#"
+            column `name TEXT` added to `shop.public.users`
+            materialized view `shop.public.c` refreshed
+            materialized view `shop.public.b` refreshed
+            materialized view `shop.public.d` refreshed
+        "#]]
+        .assert_eq(&drain(&mut events));
+    }
+
+    /// Run with `cargo test --release bench_view_references -- --ignored --nocapture`.
+    #[tokio::test]
+    #[ignore]
+    async fn bench_view_references() {
+        const TABLES: usize = 500;
+
+        let catalog = Catalog::new(metastore::connect(metastore::MEMORY_URL).await.unwrap());
+        let mut session = catalog.session("bench");
+        // Another database first, so that the ids of `shop` and its schema differ.
+        session
+            .execute("CREATE DATABASE other; CREATE DATABASE shop; USE shop")
+            .await
+            .unwrap();
+        for i in 0..TABLES {
+            let sql = format!("CREATE TABLE t{i} (id INT PRIMARY KEY)");
+            session.execute(&sql).await.unwrap();
+        }
+        let schema_id = MySchema::find()
+            .filter(my_schema::Column::DatabaseId.eq(session.current_db_id().unwrap()))
+            .filter(my_schema::Column::Name.eq("public"))
+            .one(catalog.meta())
+            .await
+            .unwrap()
+            .unwrap()
+            .id;
+        let names = (0..TABLES).map(|i| format!("t{i}")).collect::<Vec<_>>();
+        let types = &["table", "view"];
+
+        let start = std::time::Instant::now();
+        for name in &names {
+            catalog
+                .find_object(catalog.meta(), schema_id, types, name)
+                .await
+                .unwrap()
+                .unwrap();
+        }
+        let one_by_one = start.elapsed();
+
+        let start = std::time::Instant::now();
+        let objects = catalog
+            .find_objects(catalog.meta(), schema_id, types, &names)
+            .await
+            .unwrap();
+        let batched = start.elapsed();
+        assert_eq!(objects.len(), TABLES);
+
+        // As `CREATE VIEW` does, along the search path.
+        let references = names
+            .iter()
+            .map(|name| ast::ObjectName(vec![ast::Ident::new(name)]))
+            .collect::<Vec<_>>();
+        let start = std::time::Instant::now();
+        let resolved = catalog
+            .resolve_objects(catalog.meta(), session.scope(), &references, RELATION_TYPES)
+            .await
+            .unwrap();
+        let in_scope = start.elapsed();
+        assert!(resolved.iter().all(Option::is_some));
+
+        let sql = format!("CREATE VIEW v AS SELECT * FROM {}", names.join(", "));
+        let start = std::time::Instant::now();
+        session.execute(&sql).await.unwrap();
+        let create_view = start.elapsed();
+
+        println!("resolving {TABLES} references one by one: {one_by_one:?}");
+        println!("resolving {TABLES} references at once: {batched:?}");
+        println!("resolving {TABLES} references in the scope at once: {in_scope:?}");
+        println!("CREATE VIEW referencing {TABLES} tables: {create_view:?}");
+    }
+}
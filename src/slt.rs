@@ -0,0 +1,113 @@
+//! Runner of the end-to-end tests in `tests/slt`, written in a format similar to
+//! [sqllogictest](https://www.sqlite.org/sqllogictest/doc/trunk/about.wiki).
+//!
+//! Each file runs on a fresh in-memory metastore. Records are separated by blank lines:
+//!
+//! ```text
+//! # A comment.
+//! statement ok
+//! CREATE TABLE users (id INT PRIMARY KEY)
+//!
+//! statement error
+//! DROP TABLE nope
+//! ----
+//! table `nope` not found
+//!
+//! query
+//! SHOW TABLES
+//! ----
+//!  Name
+//! -------
+//!  users
+//! (1 row)
+//! ```
+//!
+//! Files are checked with `expect-test`: run with `UPDATE_EXPECT=1` to rewrite them with the actual
+//! results, then review the diff.
+
+use std::path::{Path, PathBuf};
+
+use expect_test::expect_file;
+
+use crate::catalog::Catalog;
+use crate::output::Format;
+use crate::session::Session;
+use crate::{metastore, parser};
+
+/// Headers of records. Rewritten with the actual outcome, like the results.
+const HEADERS: &[&str] = &["statement ok", "statement error", "query"];
+
+/// Run the script, returning it with the results of the statements in place of the expected
+/// ones.
+async fn run(path: &Path, script: &str) -> String {
+    let meta = metastore::connect(metastore::MEMORY_URL).await.unwrap();
+    let mut session = Catalog::new(meta).session("slt");
+
+    let mut records = Vec::new();
+    for block in script.split("\n\n").filter(|b| !b.trim().is_empty()) {
+        let lines = block.trim_matches('\n').lines().collect::<Vec<_>>();
+        let start = lines.iter().take_while(|l| l.starts_with('#')).count();
+
+        let mut record = lines[..start].join("\n");
+        if let Some(header) = lines.get(start) {
+            assert!(
+                HEADERS.contains(header),
+                "{}: unknown record `{header}`",
+                path.display()
+            );
+            let sql = lines[start + 1..]
+                .iter()
+                .take_while(|l| **l != "----")
+                .copied()
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            if !record.is_empty() {
+                record.push('\n');
+            }
+            record += &execute(&mut session, &sql).await;
+        }
+        records.push(record);
+    }
+
+    records.join("\n\n") + "\n"
+}
+
+/// Execute one statement, rendering the record with its actual result.
+async fn execute(session: &mut Session, sql: &str) -> String {
+    let result = match parser::parse(sql) {
+        Ok(stmts) => {
+            assert_eq!(stmts.len(), 1, "expected one statement: {sql}");
+            let stmt = stmts.into_iter().next().unwrap();
+            session.handle(stmt).await
+        }
+        Err(e) => Err(e.into()),
+    };
+
+    match result {
+        Ok(None) => format!("statement ok\n{sql}"),
+        Ok(Some(rows)) => {
+            let rows = rows.render(Format::Table, false);
+            format!("query\n{sql}\n----\n{}", rows.trim_end())
+        }
+        Err(e) => format!("statement error\n{sql}\n----\n{e:#}"),
+    }
+}
+
+#[tokio::test]
+async fn test_slt() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/slt");
+    let mut paths = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == "slt"))
+        .collect::<Vec<PathBuf>>();
+    paths.sort();
+    assert!(!paths.is_empty(), "no tests in {}", dir.display());
+
+    for path in paths {
+        let script = std::fs::read_to_string(&path).unwrap();
+        let actual = run(&path, &script).await;
+        expect_file![path].assert_eq(&actual);
+    }
+}
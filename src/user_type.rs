@@ -0,0 +1,293 @@
+//! User-defined types, like in Postgres: enums with their labels, composite types with their
+//! attributes, and domains, i.e., a base type with constraints.
+//!
+//! Columns, attributes and domains using a user-defined type reference it by id, and keep its
+//! name in their `data_type` too, so that it reads the same everywhere else. The type can't be
+//! dropped while they exist.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::{bail, Context, Result};
+use sea_orm::ActiveValue::*;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder,
+};
+use sqlparser::ast;
+
+use crate::catalog::display_name;
+use crate::entities::prelude::*;
+use crate::entities::*;
+use crate::error::already_exists;
+use crate::output::ResultSet;
+
+pub const ENUM: &str = "enum";
+pub const COMPOSITE: &str = "composite";
+pub const DOMAIN: &str = "domain";
+
+/// A data type as stored in the catalog, with the user-defined type it names, if any.
+#[derive(Debug, Clone)]
+pub struct DataType {
+    pub name: String,
+    pub user_type_id: Option<i32>,
+}
+
+pub async fn create_enum(
+    conn: &impl ConnectionTrait,
+    schema: &my_schema::Model,
+    name: &str,
+    labels: &[String],
+) -> Result<my_object::Model> {
+    let mut seen = BTreeSet::new();
+    if let Some(label) = labels.iter().find(|l| !seen.insert(*l)) {
+        bail!("enum label `{label}` specified more than once");
+    }
+
+    let object = create(conn, schema, name, ENUM, None).await?;
+    let labels = labels
+        .iter()
+        .enumerate()
+        .map(|(position, label)| my_enum_label::ActiveModel {
+            type_id: Set(object.id),
+            position: Set(position as i32),
+            label: Set(label.clone()),
+        })
+        .collect::<Vec<_>>();
+    if !labels.is_empty() {
+        MyEnumLabel::insert_many(labels).exec(conn).await?;
+    }
+    Ok(object)
+}
+
+pub async fn create_composite(
+    conn: &impl ConnectionTrait,
+    schema: &my_schema::Model,
+    name: &str,
+    attributes: Vec<(String, DataType)>,
+) -> Result<my_object::Model> {
+    let mut seen = BTreeSet::new();
+    if let Some((attribute, _)) = attributes.iter().find(|(a, _)| !seen.insert(a)) {
+        bail!("attribute `{attribute}` specified more than once");
+    }
+
+    let object = create(conn, schema, name, COMPOSITE, None).await?;
+    let attributes = attributes
+        .into_iter()
+        .enumerate()
+        .map(
+            |(position, (name, data_type))| my_type_attribute::ActiveModel {
+                type_id: Set(object.id),
+                position: Set(position as i32),
+                name: Set(name),
+                data_type: Set(data_type.name),
+                user_type_id: Set(data_type.user_type_id),
+            },
+        )
+        .collect::<Vec<_>>();
+    if !attributes.is_empty() {
+        MyTypeAttribute::insert_many(attributes).exec(conn).await?;
+    }
+    Ok(object)
+}
+
+/// Several `CHECK` constraints are stored as one, as they must all hold.
+pub async fn create_domain(
+    conn: &impl ConnectionTrait,
+    schema: &my_schema::Model,
+    name: &str,
+    base_type: DataType,
+    not_null: bool,
+    default: Option<&ast::Expr>,
+    checks: &[ast::Expr],
+) -> Result<my_object::Model> {
+    let check = match checks {
+        [] => None,
+        [check] => Some(check.to_string()),
+        _ => {
+            let checks = checks.iter().map(|c| format!("({c})")).collect::<Vec<_>>();
+            Some(checks.join(" AND "))
+        }
+    };
+    let domain = my_type::Model {
+        object_id: 0,
+        kind: DOMAIN.to_owned(),
+        base_type: Some(base_type.name),
+        base_type_id: base_type.user_type_id,
+        is_nullable: !not_null,
+        default_expr: default.map(|d| d.to_string()),
+        check_expr: check,
+    };
+    create(conn, schema, name, DOMAIN, Some(domain)).await
+}
+
+/// Insert the object of a type, and its `my_type` row, which is `details` for a domain.
+async fn create(
+    conn: &impl ConnectionTrait,
+    schema: &my_schema::Model,
+    name: &str,
+    kind: &str,
+    details: Option<my_type::Model>,
+) -> Result<my_object::Model> {
+    let object = my_object::ActiveModel {
+        id: NotSet,
+        name: Set(name.to_owned()),
+        r#type: Set("type".to_owned()),
+        database_id: Set(schema.database_id),
+        version: NotSet,
+        schema_id: Set(schema.id),
+    }
+    .insert(conn)
+    .await
+    .map_err(already_exists("type", name))?;
+
+    let mut user_type = details.unwrap_or(my_type::Model {
+        object_id: 0,
+        kind: kind.to_owned(),
+        base_type: None,
+        base_type_id: None,
+        is_nullable: true,
+        default_expr: None,
+        check_expr: None,
+    });
+    user_type.object_id = object.id;
+    my_type::ActiveModel::from(user_type)
+        .reset_all()
+        .insert(conn)
+        .await?;
+
+    Ok(object)
+}
+
+/// What uses the types: columns as `<table>.<column>`, attributes as `<type>.<attribute>` and
+/// domains, each with the id of the object it belongs to.
+pub async fn dependents(
+    conn: &impl ConnectionTrait,
+    type_ids: impl IntoIterator<Item = i32>,
+) -> Result<Vec<(i32, String)>> {
+    let type_ids = type_ids.into_iter().collect::<Vec<_>>();
+
+    let mut dependents = Vec::new();
+    let columns = MyColumn::find()
+        .filter(my_column::Column::UserTypeId.is_in(type_ids.clone()))
+        .all(conn)
+        .await?;
+    for column in columns {
+        dependents.push((column.table_id, column.name));
+    }
+    let attributes = MyTypeAttribute::find()
+        .filter(my_type_attribute::Column::UserTypeId.is_in(type_ids.clone()))
+        .all(conn)
+        .await?;
+    for attribute in attributes {
+        dependents.push((attribute.type_id, attribute.name));
+    }
+    let domains = MyType::find()
+        .filter(my_type::Column::BaseTypeId.is_in(type_ids))
+        .all(conn)
+        .await?;
+    for domain in domains {
+        dependents.push((domain.object_id, String::new()));
+    }
+
+    let names = names(conn, dependents.iter().map(|(id, _)| *id)).await?;
+    let mut dependents = dependents
+        .into_iter()
+        .map(|(id, part)| {
+            let name = names.get(&id).cloned().unwrap_or_default();
+            match part.is_empty() {
+                true => (id, name),
+                false => (id, format!("{name}.{part}")),
+            }
+        })
+        .collect::<Vec<_>>();
+    dependents.sort_by(|a, b| a.1.cmp(&b.1));
+    Ok(dependents)
+}
+
+/// Names of the objects, as in `data_type`, by id.
+pub async fn names(
+    conn: &impl ConnectionTrait,
+    ids: impl IntoIterator<Item = i32>,
+) -> Result<BTreeMap<i32, String>> {
+    let objects = MyObject::find()
+        .filter(my_object::Column::Id.is_in(ids))
+        .find_also_related(MySchema)
+        .all(conn)
+        .await?;
+    Ok(objects
+        .into_iter()
+        .map(|(o, s)| {
+            let name = match s {
+                Some(s) => display_name(&s.name, &o.name),
+                None => o.name,
+            };
+            (o.id, name)
+        })
+        .collect())
+}
+
+pub async fn find(conn: &impl ConnectionTrait, object_id: i32) -> Result<my_type::Model> {
+    MyType::find_by_id(object_id)
+        .one(conn)
+        .await?
+        .context("type definition not found")
+}
+
+/// The labels of an enum, the attributes of a composite type, or the constraints of a domain.
+pub async fn describe(conn: &impl ConnectionTrait, object: &my_object::Model) -> Result<ResultSet> {
+    let user_type = find(conn, object.id).await?;
+
+    let mut result = match user_type.kind.as_str() {
+        ENUM => {
+            let labels = MyEnumLabel::find()
+                .filter(my_enum_label::Column::TypeId.eq(object.id))
+                .order_by_asc(my_enum_label::Column::Position)
+                .all(conn)
+                .await?;
+            let rows = labels.into_iter().map(|l| vec![l.label]).collect();
+            ResultSet::new(&["Label"], rows)
+        }
+        COMPOSITE => {
+            let attributes = MyTypeAttribute::find()
+                .filter(my_type_attribute::Column::TypeId.eq(object.id))
+                .order_by_asc(my_type_attribute::Column::Position)
+                .all(conn)
+                .await?;
+            let names = names(conn, attributes.iter().filter_map(|a| a.user_type_id)).await?;
+            let rows = attributes
+                .into_iter()
+                .map(|a| {
+                    let data_type = a.user_type_id.and_then(|id| names.get(&id).cloned());
+                    vec![a.name, data_type.unwrap_or(a.data_type)]
+                })
+                .collect();
+            ResultSet::new(&["Attribute", "Type"], rows)
+        }
+        _ => {
+            let names = names(conn, user_type.base_type_id).await?;
+            let base_type = user_type
+                .base_type_id
+                .and_then(|id| names.get(&id).cloned());
+            let row = vec![
+                base_type.or(user_type.base_type).unwrap_or_default(),
+                if user_type.is_nullable {
+                    ""
+                } else {
+                    "NOT NULL"
+                }
+                .to_owned(),
+                user_type.default_expr.unwrap_or_default(),
+                user_type.check_expr.unwrap_or_default(),
+            ];
+            ResultSet::new(&["Type", "Nullable", "Default", "Check"], vec![row])
+        }
+    };
+    result.footer.push(format!("Kind: {}", user_type.kind));
+
+    let dependents = dependents(conn, [object.id]).await?;
+    if !dependents.is_empty() {
+        let names = dependents.into_iter().map(|(_, n)| n).collect::<Vec<_>>();
+        result.footer.push(format!("Used by: {}", names.join(", ")));
+    }
+
+    Ok(result)
+}